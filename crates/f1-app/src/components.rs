@@ -1,6 +1,9 @@
 //! ECS Components for the F1 visualization.
 
 use bevy::prelude::*;
+use f1_data::LapTrace;
+
+use crate::resources::LapRef;
 
 /// Marker component for a car entity being visualized.
 #[derive(Component)]
@@ -42,6 +45,27 @@ impl Trail {
     }
 }
 
+/// A car replaying a single lap, driven by lap-relative time.
+#[derive(Component)]
+pub struct LapCar {
+    pub trace: LapTrace,
+    /// The lap being replayed, which may be from any loaded session
+    pub lap: LapRef,
+}
+
+/// Ghost overlay for a lap car, comparing it against the reference lap.
+#[derive(Component)]
+pub struct Ghost {
+    /// Whether this is the reference lap the others are compared to
+    pub is_reference: bool,
+    /// Current gap to the reference in seconds (positive = behind)
+    pub gap: Option<f64>,
+}
+
+/// Text label showing a ghost's driver, lap and live gap.
+#[derive(Component)]
+pub struct GhostLabel;
+
 /// Component for telemetry display (speed, gear, throttle, etc.)
 #[derive(Component)]
 pub struct TelemetryDisplay {
@@ -7,7 +7,10 @@ mod plugins;
 mod resources;
 mod systems;
 
-use plugins::{CameraPlugin, ReplayPlugin, UiPlugin};
+use plugins::{
+    CameraPlugin, DrsPlugin, ExportPlugin, GhostPlugin, LapPickerPlugin, OvertakePlugin,
+    RaceHistoryPlugin, ReplayPlugin, StintChartPlugin, UiPlugin,
+};
 use resources::AppState;
 
 fn main() {
@@ -21,6 +24,17 @@ fn main() {
             ..default()
         }))
         .init_state::<AppState>()
-        .add_plugins((CameraPlugin, ReplayPlugin, UiPlugin))
+        .add_plugins((
+            CameraPlugin,
+            ReplayPlugin,
+            GhostPlugin,
+            UiPlugin,
+            ExportPlugin,
+            LapPickerPlugin,
+            StintChartPlugin,
+            RaceHistoryPlugin,
+            OvertakePlugin,
+            DrsPlugin,
+        ))
         .run();
 }
@@ -0,0 +1,177 @@
+//! Chart overlays shared by the race analysis views.
+//!
+//! A chart is drawn with gizmos in the 2D camera's space (centered on the
+//! origin, one world unit per pixel) and labelled with UI text placed in a
+//! root node of `ROOT_SIZE` in one corner of the window. Positions are in
+//! pixels from the root's bottom-left corner either way.
+
+use bevy::prelude::*;
+use f1_data::{format, SessionType};
+
+use crate::resources::SessionData;
+
+/// Plot area plus tick labels, in pixels.
+const CHART_SIZE: Vec2 = Vec2::new(720.0, 300.0);
+/// Distance from the window edges.
+const CHART_MARGIN: f32 = 20.0;
+/// Room left of and below the plot for tick labels.
+const AXIS_LEFT: f32 = 56.0;
+const AXIS_BOTTOM: f32 = 24.0;
+/// Room right of the plot for the legend.
+const LEGEND_WIDTH: f32 = 160.0;
+/// Room above the plot for the title.
+const TITLE_HEIGHT: f32 = 24.0;
+const ROOT_SIZE: Vec2 = Vec2::new(
+    CHART_SIZE.x + 2.0 * CHART_MARGIN + LEGEND_WIDTH,
+    CHART_SIZE.y + 2.0 * CHART_MARGIN + TITLE_HEIGHT,
+);
+/// Vertical spacing of legend lines.
+pub(super) const LINE_HEIGHT: f32 = 14.0;
+
+pub(super) const AXIS_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.4);
+
+/// Window corner a chart sits in.
+#[derive(Clone, Copy)]
+pub(super) enum Corner {
+    TopLeft,
+    BottomLeft,
+}
+
+/// Value ranges mapped onto the plot area. A range may run backwards (e.g.
+/// positions, with P1 at the top).
+pub(super) struct Plot {
+    pub x: (f64, f64),
+    pub y: (f64, f64),
+    pub corner: Corner,
+}
+
+impl Plot {
+    /// Bottom-left corner of the plot area.
+    pub fn origin() -> Vec2 {
+        Vec2::new(CHART_MARGIN + AXIS_LEFT, CHART_MARGIN + AXIS_BOTTOM)
+    }
+
+    pub fn size() -> Vec2 {
+        CHART_SIZE - Vec2::new(AXIS_LEFT, AXIS_BOTTOM)
+    }
+
+    /// Pixel position of a value from the root's bottom-left corner.
+    pub fn point(&self, x: f64, y: f64) -> Vec2 {
+        let fx = (x - self.x.0) / (self.x.1 - self.x.0);
+        let fy = (y - self.y.0) / (self.y.1 - self.y.0);
+        Self::origin() + Self::size() * Vec2::new(fx as f32, fy as f32)
+    }
+
+    /// Gizmo position of a value.
+    pub fn world(&self, window: &Window, x: f64, y: f64) -> Vec2 {
+        self.root_corner(window) + self.point(x, y)
+    }
+
+    fn root_corner(&self, window: &Window) -> Vec2 {
+        let half = Vec2::new(window.width(), window.height()) / 2.0;
+        match self.corner {
+            Corner::BottomLeft => -half,
+            Corner::TopLeft => Vec2::new(-half.x, half.y - ROOT_SIZE.y),
+        }
+    }
+
+    pub fn draw_axes(&self, window: &Window, gizmos: &mut Gizmos) {
+        let origin = self.root_corner(window) + Self::origin();
+        let size = Self::size();
+        gizmos.line_2d(origin, origin + Vec2::X * size.x, AXIS_COLOR);
+        gizmos.line_2d(origin, origin + Vec2::Y * size.y, AXIS_COLOR);
+    }
+
+    /// Four labels up the Y axis and five along the X axis.
+    pub fn tick_labels(
+        &self,
+        parent: &mut ChildBuilder,
+        x_label: impl Fn(f64) -> String,
+        y_label: impl Fn(f64) -> String,
+    ) {
+        for i in 0..=3 {
+            let y = self.y.0 + (self.y.1 - self.y.0) * i as f64 / 3.0;
+            let bottom = self.point(self.x.0, y).y - 7.0;
+            label(parent, &y_label(y), CHART_MARGIN, bottom, AXIS_COLOR);
+        }
+        for i in 0..=4 {
+            let x = self.x.0 + (self.x.1 - self.x.0) * i as f64 / 4.0;
+            let left = self.point(x, self.y.0).x - 6.0;
+            label(parent, &x_label(x), left, CHART_MARGIN, AXIS_COLOR);
+        }
+    }
+
+    /// Left edge of the legend.
+    pub fn legend_left() -> f32 {
+        CHART_MARGIN + CHART_SIZE.x + 12.0
+    }
+}
+
+/// Spawn the node holding a chart's labels.
+pub(super) fn spawn_root(
+    commands: &mut Commands,
+    corner: Corner,
+    marker: impl Component,
+) -> Entity {
+    let mut node = Node {
+        position_type: PositionType::Absolute,
+        left: Val::Px(0.0),
+        width: Val::Px(ROOT_SIZE.x),
+        height: Val::Px(ROOT_SIZE.y),
+        ..default()
+    };
+    match corner {
+        Corner::TopLeft => node.top = Val::Px(0.0),
+        Corner::BottomLeft => node.bottom = Val::Px(0.0),
+    }
+    commands.spawn((node, marker)).id()
+}
+
+/// The chart title above the plot.
+pub(super) fn title(parent: &mut ChildBuilder, text: &str) {
+    label(
+        parent,
+        text,
+        CHART_MARGIN,
+        CHART_MARGIN + CHART_SIZE.y + 4.0,
+        Color::WHITE,
+    );
+}
+
+/// A line of text with its bottom-left corner at `left`, `bottom`.
+pub(super) fn label(parent: &mut ChildBuilder, text: &str, left: f32, bottom: f32, color: Color) {
+    parent.spawn((
+        Text::new(text),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        TextColor(color),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(left),
+            bottom: Val::Px(bottom),
+            ..default()
+        },
+    ));
+}
+
+/// The active session if it is a race or sprint, otherwise the first one
+/// loaded.
+pub(super) fn race_session(session_data: &SessionData) -> Option<usize> {
+    let is_race = |index: usize| {
+        session_data
+            .sessions
+            .get(index)
+            .is_some_and(|s| matches!(s.session_type, SessionType::Race | SessionType::Sprint))
+    };
+    if is_race(session_data.active) {
+        return Some(session_data.active);
+    }
+    (0..session_data.sessions.len()).find(|&i| is_race(i))
+}
+
+/// `m:ss.s`
+pub(super) fn lap_time(seconds: f64) -> String {
+    format::lap_time_decimals(seconds, 1)
+}
@@ -0,0 +1,182 @@
+//! DRS and slipstream overlay on the track map.
+//!
+//! `D` toggles the active session's straights drawn over the track, DRS
+//! zones in green, each labelled with the mean top speed and time gained
+//! with DRS, in a tow and with both. Gains are averaged over the selected
+//! drivers, or the whole field.
+
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use f1_data::drs::{self, DrsAnalysis, DrsConfig, DrsEffect};
+
+use crate::resources::{AppState, DrsMap, ReplayState, SessionData, TrackView};
+
+const DRS_ZONE_COLOR: Color = Color::srgb(0.2, 0.9, 0.3);
+const STRAIGHT_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+/// How far labels sit from the straight's midpoint (pixels).
+const LABEL_OFFSET: Vec2 = Vec2::new(12.0, 12.0);
+
+pub struct DrsPlugin;
+
+impl Plugin for DrsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DrsMap>()
+            .add_systems(
+                Update,
+                (update_drs_map, draw_straights, rebuild_drs_labels)
+                    .chain()
+                    .run_if(in_state(AppState::Replay)),
+            )
+            .add_systems(OnExit(AppState::Replay), despawn_drs_labels);
+    }
+}
+
+#[derive(Component)]
+struct DrsLabel;
+
+/// Toggle the overlay with `D` and analyze the active session when needed.
+fn update_drs_map(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    session_data: Res<SessionData>,
+    mut map: ResMut<DrsMap>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyD) {
+        map.open = !map.open;
+    }
+    if session_data.is_changed() && map.analysis.is_some() {
+        map.analysis = None;
+    }
+    if !map.open || map.analysis.is_some() {
+        return;
+    }
+    let (Some(session), Some(laps)) = (session_data.session(), session_data.active_laps()) else {
+        return;
+    };
+    let analysis = drs::analyze_drs(session, &laps.classes, &DrsConfig::default());
+    info!(
+        "{} straights ({} DRS zones) in {} {}",
+        analysis.straights.len(),
+        analysis.straights.iter().filter(|s| s.drs_zone).count(),
+        session.year,
+        session.circuit
+    );
+    map.analysis = Some((session_data.active, analysis));
+}
+
+fn draw_straights(map: Res<DrsMap>, track_view: Res<TrackView>, mut gizmos: Gizmos) {
+    let (true, Some((_, analysis))) = (map.open, &map.analysis) else {
+        return;
+    };
+    for straight in &analysis.straights {
+        let color = if straight.drs_zone {
+            DRS_ZONE_COLOR
+        } else {
+            STRAIGHT_COLOR
+        };
+        // Doubled a pixel apart so the straight stands out from the track
+        for offset in [Vec2::ZERO, Vec2::ONE] {
+            let points = straight
+                .path
+                .iter()
+                .map(|p| track_view.project(*p) + offset);
+            gizmos.linestrip_2d(points, color);
+        }
+    }
+}
+
+/// Mean of an effect's gain over the charted drivers.
+fn mean_gain(effects: &[&DrsEffect], gain: impl Fn(&DrsEffect) -> Option<f64>) -> Option<f64> {
+    let values: Vec<f64> = effects.iter().filter_map(|e| gain(e)).collect();
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+fn straight_label(analysis: &DrsAnalysis, number: usize, drivers: &[String]) -> String {
+    let effects: Vec<&DrsEffect> = analysis
+        .effects
+        .iter()
+        .filter(|e| e.straight == number)
+        .filter(|e| drivers.is_empty() || drivers.contains(&e.driver_code))
+        .collect();
+    let mut label = format!("S{number}");
+    let gains = [
+        (
+            "DRS",
+            mean_gain(&effects, |e| e.drs_speed_gain),
+            mean_gain(&effects, |e| e.drs_time_gain),
+        ),
+        (
+            "tow",
+            mean_gain(&effects, |e| e.tow_speed_gain),
+            mean_gain(&effects, |e| e.tow_time_gain),
+        ),
+        (
+            "DRS+tow",
+            mean_gain(&effects, |e| e.drs_tow_speed_gain),
+            mean_gain(&effects, |e| e.drs_tow_time_gain),
+        ),
+    ];
+    for (name, speed, time) in gains {
+        if let (Some(speed), Some(time)) = (speed, time) {
+            label.push_str(&format!("\n{name} {speed:+.1} km/h, {time:+.3} s"));
+        }
+    }
+    label
+}
+
+/// Rebuild the labels when the overlay, the track view or the selected
+/// drivers change.
+fn rebuild_drs_labels(
+    mut commands: Commands,
+    map: Res<DrsMap>,
+    track_view: Res<TrackView>,
+    replay_state: Res<ReplayState>,
+    labels: Query<Entity, With<DrsLabel>>,
+    mut last: Local<Option<Vec<String>>>,
+) {
+    let unchanged = !map.is_changed()
+        && !track_view.is_changed()
+        && last.as_ref() == Some(&replay_state.selected_drivers);
+    if unchanged && labels.is_empty() != map.open {
+        return;
+    }
+    *last = Some(replay_state.selected_drivers.clone());
+
+    for entity in &labels {
+        commands.entity(entity).despawn_recursive();
+    }
+    let (true, Some((_, analysis))) = (map.open, &map.analysis) else {
+        return;
+    };
+    for straight in &analysis.straights {
+        let Some(midpoint) = straight.midpoint() else {
+            continue;
+        };
+        let color = if straight.drs_zone {
+            DRS_ZONE_COLOR
+        } else {
+            STRAIGHT_COLOR
+        };
+        let at = track_view.project(midpoint) + LABEL_OFFSET;
+        commands.spawn((
+            Text2d::new(straight_label(
+                analysis,
+                straight.number,
+                &replay_state.selected_drivers,
+            )),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(color),
+            Anchor::BottomLeft,
+            Transform::from_xyz(at.x, at.y, 2.0),
+            DrsLabel,
+        ));
+    }
+}
+
+fn despawn_drs_labels(mut commands: Commands, labels: Query<Entity, With<DrsLabel>>) {
+    for entity in &labels {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,144 @@
+//! Export plugin: write the current analysis to disk.
+//!
+//! `E` during replay writes lap tables for the shown drivers, delta traces of
+//! the side-by-side laps against the first one, and the selected turn's
+//! comparison, each as CSV, JSON and Markdown, to
+//! `exports/<year>_<circuit>_<session>/` of the active session. Side-by-side
+//! laps from other loaded sessions are named with their session.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use f1_data::analysis::TurnComparison;
+use f1_data::export::{self, ExportFormat, Exportable, LapTable};
+use f1_data::{DeltaTrace, Lap, LapTrace, Session};
+
+use crate::resources::{
+    AppState, LapRef, ReplayMode, ReplayState, SelectedTurn, SessionData, TurnData,
+};
+
+/// Spacing of delta trace points (m).
+const DELTA_STEP: f32 = 10.0;
+
+pub struct ExportPlugin;
+
+impl Plugin for ExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, export_analysis.run_if(in_state(AppState::Replay)));
+    }
+}
+
+fn export_analysis(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    session_data: Res<SessionData>,
+    replay_state: Res<ReplayState>,
+    turn_data: Res<TurnData>,
+    selected_turn: Res<SelectedTurn>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+    let (Some(session), Some(laps)) = (session_data.session(), session_data.active_laps()) else {
+        return;
+    };
+
+    let dir = export_dir(session);
+    if let Err(e) = fs::create_dir_all(&dir) {
+        warn!("Could not create export directory {:?}: {}", dir, e);
+        return;
+    }
+
+    let mut written = 0;
+    let drivers = session.drivers.iter().filter(|d| {
+        replay_state.selected_drivers.is_empty()
+            || replay_state.selected_drivers.contains(&d.driver.code)
+    });
+    for driver in drivers {
+        let stem = format!("laps_{}", driver.driver.code);
+        written += write_all(&dir, &stem, &LapTable::classified(driver, &laps.classes));
+    }
+
+    // Side-by-side laps, possibly from several sessions
+    let selections: &[LapRef] = if replay_state.mode == ReplayMode::LapRelative {
+        &replay_state.lap_selections
+    } else {
+        &[]
+    };
+    let laps: Vec<(String, &Lap)> = selections
+        .iter()
+        .filter_map(|sel| Some((session_data.lap_label(sel), session_data.sessions.lap(sel)?)))
+        .collect();
+
+    if let Some(((reference_name, reference_lap), others)) = laps.split_first() {
+        if let Some(reference) = LapTrace::new(reference_lap) {
+            for (name, lap) in others {
+                let Some(trace) = LapTrace::new(lap) else {
+                    continue;
+                };
+                let delta = DeltaTrace::new(name, &trace, reference_name, &reference, DELTA_STEP);
+                let stem = format!("delta_{}_L{}", name.replace(' ', "_"), lap.number);
+                written += write_all(&dir, &stem, &delta);
+            }
+        }
+    }
+
+    let turn = turn_data
+        .turns
+        .as_ref()
+        .zip(selected_turn.turn_name.as_deref())
+        .and_then(|(turns, name)| turns.get_turn(name));
+    if let Some(turn) = turn {
+        let comparison = if laps.is_empty() {
+            TurnComparison::compare_drivers(
+                &fastest_laps(session, &replay_state.selected_drivers),
+                turn,
+            )
+        } else {
+            session_data.sessions.compare_turn(selections, turn)
+        };
+        written += write_all(&dir, &format!("turn_{}", turn.name), &comparison);
+    }
+
+    info!("Exported {} files to {:?}", written, dir);
+}
+
+/// `exports/<year>_<circuit>_<session>`, e.g. `exports/2024_monza_Q`.
+fn export_dir(session: &Session) -> PathBuf {
+    let circuit = if session.circuit_short.is_empty() {
+        &session.circuit
+    } else {
+        &session.circuit_short
+    };
+    let name = format!(
+        "{}_{}_{}",
+        session.year,
+        circuit.to_lowercase().replace(' ', "_"),
+        session.session_type.as_str()
+    );
+    Path::new("exports").join(name)
+}
+
+/// Fastest lap of each selected driver (all drivers if none are selected).
+fn fastest_laps<'a>(session: &'a Session, selected: &[String]) -> Vec<(&'a str, &'a Lap)> {
+    session
+        .drivers
+        .iter()
+        .filter(|d| selected.is_empty() || selected.contains(&d.driver.code))
+        .filter_map(|d| Some((d.driver.code.as_str(), d.fastest_lap()?)))
+        .collect()
+}
+
+/// Write `value` in every export format; returns the number of files written.
+fn write_all(dir: &Path, stem: &str, value: &impl Exportable) -> usize {
+    let mut written = 0;
+    for format in ExportFormat::ALL {
+        // Not `with_extension`, which would cut stems like "T1.5" short
+        let path = dir.join(format!("{stem}.{}", format.extension()));
+        match export::export_to_file(value, &path) {
+            Ok(()) => written += 1,
+            Err(e) => warn!("Could not export {:?}: {}", path, e),
+        }
+    }
+    written
+}
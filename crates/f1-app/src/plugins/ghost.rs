@@ -0,0 +1,196 @@
+//! Ghost car plugin: replay a reference lap against comparison laps.
+//!
+//! Ghost mode is lap-relative replay (`ReplayMode::LapRelative`) with one of
+//! the selected laps marked as the reference. Every other lap car gets a
+//! label with its live gap to the reference at equal track distance.
+//!
+//! The laps are whatever is picked in the lap picker (`P`), the first one
+//! being the reference until another is marked there; with nothing picked,
+//! `G` starts from the session's fastest lap against the selected drivers.
+
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use crate::components::{Ghost, GhostLabel, LapCar};
+use crate::resources::{AppState, GhostMode, ReplayMode, ReplayState, SessionData};
+
+/// Lap cars that haven't been turned into ghosts yet.
+type UnmarkedLapCars = (With<LapCar>, Without<Ghost>);
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GhostMode>()
+            .add_systems(
+                Update,
+                (
+                    toggle_ghost_mode,
+                    sync_ghost_reference,
+                    attach_ghosts,
+                    update_ghost_gaps,
+                    update_ghost_labels,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Replay)),
+            )
+            .add_systems(OnExit(AppState::Replay), disable_ghost_mode);
+    }
+}
+
+/// Toggle ghost mode with `G`, racing the picked laps against the first of
+/// them (or, with none picked, the session's fastest lap against the best
+/// laps of the selected drivers).
+fn toggle_ghost_mode(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    session_data: Res<SessionData>,
+    mut replay_state: ResMut<ReplayState>,
+    mut ghost_mode: ResMut<GhostMode>,
+) {
+    let Some(session) = session_data.session() else {
+        return;
+    };
+    // Leaving lap-relative mode (e.g. with `L`) also ends ghost mode
+    if ghost_mode.enabled && replay_state.mode != ReplayMode::LapRelative {
+        ghost_mode.enabled = false;
+    }
+    if !keyboard.just_pressed(KeyCode::KeyG) {
+        return;
+    }
+
+    ghost_mode.enabled = !ghost_mode.enabled;
+    if ghost_mode.enabled {
+        if replay_state.lap_selections.is_empty() {
+            replay_state.lap_selections = super::replay::default_lap_selections(
+                session,
+                session_data.active,
+                &replay_state.selected_drivers,
+            );
+        }
+        ghost_mode.reference = replay_state.lap_selections.first().cloned();
+        if replay_state.mode != ReplayMode::LapRelative {
+            replay_state.mode = ReplayMode::LapRelative;
+            replay_state.current_time = 0.0;
+        }
+    } else {
+        replay_state.lap_selections.clear();
+        replay_state.mode = ReplayMode::SessionTime;
+        replay_state.current_time = session.time_range().map_or(0.0, |(start, _)| start);
+    }
+}
+
+/// Keep the reference among the picked laps, falling back to the first, and
+/// mark the ghost racing it.
+fn sync_ghost_reference(
+    replay_state: Res<ReplayState>,
+    mut ghost_mode: ResMut<GhostMode>,
+    mut ghosts: Query<(&LapCar, &mut Ghost)>,
+) {
+    if !ghost_mode.enabled {
+        return;
+    }
+    let picked = ghost_mode
+        .reference
+        .as_ref()
+        .is_some_and(|lap| replay_state.lap_selections.contains(lap));
+    if !picked {
+        ghost_mode.reference = replay_state.lap_selections.first().cloned();
+    }
+    for (car, mut ghost) in &mut ghosts {
+        let is_reference = ghost_mode.reference.as_ref() == Some(&car.lap);
+        if ghost.is_reference != is_reference {
+            ghost.is_reference = is_reference;
+        }
+    }
+}
+
+/// Mark lap cars as ghosts and give them a gap label.
+fn attach_ghosts(
+    mut commands: Commands,
+    ghost_mode: Res<GhostMode>,
+    session_data: Res<SessionData>,
+    new_cars: Query<(Entity, &LapCar), UnmarkedLapCars>,
+) {
+    if !ghost_mode.enabled {
+        return;
+    }
+
+    for (entity, car) in &new_cars {
+        let is_reference = ghost_mode.reference.as_ref() == Some(&car.lap);
+        let color = session_data
+            .driver(&car.lap.driver_code)
+            .map_or(Color::WHITE, |d| {
+                crate::systems::team_color(&d.driver.team_color)
+            });
+
+        commands
+            .entity(entity)
+            .insert(Ghost {
+                is_reference,
+                gap: None,
+            })
+            .with_children(|parent| {
+                parent.spawn((
+                    Text2d::default(),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(color),
+                    Anchor::CenterLeft,
+                    Transform::from_xyz(10.0, 0.0, 1.0),
+                    GhostLabel,
+                ));
+            });
+    }
+}
+
+/// Compute each ghost's gap to the reference lap at equal distance.
+fn update_ghost_gaps(
+    replay_state: Res<ReplayState>,
+    mut ghosts: Query<(Entity, &LapCar, &mut Ghost)>,
+) {
+    let lap_time = replay_state.current_time;
+    let reference = ghosts
+        .iter()
+        .find(|(_, _, ghost)| ghost.is_reference)
+        .map(|(_, car, _)| &car.trace);
+    let gaps: Vec<(Entity, Option<f64>)> = ghosts
+        .iter()
+        .map(|(entity, car, ghost)| {
+            let gap = reference
+                .filter(|_| !ghost.is_reference)
+                .map(|reference| car.trace.gap_to(reference, lap_time));
+            (entity, gap)
+        })
+        .collect();
+
+    for (entity, gap) in gaps {
+        if let Ok((_, _, mut ghost)) = ghosts.get_mut(entity) {
+            ghost.gap = gap;
+        }
+    }
+}
+
+/// Show driver, session (when several are loaded), lap number and gap to
+/// the reference next to each ghost.
+fn update_ghost_labels(
+    session_data: Res<SessionData>,
+    ghosts: Query<(&LapCar, &Ghost)>,
+    mut labels: Query<(&Parent, &mut Text2d), With<GhostLabel>>,
+) {
+    for (parent, mut text) in &mut labels {
+        let Ok((car, ghost)) = ghosts.get(parent.get()) else {
+            continue;
+        };
+        let name = session_data.lap_label(&car.lap);
+        text.0 = match ghost.gap {
+            Some(gap) => format!("{} L{} {:+.3}", name, car.lap.lap_number, gap),
+            None => format!("{} L{} REF", name, car.lap.lap_number),
+        };
+    }
+}
+
+fn disable_ghost_mode(mut ghost_mode: ResMut<GhostMode>) {
+    ghost_mode.enabled = false;
+}
@@ -0,0 +1,213 @@
+//! Race history chart: positions and gaps to the leader lap by lap.
+//!
+//! `H` cycles through the position chart, the gap chart and off, for the
+//! same race as the stint chart (see `chart::race_session`). Each driver is
+//! a line in their team color, with a ring on the laps they pitted. The
+//! charts show the selected drivers, or the whole field.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use f1_data::history::{DriverHistory, LapStanding, RaceHistory};
+
+use super::chart::{self, Corner, Plot, LINE_HEIGHT};
+use crate::resources::{AppState, HistoryView, RaceHistoryChart, ReplayState, SessionData};
+use crate::systems::team_color;
+
+pub struct RaceHistoryPlugin;
+
+impl Plugin for RaceHistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RaceHistoryChart>()
+            .add_systems(
+                Update,
+                (
+                    update_history_chart,
+                    draw_history_chart,
+                    rebuild_history_labels,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Replay)),
+            )
+            .add_systems(OnExit(AppState::Replay), despawn_history_labels);
+    }
+}
+
+#[derive(Component)]
+struct HistoryChartRoot;
+
+/// Cycle the view with `H` and compute the race's history when needed.
+fn update_history_chart(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    session_data: Res<SessionData>,
+    mut chart: ResMut<RaceHistoryChart>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyH) {
+        chart.view = match chart.view {
+            None => Some(HistoryView::Positions),
+            Some(HistoryView::Positions) => Some(HistoryView::Gaps),
+            Some(HistoryView::Gaps) => None,
+        };
+    }
+    if session_data.is_changed() && chart.history.is_some() {
+        chart.history = None;
+    }
+    if chart.view.is_none() || chart.history.is_some() {
+        return;
+    }
+    let Some(index) = chart::race_session(&session_data) else {
+        return;
+    };
+    let (Some(session), Some(laps)) = (session_data.sessions.get(index), session_data.laps(index))
+    else {
+        return;
+    };
+    chart.history = Some((index, RaceHistory::new(session, &laps.stops)));
+}
+
+/// Charted drivers in finishing order: most laps completed, then race time.
+fn charted_drivers<'a>(history: &'a RaceHistory, selected: &[String]) -> Vec<&'a DriverHistory> {
+    let mut drivers: Vec<&DriverHistory> = history
+        .drivers
+        .iter()
+        .filter(|d| !d.laps.is_empty())
+        .filter(|d| selected.is_empty() || selected.contains(&d.driver_code))
+        .collect();
+    drivers.sort_by(|a, b| {
+        let (a, b) = (a.laps.last(), b.laps.last());
+        let lap = |l: Option<&LapStanding>| l.map_or(0, |l| l.lap_number);
+        let time = |l: Option<&LapStanding>| l.map_or(0.0, |l| l.race_time);
+        lap(b).cmp(&lap(a)).then(time(a).total_cmp(&time(b)))
+    });
+    drivers
+}
+
+/// The charted value of a lap.
+fn value(view: HistoryView, lap: &LapStanding) -> f64 {
+    match view {
+        HistoryView::Positions => lap.position as f64,
+        HistoryView::Gaps => lap.gap,
+    }
+}
+
+/// Lap number against position or gap, leader at the top.
+fn history_plot(view: HistoryView, drivers: &[&DriverHistory]) -> Option<Plot> {
+    let laps = drivers.iter().flat_map(|d| &d.laps);
+    let last_lap = laps.clone().map(|l| l.lap_number).max()?;
+    let worst = laps.map(|l| value(view, l)).fold(0.0, f64::max);
+    let y = match view {
+        HistoryView::Positions => (worst.max(2.0), 1.0),
+        HistoryView::Gaps => (worst.max(1.0), 0.0),
+    };
+    Some(Plot {
+        x: (1.0, (last_lap as f64).max(2.0)),
+        y,
+        corner: Corner::TopLeft,
+    })
+}
+
+fn draw_history_chart(
+    chart: Res<RaceHistoryChart>,
+    session_data: Res<SessionData>,
+    replay_state: Res<ReplayState>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut gizmos: Gizmos,
+) {
+    let (Some(view), Some((_, history))) = (chart.view, &chart.history) else {
+        return;
+    };
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let drivers = charted_drivers(history, &replay_state.selected_drivers);
+    let Some(plot) = history_plot(view, &drivers) else {
+        return;
+    };
+    plot.draw_axes(window, &mut gizmos);
+
+    for driver in drivers {
+        let color = session_data
+            .driver(&driver.driver_code)
+            .map_or(Color::WHITE, |d| team_color(&d.driver.team_color));
+        let points = driver
+            .laps
+            .iter()
+            .map(|l| plot.world(window, l.lap_number as f64, value(view, l)));
+        gizmos.linestrip_2d(points, color);
+        for lap in driver.laps.iter().filter(|l| l.pitted) {
+            let at = plot.world(window, lap.lap_number as f64, value(view, lap));
+            gizmos.circle_2d(at, 4.0, color);
+        }
+    }
+}
+
+/// Rebuild the title, tick labels and legend when the chart or the selected
+/// drivers change.
+fn rebuild_history_labels(
+    mut commands: Commands,
+    chart: Res<RaceHistoryChart>,
+    session_data: Res<SessionData>,
+    replay_state: Res<ReplayState>,
+    roots: Query<Entity, With<HistoryChartRoot>>,
+    mut last: Local<Option<Vec<String>>>,
+) {
+    let open = chart.view.is_some();
+    let unchanged = !chart.is_changed() && last.as_ref() == Some(&replay_state.selected_drivers);
+    if unchanged && roots.is_empty() != open {
+        return;
+    }
+    *last = Some(replay_state.selected_drivers.clone());
+
+    for entity in &roots {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(view) = chart.view else {
+        return;
+    };
+
+    let root = chart::spawn_root(&mut commands, Corner::TopLeft, HistoryChartRoot);
+    let Some((index, history)) = &chart.history else {
+        commands.entity(root).with_children(|parent| {
+            chart::title(parent, "Race history (H): no race loaded");
+        });
+        return;
+    };
+    let drivers = charted_drivers(history, &replay_state.selected_drivers);
+    let name = session_data
+        .sessions
+        .short_label(*index)
+        .unwrap_or_default();
+
+    commands.entity(root).with_children(|parent| {
+        let Some(plot) = history_plot(view, &drivers) else {
+            chart::title(parent, &format!("{name}: no timed laps (H)"));
+            return;
+        };
+        let (title, y_label): (_, fn(f64) -> String) = match view {
+            HistoryView::Positions => ("positions", |p| format!("P{}", p.round())),
+            HistoryView::Gaps => ("gap to leader", |g| format!("{g:.0} s")),
+        };
+        chart::title(
+            parent,
+            &format!("{name} {title} by lap, rings at pit stops (H)"),
+        );
+        plot.tick_labels(parent, |lap| format!("L{}", lap.round()), y_label);
+
+        // Legend in finishing order
+        let legend_x = Plot::legend_left();
+        let mut y = Plot::origin().y + Plot::size().y - LINE_HEIGHT;
+        for (position, driver) in drivers.iter().enumerate() {
+            let color = session_data
+                .driver(&driver.driver_code)
+                .map_or(Color::WHITE, |d| team_color(&d.driver.team_color));
+            let text = format!("{:>2}. {}", position + 1, driver.driver_code);
+            chart::label(parent, &text, legend_x, y, color);
+            y -= LINE_HEIGHT;
+        }
+    });
+}
+
+fn despawn_history_labels(mut commands: Commands, roots: Query<Entity, With<HistoryChartRoot>>) {
+    for entity in &roots {
+        commands.entity(entity).despawn_recursive();
+    }
+}
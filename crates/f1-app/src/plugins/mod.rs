@@ -1,9 +1,24 @@
 //! Bevy plugins for the application.
 
 mod camera;
+mod chart;
+mod drs;
+mod export;
+mod ghost;
+mod history;
+mod overtakes;
+mod picker;
 mod replay;
+mod stints;
 mod ui;
 
 pub use camera::CameraPlugin;
+pub use drs::DrsPlugin;
+pub use export::ExportPlugin;
+pub use ghost::GhostPlugin;
+pub use history::RaceHistoryPlugin;
+pub use overtakes::OvertakePlugin;
+pub use picker::LapPickerPlugin;
 pub use replay::ReplayPlugin;
+pub use stints::StintChartPlugin;
 pub use ui::UiPlugin;
@@ -0,0 +1,277 @@
+//! Overtake list: every pass in the active session, and jumping to them.
+//!
+//! `O` toggles a panel listing the session's overtakes and rings each pass
+//! on the track map in the passing car's color. Clicking an overtake, or
+//! `,` / `.` for the previous / next one, moves the session-time replay to
+//! a few seconds before it. Passes are named after the loaded turn
+//! definitions when there are any.
+
+use bevy::prelude::*;
+use f1_data::overtake::{self, Overtake, OvertakeConfig};
+
+use crate::resources::{
+    AppState, OvertakeList, ReplayMode, ReplayState, SessionData, TrackView, TurnData,
+};
+use crate::systems::team_color;
+
+/// Replay time shown before a pass when jumping to it (s).
+const LEAD_IN: f64 = 5.0;
+/// Overtakes listed at once, around the current one.
+const LISTED: usize = 20;
+
+const BUTTON_COLOR: Color = Color::srgb(0.2, 0.2, 0.2);
+const SELECTED_COLOR: Color = Color::srgb(0.6, 0.2, 0.8);
+
+pub struct OvertakePlugin;
+
+impl Plugin for OvertakePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OvertakeList>()
+            .add_systems(
+                Update,
+                (
+                    update_overtakes,
+                    jump_to_overtake,
+                    handle_overtake_buttons,
+                    draw_overtakes,
+                    rebuild_overtake_list,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Replay)),
+            )
+            .add_systems(OnExit(AppState::Replay), despawn_overtake_list);
+    }
+}
+
+#[derive(Component)]
+struct OvertakeListRoot;
+
+/// Jump to the overtake at this index.
+#[derive(Component)]
+struct OvertakeButton(usize);
+
+/// Toggle the list with `O` and detect the active session's overtakes when
+/// they're first needed.
+fn update_overtakes(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    session_data: Res<SessionData>,
+    turn_data: Res<TurnData>,
+    mut list: ResMut<OvertakeList>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyO) {
+        list.open = !list.open;
+    }
+    if (session_data.is_changed() || turn_data.is_changed()) && list.overtakes.is_some() {
+        list.overtakes = None;
+        list.current = None;
+    }
+    let jumping = keyboard.any_just_pressed([KeyCode::Comma, KeyCode::Period]);
+    if !(list.open || jumping) || list.overtakes.is_some() {
+        return;
+    }
+    let (Some(session), Some(laps)) = (session_data.session(), session_data.active_laps()) else {
+        return;
+    };
+    let overtakes = overtake::detect_overtakes(
+        session,
+        &laps.stops,
+        turn_data.turns.as_ref(),
+        &OvertakeConfig::default(),
+    );
+    info!(
+        "{} overtakes in {} {}",
+        overtakes.overtakes.len(),
+        session.year,
+        session.circuit
+    );
+    list.overtakes = Some((session_data.active, overtakes));
+}
+
+/// `,` / `.` jump to the previous / next overtake from the replay time.
+fn jump_to_overtake(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut list: ResMut<OvertakeList>,
+    mut replay_state: ResMut<ReplayState>,
+) {
+    let (previous, next) = (
+        keyboard.just_pressed(KeyCode::Comma),
+        keyboard.just_pressed(KeyCode::Period),
+    );
+    if !(previous || next) {
+        return;
+    }
+    let Some((_, overtakes)) = &list.overtakes else {
+        return;
+    };
+    // Lap-relative replay has no session time: start from the first pass
+    let now = match replay_state.mode {
+        ReplayMode::SessionTime => replay_state.current_time,
+        ReplayMode::LapRelative => f64::NEG_INFINITY,
+    };
+    let jump_time = |o: &Overtake| o.session_time - LEAD_IN;
+    let index = if next {
+        overtakes
+            .overtakes
+            .iter()
+            .position(|o| jump_time(o) > now + 0.5)
+    } else {
+        overtakes
+            .overtakes
+            .iter()
+            .rposition(|o| jump_time(o) < now - 0.5)
+    };
+    if let Some(index) = index {
+        jump(&mut list, &mut replay_state, index);
+    }
+}
+
+fn handle_overtake_buttons(
+    buttons: Query<(&Interaction, &OvertakeButton), Changed<Interaction>>,
+    mut list: ResMut<OvertakeList>,
+    mut replay_state: ResMut<ReplayState>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction == Interaction::Pressed {
+            jump(&mut list, &mut replay_state, button.0);
+        }
+    }
+}
+
+/// Replay the session from just before an overtake.
+fn jump(list: &mut OvertakeList, replay_state: &mut ReplayState, index: usize) {
+    let Some(time) = list
+        .overtakes
+        .as_ref()
+        .and_then(|(_, o)| o.overtakes.get(index))
+        .map(|o| o.session_time)
+    else {
+        return;
+    };
+    replay_state.mode = ReplayMode::SessionTime;
+    replay_state.lap_selections.clear();
+    replay_state.current_time = time - LEAD_IN;
+    list.current = Some(index);
+}
+
+/// Ring every pass on the track map, the current one in white.
+fn draw_overtakes(
+    list: Res<OvertakeList>,
+    session_data: Res<SessionData>,
+    track_view: Res<TrackView>,
+    mut gizmos: Gizmos,
+) {
+    let (true, Some((_, overtakes))) = (list.open, &list.overtakes) else {
+        return;
+    };
+    for (index, pass) in overtakes.overtakes.iter().enumerate() {
+        let at = track_view.project(pass.position);
+        let color = session_data
+            .driver(&pass.driver_code)
+            .map_or(Color::WHITE, |d| team_color(&d.driver.team_color));
+        gizmos.circle_2d(at, 8.0, color);
+        if list.current == Some(index) {
+            gizmos.circle_2d(at, 12.0, Color::WHITE);
+        }
+    }
+}
+
+/// Rebuild the panel when it's toggled, recomputed or jumped.
+fn rebuild_overtake_list(
+    mut commands: Commands,
+    list: Res<OvertakeList>,
+    panels: Query<Entity, With<OvertakeListRoot>>,
+) {
+    if !list.is_changed() && panels.is_empty() != list.open {
+        return;
+    }
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !list.open {
+        return;
+    }
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                right: Val::Px(10.0),
+                width: Val::Px(260.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+            OvertakeListRoot,
+        ))
+        .with_children(|parent| {
+            let overtakes = list
+                .overtakes
+                .as_ref()
+                .map_or(&[][..], |(_, o)| &o.overtakes[..]);
+            let title = match overtakes.len() {
+                0 => "No overtakes (O)".to_string(),
+                count => format!("{count} overtakes (O, , and . to jump)"),
+            };
+            parent.spawn((
+                Text::new(title),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            let first = list
+                .current
+                .map_or(0, |c| c.saturating_sub(LISTED / 2))
+                .min(overtakes.len().saturating_sub(LISTED));
+            for (index, pass) in overtakes.iter().enumerate().skip(first).take(LISTED) {
+                let label = format!(
+                    "L{} {} passes {}{}",
+                    pass.lap_number,
+                    pass.driver_code,
+                    pass.passed_code,
+                    pass.turn
+                        .as_ref()
+                        .map_or(String::new(), |t| format!(" into {t}"))
+                );
+                overtake_button(parent, &label, list.current == Some(index), index);
+            }
+        });
+}
+
+fn overtake_button(parent: &mut ChildBuilder, label: &str, selected: bool, index: usize) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                margin: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            BackgroundColor(if selected {
+                SELECTED_COLOR
+            } else {
+                BUTTON_COLOR
+            }),
+            OvertakeButton(index),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+fn despawn_overtake_list(mut commands: Commands, panels: Query<Entity, With<OvertakeListRoot>>) {
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+}
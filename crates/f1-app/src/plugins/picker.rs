@@ -0,0 +1,292 @@
+//! Lap picker: choose laps to replay side by side from any loaded session.
+//!
+//! `P` toggles a panel listing the drivers of every loaded session. For the
+//! chosen driver it shows each session's fastest laps; clicking one adds it
+//! to, or removes it from, the lap-relative replay; in ghost mode each picked
+//! lap also gets a "Ref" button making it the reference. Clicking a session's name
+//! makes it the active session, and "Add session" goes back to the session
+//! select screen to load another one alongside. Sessions aligned to the first
+//! one on load show the fit's RMS error next to their name.
+
+use bevy::prelude::*;
+use f1_data::{format, Session};
+
+use crate::resources::{
+    AppState, GhostMode, LapPicker, LapRef, ReplayMode, ReplayState, SessionData, TrackView,
+};
+
+/// Number of laps listed per session, fastest first.
+const LAPS_PER_SESSION: usize = 5;
+
+const BUTTON_COLOR: Color = Color::srgb(0.2, 0.2, 0.2);
+const SELECTED_COLOR: Color = Color::srgb(0.6, 0.2, 0.8);
+
+pub struct LapPickerPlugin;
+
+impl Plugin for LapPickerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LapPicker>()
+            .add_systems(
+                Update,
+                (toggle_lap_picker, handle_picker_buttons, rebuild_lap_picker)
+                    .chain()
+                    .run_if(in_state(AppState::Replay)),
+            )
+            .add_systems(OnExit(AppState::Replay), despawn_lap_picker);
+    }
+}
+
+#[derive(Component)]
+struct LapPickerRoot;
+
+#[derive(Component)]
+enum PickerButton {
+    Driver(String),
+    /// Make the session at this index active
+    Session(usize),
+    Lap(LapRef),
+    /// Make this picked lap the ghost reference
+    Reference(LapRef),
+    AddSession,
+}
+
+/// Toggle the panel with `P`, starting on a selected or replayed driver.
+fn toggle_lap_picker(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    session_data: Res<SessionData>,
+    replay_state: Res<ReplayState>,
+    mut picker: ResMut<LapPicker>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyP) {
+        return;
+    }
+    picker.open = !picker.open;
+    if picker.driver.is_none() {
+        picker.driver = replay_state
+            .lap_selections
+            .first()
+            .map(|lap| lap.driver_code.clone())
+            .or_else(|| replay_state.selected_drivers.first().cloned())
+            .or_else(|| {
+                session_data
+                    .sessions
+                    .driver_codes()
+                    .first()
+                    .map(|c| c.to_string())
+            });
+    }
+}
+
+fn handle_picker_buttons(
+    buttons: Query<(&Interaction, &PickerButton), Changed<Interaction>>,
+    mut picker: ResMut<LapPicker>,
+    mut session_data: ResMut<SessionData>,
+    mut replay_state: ResMut<ReplayState>,
+    mut ghost_mode: ResMut<GhostMode>,
+    mut track_view: ResMut<TrackView>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            PickerButton::Driver(code) => picker.driver = Some(code.clone()),
+            PickerButton::Session(index) => {
+                if session_data.active == *index {
+                    continue;
+                }
+                session_data.active = *index;
+                let Some(session) = session_data.session() else {
+                    continue;
+                };
+                *track_view = TrackView::from_session(session).unwrap_or_default();
+                if replay_state.mode == ReplayMode::SessionTime {
+                    replay_state.current_time = session_start(session);
+                }
+            }
+            PickerButton::Lap(lap) => {
+                if let Some(i) = replay_state.lap_selections.iter().position(|l| l == lap) {
+                    replay_state.lap_selections.remove(i);
+                    if replay_state.lap_selections.is_empty() {
+                        replay_state.mode = ReplayMode::SessionTime;
+                        replay_state.current_time =
+                            session_data.session().map_or(0.0, session_start);
+                    }
+                } else {
+                    replay_state.lap_selections.push(lap.clone());
+                    if replay_state.mode != ReplayMode::LapRelative {
+                        replay_state.mode = ReplayMode::LapRelative;
+                        replay_state.current_time = 0.0;
+                    }
+                }
+            }
+            PickerButton::Reference(lap) => ghost_mode.reference = Some(lap.clone()),
+            PickerButton::AddSession => next_state.set(AppState::SessionSelect),
+        }
+    }
+}
+
+fn session_start(session: &Session) -> f64 {
+    session.time_range().map_or(0.0, |(start, _)| start)
+}
+
+/// Rebuild the panel when it's toggled or the sessions, driver or laps change.
+fn rebuild_lap_picker(
+    mut commands: Commands,
+    picker: Res<LapPicker>,
+    session_data: Res<SessionData>,
+    replay_state: Res<ReplayState>,
+    ghost_mode: Res<GhostMode>,
+    panels: Query<Entity, With<LapPickerRoot>>,
+    mut last: Local<Option<Vec<LapRef>>>,
+) {
+    let unchanged = !picker.is_changed()
+        && !session_data.is_changed()
+        && !ghost_mode.is_changed()
+        && last.as_ref() == Some(&replay_state.lap_selections);
+    if unchanged && panels.is_empty() != picker.open {
+        return;
+    }
+    *last = Some(replay_state.lap_selections.clone());
+
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !picker.open {
+        return;
+    }
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                right: Val::Px(10.0),
+                width: Val::Px(320.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+            LapPickerRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Laps (P)"),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            parent
+                .spawn(Node {
+                    flex_wrap: FlexWrap::Wrap,
+                    margin: UiRect::vertical(Val::Px(6.0)),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for code in session_data.sessions.driver_codes() {
+                        let selected = picker.driver.as_deref() == Some(code);
+                        picker_button(
+                            parent,
+                            code,
+                            selected,
+                            PickerButton::Driver(code.to_string()),
+                        );
+                    }
+                });
+
+            let driver = picker.driver.as_deref().unwrap_or_default();
+            for (index, session) in session_data.sessions.sessions().iter().enumerate() {
+                let mut label = session_data.sessions.short_label(index).unwrap_or_default();
+                if let Some(registration) = session_data.registration(index) {
+                    label = format!("{label} (aligned, {:.1} m)", registration.rms_error);
+                }
+                picker_button(
+                    parent,
+                    &label,
+                    index == session_data.active,
+                    PickerButton::Session(index),
+                );
+
+                let mut laps: Vec<(u32, f64)> = session
+                    .get_driver(driver)
+                    .map(|d| {
+                        d.laps
+                            .iter()
+                            .filter(|l| l.is_valid && !l.samples.is_empty())
+                            .filter_map(|l| Some((l.number, l.lap_time?)))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                laps.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+                parent
+                    .spawn(Node {
+                        flex_wrap: FlexWrap::Wrap,
+                        margin: UiRect::left(Val::Px(12.0)),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (number, time) in laps.into_iter().take(LAPS_PER_SESSION) {
+                            let lap = LapRef {
+                                session: index,
+                                driver_code: driver.to_string(),
+                                lap_number: number,
+                            };
+                            let selected = replay_state.lap_selections.contains(&lap);
+                            let label = format!("L{} {}", number, format::lap_time(time));
+                            picker_button(parent, &label, selected, PickerButton::Lap(lap.clone()));
+                            if selected && ghost_mode.enabled {
+                                let reference = ghost_mode.reference.as_ref() == Some(&lap);
+                                picker_button(
+                                    parent,
+                                    "Ref",
+                                    reference,
+                                    PickerButton::Reference(lap),
+                                );
+                            }
+                        }
+                    });
+            }
+
+            picker_button(parent, "Add session", false, PickerButton::AddSession);
+        });
+}
+
+fn picker_button(parent: &mut ChildBuilder, label: &str, selected: bool, button: PickerButton) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                margin: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            BackgroundColor(if selected {
+                SELECTED_COLOR
+            } else {
+                BUTTON_COLOR
+            }),
+            button,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+fn despawn_lap_picker(mut commands: Commands, panels: Query<Entity, With<LapPickerRoot>>) {
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+}
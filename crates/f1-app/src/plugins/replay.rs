@@ -1,10 +1,25 @@
 //! Replay plugin for session playback.
 
 use bevy::prelude::*;
+use bevy::tasks::{block_on, poll_once, AsyncComputeTaskPool};
+use f1_data::loader::{LoadError, LoadProgress};
+use f1_data::registration::{register_laps, Registration, RegistrationConfig};
+use f1_data::{Lap, LapTrace, Session};
 use std::fs;
 use std::path::Path;
+use std::sync::Arc;
 
-use crate::resources::{AppState, AvailableSession, AvailableSessions, LoadSession, ReplayState, SessionData};
+use crate::components::{Car, LapCar, Trail};
+use crate::resources::{
+    AppState, AvailableSession, AvailableSessions, LapRef, LoadSession, LoadedSession, ReplayMode,
+    ReplayState, SessionData, SessionLaps, SessionLoadError, SessionLoadTask, TrackView,
+};
+
+/// Number of laps picked for lap-relative mode when no drivers are selected.
+const DEFAULT_LAP_SELECTIONS: usize = 3;
+
+/// The replay settings that determine which cars exist.
+type CarSelection = (ReplayMode, Vec<String>, Vec<LapRef>);
 
 pub struct ReplayPlugin;
 
@@ -13,13 +28,29 @@ impl Plugin for ReplayPlugin {
         app.init_resource::<SessionData>()
             .init_resource::<ReplayState>()
             .init_resource::<AvailableSessions>()
+            .init_resource::<TrackView>()
+            .init_resource::<SessionLoadError>()
             .add_event::<LoadSession>()
             .add_systems(Update, (
                 scan_sessions.run_if(in_state(AppState::Loading)),
-                update_replay.run_if(in_state(AppState::Replay)),
+                (
+                    crate::systems::handle_playback_input,
+                    toggle_lap_relative_mode,
+                    sync_cars,
+                    update_replay,
+                    update_session_cars,
+                    update_lap_cars,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Replay)),
                 crate::systems::draw_circuit.run_if(in_state(AppState::Replay)),
+                (crate::systems::update_trails, crate::systems::draw_trails, crate::systems::draw_cars)
+                    .chain()
+                    .run_if(in_state(AppState::Replay)),
                 load_session,
-            ));
+                poll_session_load.run_if(in_state(AppState::SessionLoading)),
+            ))
+            .add_systems(OnExit(AppState::Replay), despawn_cars);
     }
 }
 
@@ -58,9 +89,20 @@ fn scan_sessions(
             let path = entry.path();
             if path.extension().and_then(|e| e.to_str()) == Some("json") {
                 if let Some(file_name) = path.file_stem().and_then(|s| s.to_str()) {
-                    // Format: 2025_austin_q -> "Austin 2025 Q"
+                    // Name from the file's header, read without its telemetry
+                    let info = f1_data::lazy::read_info(&path)
+                        .map_err(|e| warn!("Could not read {:?}: {}", path, e))
+                        .ok();
+                    // Otherwise from the file name: 2025_austin_q -> "Austin 2025 Q"
                     let parts: Vec<&str> = file_name.split('_').collect();
-                    let name = if parts.len() >= 3 {
+                    let name = if let Some(info) = info {
+                        let circuit = if info.circuit_short.is_empty() {
+                            &info.circuit
+                        } else {
+                            &info.circuit_short
+                        };
+                        format!("{} {} {}", circuit, info.year, info.session_type.as_str())
+                    } else if parts.len() >= 3 {
                         let year = parts[0];
                         let circuit = parts[1]; // simplified, would need proper casing
                         let session = parts[2].to_uppercase();
@@ -95,23 +137,253 @@ fn scan_sessions(
     next_state.set(AppState::SessionSelect);
 }
 
+/// Start loading the requested session on the async compute pool.
+///
+/// A session added at the same circuit as the first loaded one is aligned to
+/// its coordinate frame, so turn boxes line up across years. Its laps are
+/// classified and its pit stops found on the same task.
 fn load_session(
     mut commands: Commands,
     mut events: EventReader<LoadSession>,
+    available_sessions: Res<AvailableSessions>,
+    session_data: Res<SessionData>,
+    mut load_error: ResMut<SessionLoadError>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    // Only the most recent request matters
+    let Some(event) = events.read().last() else {
+        return;
+    };
+    info!("Loading session from {:?}", event.path);
+
+    let path = event.path.clone();
+    let name = available_sessions
+        .sessions
+        .iter()
+        .find(|s| s.path == path)
+        .map_or_else(|| path.display().to_string(), |s| s.name.clone());
+    let progress = Arc::new(LoadProgress::default());
+    let task_progress = progress.clone();
+    let reference = session_data
+        .sessions
+        .get(0)
+        .filter(|_| event.add)
+        .and_then(|s| Some((s.circuit.clone(), s.fastest_laps().first()?.1.clone())));
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        let mut session = f1_data::loader::load_session_with_progress(&path, &task_progress)?;
+        let registration = reference
+            .filter(|(circuit, _)| *circuit == session.circuit)
+            .and_then(|(_, lap)| align_session(&mut session, &lap));
+        let laps = SessionLaps::analyze(&session);
+        Ok(LoadedSession { session, laps, registration })
+    });
+
+    commands.insert_resource(SessionLoadTask {
+        task,
+        progress,
+        name,
+        add: event.add,
+    });
+    load_error.0 = None;
+    next_state.set(AppState::SessionLoading);
+}
+
+/// Move a session into the coordinate frame of a reference lap's session,
+/// returning the registration used.
+fn align_session(session: &mut Session, reference: &Lap) -> Option<Registration> {
+    let (_, lap) = session.fastest_laps().into_iter().next()?;
+    let registration = register_laps(lap, reference, &RegistrationConfig::default())?;
+    info!(
+        "Aligned {} to the first session: rotation {:.3}°, RMS error {:.2} m",
+        session.label(),
+        registration.transform.rotation.to_degrees(),
+        registration.rms_error
+    );
+    registration.transform.apply_to_session(session);
+    Some(registration)
+}
+
+/// Check the background load and move to replay (or back to session select) when it finishes.
+///
+/// The loaded session becomes the active one. Unless it was added to the
+/// loaded sessions, the replay starts over in session-time mode.
+fn poll_session_load(
+    mut commands: Commands,
+    task: Option<ResMut<SessionLoadTask>>,
     mut session_data: ResMut<SessionData>,
+    mut track_view: ResMut<TrackView>,
+    mut replay_state: ResMut<ReplayState>,
+    mut load_error: ResMut<SessionLoadError>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
-    for event in events.read() {
-        info!("Loading session from {:?}", event.0);
-        
-        match f1_data::loader::load_session(&event.0) {
-            Ok(session) => {
-                info!("Loaded session: {} {} {}", session.year, session.circuit, session.session_type.as_str());
-                session_data.session = Some(session);
-                next_state.set(AppState::Replay);
+    // Cancelled loads are cleaned up by the loading screen
+    let Some(mut task) = task.filter(|t| !t.progress.is_cancelled()) else {
+        return;
+    };
+    let Some(result) = block_on(poll_once(&mut task.task)) else {
+        return;
+    };
+    commands.remove_resource::<SessionLoadTask>();
+
+    match result {
+        Ok(loaded) => {
+            let session = &loaded.session;
+            info!("Loaded session: {} {} {}", session.year, session.circuit, session.session_type.as_str());
+            *track_view = TrackView::from_session(session).unwrap_or_default();
+            if !task.add {
+                session_data.clear();
+                replay_state.mode = ReplayMode::SessionTime;
+                replay_state.lap_selections.clear();
             }
-            Err(e) => {
-                error!("Failed to load session: {:?}", e);
+            if replay_state.mode == ReplayMode::SessionTime {
+                replay_state.current_time = session.time_range().map_or(0.0, |(start, _)| start);
+            }
+            session_data.active = session_data.push(loaded);
+            next_state.set(AppState::Replay);
+        }
+        Err(LoadError::Cancelled) => {
+            info!("Session loading cancelled");
+            next_state.set(AppState::SessionSelect);
+        }
+        Err(e) => {
+            error!("Failed to load session: {:?}", e);
+            load_error.0 = Some(format!("Failed to load {}: {}", task.name, e));
+            next_state.set(AppState::SessionSelect);
+        }
+    }
+}
+
+/// Toggle lap-relative mode with `L`, replaying each driver's best lap from a common start.
+fn toggle_lap_relative_mode(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    session_data: Res<SessionData>,
+    mut replay_state: ResMut<ReplayState>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyL) {
+        return;
+    }
+    let Some(session) = session_data.session() else {
+        return;
+    };
+
+    match replay_state.mode {
+        ReplayMode::SessionTime => {
+            replay_state.lap_selections =
+                default_lap_selections(session, session_data.active, &replay_state.selected_drivers);
+            replay_state.mode = ReplayMode::LapRelative;
+            replay_state.current_time = 0.0;
+        }
+        ReplayMode::LapRelative => {
+            replay_state.lap_selections.clear();
+            replay_state.mode = ReplayMode::SessionTime;
+            replay_state.current_time = session.time_range().map_or(0.0, |(start, _)| start);
+        }
+    }
+}
+
+/// Default laps for lap-relative replay: the session's fastest lap, followed
+/// by the best laps of the selected drivers (or the next fastest drivers).
+pub(crate) fn default_lap_selections(
+    session: &Session,
+    session_index: usize,
+    selected_drivers: &[String],
+) -> Vec<LapRef> {
+    let fastest = session.fastest_laps();
+    let (first, others) = match fastest.split_first() {
+        Some((first, others)) => (first, others),
+        None => return Vec::new(),
+    };
+
+    let others: Vec<_> = if selected_drivers.is_empty() {
+        others.iter().take(DEFAULT_LAP_SELECTIONS - 1).collect()
+    } else {
+        others
+            .iter()
+            .filter(|(driver, _)| selected_drivers.contains(&driver.code))
+            .collect()
+    };
+
+    std::iter::once(first)
+        .chain(others)
+        .map(|(driver, lap)| LapRef {
+            session: session_index,
+            driver_code: driver.code.clone(),
+            lap_number: lap.number,
+        })
+        .collect()
+}
+
+/// Respawn car entities whenever the mode, driver selection or lap selection changes.
+///
+/// Session-time mode gets one car per driver; lap-relative mode gets one car
+/// per selected lap, from any of the loaded sessions.
+fn sync_cars(
+    mut commands: Commands,
+    session_data: Res<SessionData>,
+    replay_state: Res<ReplayState>,
+    cars: Query<Entity, With<Car>>,
+    mut last: Local<Option<CarSelection>>,
+) {
+    let current = (
+        replay_state.mode,
+        replay_state.selected_drivers.clone(),
+        replay_state.lap_selections.clone(),
+    );
+    if !session_data.is_changed() && last.as_ref() == Some(&current) {
+        return;
+    }
+    *last = Some(current);
+
+    for entity in &cars {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(session) = session_data.session() else {
+        return;
+    };
+
+    match replay_state.mode {
+        ReplayMode::SessionTime => {
+            let drivers = session.drivers.iter().filter(|d| {
+                replay_state.selected_drivers.is_empty()
+                    || replay_state.selected_drivers.contains(&d.driver.code)
+            });
+            for driver in drivers {
+                commands.spawn((
+                    Car {
+                        driver_code: driver.driver.code.clone(),
+                        current_lap: 0,
+                        sample_index: 0,
+                    },
+                    Trail::new(60),
+                    Transform::default(),
+                    Visibility::Hidden,
+                ));
+            }
+        }
+        ReplayMode::LapRelative => {
+            for lap_ref in &replay_state.lap_selections {
+                let Some(trace) = session_data.sessions.lap(lap_ref).and_then(LapTrace::new) else {
+                    warn!(
+                        "Lap {} {} has no telemetry",
+                        session_data.lap_label(lap_ref),
+                        lap_ref.lap_number
+                    );
+                    continue;
+                };
+                commands.spawn((
+                    Car {
+                        driver_code: lap_ref.driver_code.clone(),
+                        current_lap: lap_ref.lap_number,
+                        sample_index: 0,
+                    },
+                    LapCar {
+                        trace,
+                        lap: lap_ref.clone(),
+                    },
+                    Trail::new(60),
+                    Transform::default(),
+                    Visibility::default(),
+                ));
             }
         }
     }
@@ -121,18 +393,72 @@ fn update_replay(
     time: Res<Time>,
     mut replay_state: ResMut<ReplayState>,
     session_data: Res<SessionData>,
+    lap_cars: Query<&LapCar>,
 ) {
     if !replay_state.playing {
         return;
     }
 
-    let Some(_session) = &session_data.session else {
+    let Some(session) = session_data.session() else {
         return;
     };
 
     // Advance replay time
     replay_state.current_time += time.delta_secs_f64() * replay_state.speed as f64;
 
-    // TODO: Update car positions based on current_time
-    // This will interpolate between telemetry samples
+    // Stop at the end of the session, or of the longest selected lap
+    let end = match replay_state.mode {
+        ReplayMode::SessionTime => session.time_range().map(|(_, end)| end),
+        ReplayMode::LapRelative => lap_cars.iter().map(|car| car.trace.duration()).reduce(f64::max),
+    };
+    if let Some(end) = end.filter(|&end| replay_state.current_time >= end) {
+        replay_state.current_time = end;
+        replay_state.playing = false;
+    }
+}
+
+/// Position session-time cars by interpolating their telemetry at the current session time.
+fn update_session_cars(
+    replay_state: Res<ReplayState>,
+    session_data: Res<SessionData>,
+    track_view: Res<TrackView>,
+    mut cars: Query<(&mut Car, &mut Transform, &mut Visibility), Without<LapCar>>,
+) {
+    let Some(session) = session_data.session() else {
+        return;
+    };
+
+    for (mut car, mut transform, mut visibility) in &mut cars {
+        let sample = session
+            .get_driver(&car.driver_code)
+            .and_then(|d| f1_data::session_sample_at(d, replay_state.current_time));
+        let Some((lap, index, sample)) = sample else {
+            // No telemetry at this time (e.g. in the garage)
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        car.current_lap = lap.number;
+        car.sample_index = index;
+        transform.translation = track_view.project(sample.position).extend(1.0);
+        *visibility = Visibility::Visible;
+    }
+}
+
+/// Position lap-relative cars at the current time since lap start.
+fn update_lap_cars(
+    replay_state: Res<ReplayState>,
+    track_view: Res<TrackView>,
+    mut cars: Query<(&LapCar, &mut Car, &mut Transform)>,
+) {
+    for (lap_car, mut car, mut transform) in &mut cars {
+        let sample = lap_car.trace.sample_at(replay_state.current_time);
+        car.sample_index = lap_car.trace.sample_index_at(replay_state.current_time);
+        transform.translation = track_view.project(sample.position).extend(1.0);
+    }
+}
+
+fn despawn_cars(mut commands: Commands, cars: Query<Entity, With<Car>>) {
+    for entity in &cars {
+        commands.entity(entity).despawn_recursive();
+    }
 }
@@ -0,0 +1,249 @@
+//! Stint chart: race pace of several drivers across a race.
+//!
+//! `S` toggles a chart of fuel-corrected push lap times over lap number for
+//! the loaded Race (the active session if it is a race or sprint, otherwise
+//! the first race loaded). Each stint is drawn as its laps plus a fit line,
+//! whose slope is the tyre degradation; the breaks between lines are the
+//! pit stops. The chart shows the selected drivers, or the five with the
+//! best pace. Stints are computed from the lap classes and pit stops found
+//! when the race loaded.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use f1_data::stint::{self, RacePace, Stint, StintConfig};
+
+use super::chart::{self, Corner, Plot, AXIS_COLOR, LINE_HEIGHT};
+use crate::resources::{AppState, ReplayState, SessionData, StintChart};
+use crate::systems::team_color;
+
+/// Drivers charted when none are selected.
+const DEFAULT_DRIVERS: usize = 5;
+
+pub struct StintChartPlugin;
+
+impl Plugin for StintChartPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StintChart>()
+            .add_systems(
+                Update,
+                (update_stint_chart, draw_stint_chart, rebuild_stint_labels)
+                    .chain()
+                    .run_if(in_state(AppState::Replay)),
+            )
+            .add_systems(OnExit(AppState::Replay), despawn_stint_labels);
+    }
+}
+
+#[derive(Component)]
+struct StintChartRoot;
+
+/// Toggle the chart with `S` and compute the race's stints when needed.
+fn update_stint_chart(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    session_data: Res<SessionData>,
+    mut chart: ResMut<StintChart>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyS) {
+        chart.open = !chart.open;
+    }
+    // Loading or switching sessions may change which race is charted
+    if session_data.is_changed() && chart.pace.is_some() {
+        chart.pace = None;
+    }
+    if !chart.open || chart.pace.is_some() {
+        return;
+    }
+    let Some(index) = chart::race_session(&session_data) else {
+        return;
+    };
+    let (Some(session), Some(laps)) = (session_data.sessions.get(index), session_data.laps(index))
+    else {
+        return;
+    };
+    let pace = stint::race_pace(session, &laps.classes, &laps.stops, &StintConfig::default());
+    info!(
+        "{} stints, {} pit stops in {} {}",
+        pace.stints.len(),
+        laps.stops.stops.len(),
+        session.year,
+        session.circuit
+    );
+    chart.pace = Some((index, pace));
+}
+
+/// Drivers to chart: the selected ones, or those with the best median
+/// fuel-corrected stint.
+fn charted_drivers(pace: &RacePace, selected: &[String]) -> Vec<String> {
+    if !selected.is_empty() {
+        return selected.to_vec();
+    }
+    let mut best: Vec<(&str, f64)> = Vec::new();
+    for stint in &pace.stints {
+        let Some(time) = stint.fuel_corrected_median else {
+            continue;
+        };
+        match best.iter_mut().find(|(code, _)| *code == stint.driver_code) {
+            Some(entry) => entry.1 = entry.1.min(time),
+            None => best.push((&stint.driver_code, time)),
+        }
+    }
+    best.sort_by(|a, b| a.1.total_cmp(&b.1));
+    best.into_iter()
+        .take(DEFAULT_DRIVERS)
+        .map(|(code, _)| code.to_string())
+        .collect()
+}
+
+/// Lap number against fuel-corrected lap time, `None` without push laps.
+fn stint_plot(stints: &[&Stint]) -> Option<Plot> {
+    let laps = stints.iter().flat_map(|s| &s.laps);
+    let (mut first, mut last) = (f64::INFINITY, f64::NEG_INFINITY);
+    let (mut fastest, mut slowest) = (f64::INFINITY, f64::NEG_INFINITY);
+    for lap in laps {
+        first = first.min(lap.lap_number as f64);
+        last = last.max(lap.lap_number as f64);
+        fastest = fastest.min(lap.fuel_corrected);
+        slowest = slowest.max(lap.fuel_corrected);
+    }
+    if !first.is_finite() {
+        return None;
+    }
+    let padding = ((slowest - fastest) * 0.1).max(0.2);
+    Some(Plot {
+        x: (first, last.max(first + 1.0)),
+        y: (fastest - padding, slowest + padding),
+        corner: Corner::BottomLeft,
+    })
+}
+
+fn charted_stints<'a>(pace: &'a RacePace, drivers: &[String]) -> Vec<&'a Stint> {
+    pace.stints
+        .iter()
+        .filter(|s| drivers.contains(&s.driver_code))
+        .collect()
+}
+
+fn draw_stint_chart(
+    chart: Res<StintChart>,
+    session_data: Res<SessionData>,
+    replay_state: Res<ReplayState>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut gizmos: Gizmos,
+) {
+    let (true, Some((_, pace))) = (chart.open, &chart.pace) else {
+        return;
+    };
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let drivers = charted_drivers(pace, &replay_state.selected_drivers);
+    let stints = charted_stints(pace, &drivers);
+    let Some(plot) = stint_plot(&stints) else {
+        return;
+    };
+    let at = |lap: f64, time: f64| plot.world(window, lap, time);
+    plot.draw_axes(window, &mut gizmos);
+
+    for stint in stints {
+        let color = session_data
+            .driver(&stint.driver_code)
+            .map_or(Color::WHITE, |d| team_color(&d.driver.team_color));
+        for lap in &stint.laps {
+            gizmos.circle_2d(at(lap.lap_number as f64, lap.fuel_corrected), 2.5, color);
+        }
+        // The least-squares line passes through the mean lap
+        let Some(degradation) = stint.degradation else {
+            continue;
+        };
+        let count = stint.laps.len() as f64;
+        let mean_lap = stint.laps.iter().map(|l| l.lap_number as f64).sum::<f64>() / count;
+        let mean_time = stint.laps.iter().map(|l| l.fuel_corrected).sum::<f64>() / count;
+        let (first, last) = (
+            stint.laps[0].lap_number as f64,
+            stint.laps[stint.laps.len() - 1].lap_number as f64,
+        );
+        gizmos.line_2d(
+            at(first, mean_time + (first - mean_lap) * degradation),
+            at(last, mean_time + (last - mean_lap) * degradation),
+            color,
+        );
+    }
+}
+
+/// Rebuild the title, tick labels and legend when the chart or the selected
+/// drivers change.
+fn rebuild_stint_labels(
+    mut commands: Commands,
+    chart: Res<StintChart>,
+    session_data: Res<SessionData>,
+    replay_state: Res<ReplayState>,
+    roots: Query<Entity, With<StintChartRoot>>,
+    mut last: Local<Option<Vec<String>>>,
+) {
+    let unchanged = !chart.is_changed() && last.as_ref() == Some(&replay_state.selected_drivers);
+    if unchanged && roots.is_empty() != chart.open {
+        return;
+    }
+    *last = Some(replay_state.selected_drivers.clone());
+
+    for entity in &roots {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !chart.open {
+        return;
+    }
+
+    let root = chart::spawn_root(&mut commands, Corner::BottomLeft, StintChartRoot);
+
+    let Some((index, pace)) = &chart.pace else {
+        commands.entity(root).with_children(|parent| {
+            chart::title(parent, "Stints (S): no race loaded");
+        });
+        return;
+    };
+    let drivers = charted_drivers(pace, &replay_state.selected_drivers);
+    let stints = charted_stints(pace, &drivers);
+    let name = session_data
+        .sessions
+        .short_label(*index)
+        .unwrap_or_default();
+
+    commands.entity(root).with_children(|parent| {
+        let Some(plot) = stint_plot(&stints) else {
+            chart::title(parent, &format!("{name}: no push laps to chart (S)"));
+            return;
+        };
+        chart::title(
+            parent,
+            &format!("{name} race pace, fuel-corrected push laps (S)"),
+        );
+        plot.tick_labels(parent, |lap| format!("L{}", lap.round()), chart::lap_time);
+
+        // Legend: each driver's stints as median pace and degradation
+        let legend_x = Plot::legend_left();
+        let mut y = Plot::origin().y + Plot::size().y - LINE_HEIGHT;
+        for code in &drivers {
+            let color = session_data
+                .driver(code)
+                .map_or(Color::WHITE, |d| team_color(&d.driver.team_color));
+            chart::label(parent, code, legend_x, y, color);
+            y -= LINE_HEIGHT;
+            for stint in stints.iter().filter(|s| &s.driver_code == code) {
+                let (Some(median), Some(degradation)) =
+                    (stint.fuel_corrected_median, stint.degradation)
+                else {
+                    continue;
+                };
+                let text = format!("  {} {:+.3}/lap", chart::lap_time(median), degradation);
+                chart::label(parent, &text, legend_x, y, AXIS_COLOR);
+                y -= LINE_HEIGHT;
+            }
+        }
+    });
+}
+
+fn despawn_stint_labels(mut commands: Commands, roots: Query<Entity, With<StintChartRoot>>) {
+    for entity in &roots {
+        commands.entity(entity).despawn_recursive();
+    }
+}
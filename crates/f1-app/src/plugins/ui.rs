@@ -2,7 +2,10 @@
 
 use bevy::prelude::*;
 
-use crate::resources::{AppState, AvailableSessions, ComparisonDrivers, LoadSession, SelectedTurn, TurnData};
+use crate::resources::{
+    AppState, AvailableSessions, ComparisonDrivers, LoadSession, SelectedTurn, SessionData,
+    SessionLoadError, SessionLoadTask, TurnData,
+};
 
 pub struct UiPlugin;
 
@@ -12,18 +15,54 @@ impl Plugin for UiPlugin {
             .init_resource::<SelectedTurn>()
             .init_resource::<ComparisonDrivers>()
             .add_systems(OnEnter(AppState::SessionSelect), setup_session_select_ui)
-            .add_systems(Update, handle_session_select.run_if(in_state(AppState::SessionSelect)))
-            .add_systems(OnExit(AppState::SessionSelect), cleanup_session_select_ui);
+            .add_systems(
+                Update,
+                (handle_session_select, handle_back_to_replay).run_if(in_state(AppState::SessionSelect)),
+            )
+            .add_systems(OnExit(AppState::SessionSelect), cleanup_session_select_ui)
+            .add_systems(OnEnter(AppState::SessionLoading), setup_loading_ui)
+            .add_systems(
+                Update,
+                (update_loading_ui, handle_cancel_load).run_if(in_state(AppState::SessionLoading)),
+            )
+            .add_systems(OnExit(AppState::SessionLoading), cleanup_loading_ui);
     }
 }
 
 #[derive(Component)]
 struct SessionSelectRoot;
 
+/// Loads a session, replacing the loaded ones unless `add` is set.
 #[derive(Component)]
-struct SessionButton(std::path::PathBuf);
+struct SessionButton {
+    path: std::path::PathBuf,
+    add: bool,
+}
+
+#[derive(Component)]
+struct BackToReplayButton;
+
+#[derive(Component)]
+struct LoadingRoot;
+
+#[derive(Component)]
+struct LoadingProgressBar;
+
+#[derive(Component)]
+struct LoadingStatusText;
+
+#[derive(Component)]
+struct CancelLoadButton;
+
+fn setup_session_select_ui(
+    mut commands: Commands,
+    available_sessions: Res<AvailableSessions>,
+    session_data: Res<SessionData>,
+    load_error: Res<SessionLoadError>,
+) {
+    // With sessions loaded, each session can also be added alongside them
+    let can_add = !session_data.sessions.is_empty();
 
-fn setup_session_select_ui(mut commands: Commands, available_sessions: Res<AvailableSessions>) {
     commands
         .spawn((
             Node {
@@ -51,32 +90,72 @@ fn setup_session_select_ui(mut commands: Commands, available_sessions: Res<Avail
                 },
             ));
 
+            if let Some(error) = &load_error.0 {
+                parent.spawn((
+                    Text::new(error.clone()),
+                    TextFont {
+                        font_size: 18.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(1.0, 0.35, 0.35)),
+                    Node {
+                        margin: UiRect::bottom(Val::Px(20.0)),
+                        max_width: Val::Px(800.0),
+                        ..default()
+                    },
+                ));
+            }
+
             for session in &available_sessions.sessions {
                 parent
-                    .spawn((
-                        Button,
-                        Node {
-                            width: Val::Px(400.0),
-                            height: Val::Px(50.0),
-                            margin: UiRect::all(Val::Px(10.0)),
-                            justify_content: JustifyContent::Center,
-                            align_items: AlignItems::Center,
-                            ..default()
-                        },
-                        BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
-                        SessionButton(session.path.clone()),
-                    ))
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    })
                     .with_children(|parent| {
-                        parent.spawn((
-                            Text::new(session.name.clone()),
-                            TextFont {
-                                font_size: 20.0,
-                                ..default()
-                            },
-                            TextColor(Color::WHITE),
-                        ));
+                        session_button(parent, 400.0, &session.name, SessionButton {
+                            path: session.path.clone(),
+                            add: false,
+                        });
+                        if can_add {
+                            session_button(parent, 50.0, "+", SessionButton {
+                                path: session.path.clone(),
+                                add: true,
+                            });
+                        }
                     });
             }
+
+            if can_add {
+                session_button(parent, 160.0, "Back (Esc)", BackToReplayButton);
+            }
+        });
+}
+
+fn session_button(parent: &mut ChildBuilder, width: f32, label: &str, marker: impl Component) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(width),
+                height: Val::Px(50.0),
+                margin: UiRect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+            marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
         });
 }
 
@@ -91,7 +170,10 @@ fn handle_session_select(
         match *interaction {
             Interaction::Pressed => {
                 *color = BackgroundColor(Color::srgb(0.35, 0.75, 0.35));
-                load_session_writer.send(LoadSession(session_button.0.clone()));
+                load_session_writer.send(LoadSession {
+                    path: session_button.path.clone(),
+                    add: session_button.add,
+                });
             }
             Interaction::Hovered => {
                 *color = BackgroundColor(Color::srgb(0.25, 0.25, 0.25));
@@ -103,8 +185,167 @@ fn handle_session_select(
     }
 }
 
+/// Return to the loaded sessions via the button or `Esc`.
+fn handle_back_to_replay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<BackToReplayButton>)>,
+    session_data: Res<SessionData>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let pressed = interaction_query.iter().any(|i| *i == Interaction::Pressed);
+    if (pressed || keyboard.just_pressed(KeyCode::Escape)) && !session_data.sessions.is_empty() {
+        next_state.set(AppState::Replay);
+    }
+}
+
 fn cleanup_session_select_ui(mut commands: Commands, query: Query<Entity, With<SessionSelectRoot>>) {
     for entity in &query {
         commands.entity(entity).despawn_recursive();
     }
 }
+
+fn setup_loading_ui(mut commands: Commands, task: Option<Res<SessionLoadTask>>) {
+    let name = task.map_or_else(String::new, |t| t.name.clone());
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::BLACK),
+            LoadingRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(format!("Loading {name}")),
+                TextFont {
+                    font_size: 32.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    ..default()
+                },
+            ));
+
+            // Progress bar: a track with a fill whose width follows the load progress
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(400.0),
+                        height: Val::Px(16.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Node {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(0.6, 0.2, 0.8)),
+                        LoadingProgressBar,
+                    ));
+                });
+
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+                Node {
+                    margin: UiRect::top(Val::Px(10.0)),
+                    ..default()
+                },
+                LoadingStatusText,
+            ));
+
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(160.0),
+                        height: Val::Px(40.0),
+                        margin: UiRect::top(Val::Px(20.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                    CancelLoadButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Cancel (Esc)"),
+                        TextFont {
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+                });
+        });
+}
+
+fn update_loading_ui(
+    task: Option<Res<SessionLoadTask>>,
+    mut bar: Query<&mut Node, With<LoadingProgressBar>>,
+    mut status: Query<&mut Text, With<LoadingStatusText>>,
+) {
+    let Some(task) = task else {
+        return;
+    };
+    let progress = &task.progress;
+
+    for mut node in &mut bar {
+        node.width = Val::Percent(progress.fraction() * 100.0);
+    }
+
+    let (read, total_bytes) = progress.bytes();
+    let (decoded, total_drivers) = progress.drivers();
+    const MB: f64 = 1024.0 * 1024.0;
+    for mut text in &mut status {
+        text.0 = if total_drivers == 0 {
+            format!("Reading {:.1} / {:.1} MB", read as f64 / MB, total_bytes as f64 / MB)
+        } else {
+            format!("Decoding drivers {decoded} / {total_drivers}")
+        };
+    }
+}
+
+/// Cancel the background load via the button or `Esc`.
+fn handle_cancel_load(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<CancelLoadButton>)>,
+    task: Option<Res<SessionLoadTask>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let pressed = interaction_query.iter().any(|i| *i == Interaction::Pressed);
+    if !pressed && !keyboard.just_pressed(KeyCode::Escape) {
+        return;
+    }
+
+    if let Some(task) = task {
+        // The loader stops at its next checkpoint; the task is dropped here
+        task.progress.cancel();
+        commands.remove_resource::<SessionLoadTask>();
+    }
+    next_state.set(AppState::SessionSelect);
+}
+
+fn cleanup_loading_ui(mut commands: Commands, query: Query<Entity, With<LoadingRoot>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,7 +1,17 @@
 //! Shared resources for the application.
 
 use bevy::prelude::*;
-use f1_data::{CircuitTurns, Session};
+use bevy::tasks::Task;
+use f1_data::loader::{LoadError, LoadProgress};
+use f1_data::classify::{self, ClassifyConfig, LapClasses};
+use f1_data::drs::DrsAnalysis;
+use f1_data::history::RaceHistory;
+use f1_data::overtake::Overtakes;
+use f1_data::pit::{self, PitConfig, PitStops};
+use f1_data::registration::Registration;
+use f1_data::stint::RacePace;
+use f1_data::{CircuitTurns, DriverSession, Session, SessionCollection, SessionLapRef};
+use std::sync::Arc;
 
 /// Application state machine.
 #[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
@@ -9,14 +19,147 @@ pub enum AppState {
     #[default]
     Loading,
     SessionSelect,
+    /// A session file is being loaded in the background
+    SessionLoading,
     Replay,
     Analysis,
 }
 
-/// Currently loaded session data.
+/// Mapping from track coordinates (X/Z plane) to 2D screen space.
+///
+/// Computed once per session so the circuit, cars and overlays share the
+/// same framing.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct TrackView {
+    pub center: Vec2,
+    pub scale: f32,
+}
+
+impl Default for TrackView {
+    fn default() -> Self {
+        Self {
+            center: Vec2::ZERO,
+            scale: 1.0,
+        }
+    }
+}
+
+impl TrackView {
+    /// Size of the area the track is fitted into (pixels).
+    const FIT_SIZE: f32 = 800.0;
+
+    /// Fit the view to the first driver's first lap with telemetry.
+    pub fn from_session(session: &Session) -> Option<Self> {
+        let lap = session
+            .drivers
+            .first()
+            .and_then(|d| d.laps.iter().find(|l| !l.samples.is_empty()))?;
+
+        let min_x = lap.samples.iter().map(|s| s.position.x).fold(f32::INFINITY, f32::min);
+        let max_x = lap.samples.iter().map(|s| s.position.x).fold(f32::NEG_INFINITY, f32::max);
+        let min_z = lap.samples.iter().map(|s| s.position.z).fold(f32::INFINITY, f32::min);
+        let max_z = lap.samples.iter().map(|s| s.position.z).fold(f32::NEG_INFINITY, f32::max);
+
+        let width = max_x - min_x;
+        let height = max_z - min_z;
+        Some(Self {
+            center: Vec2::new(min_x + width / 2.0, min_z + height / 2.0),
+            scale: Self::FIT_SIZE / width.max(height).max(f32::EPSILON),
+        })
+    }
+
+    /// Project a track position to screen space (F1 X -> Bevy X, F1 Z -> Bevy Y).
+    pub fn project(&self, position: Vec3) -> Vec2 {
+        (Vec2::new(position.x, position.z) - self.center) * self.scale
+    }
+}
+
+/// Loaded sessions, e.g. qualifying and the race of one weekend.
 #[derive(Resource, Default)]
 pub struct SessionData {
-    pub session: Option<Session>,
+    pub sessions: SessionCollection,
+    /// Index of the session replayed in session-time mode
+    pub active: usize,
+    /// Lap classes and pit stops by session index, shared by the race
+    /// charts, overlays and exports
+    laps: Vec<SessionLaps>,
+    /// How each session was moved into the first session's frame on load
+    registrations: Vec<Option<Registration>>,
+}
+
+impl SessionData {
+    /// Add a loaded session, its laps and registration, replacing a session
+    /// with the same year, circuit and type; returns its index.
+    pub fn push(&mut self, loaded: LoadedSession) -> usize {
+        let index = self.sessions.push(loaded.session);
+        if index < self.laps.len() {
+            self.laps[index] = loaded.laps;
+            self.registrations[index] = loaded.registration;
+        } else {
+            self.laps.push(loaded.laps);
+            self.registrations.push(loaded.registration);
+        }
+        index
+    }
+
+    /// Remove every session.
+    pub fn clear(&mut self) {
+        self.sessions.clear();
+        self.laps.clear();
+        self.registrations.clear();
+    }
+
+    /// How a session was moved into the first session's frame, if it was
+    /// aligned when added; the lap picker shows its fit error.
+    pub fn registration(&self, index: usize) -> Option<&Registration> {
+        self.registrations.get(index)?.as_ref()
+    }
+
+    /// Lap classes and pit stops of a loaded session.
+    pub fn laps(&self, index: usize) -> Option<&SessionLaps> {
+        self.laps.get(index)
+    }
+
+    /// Lap classes and pit stops of the active session.
+    pub fn active_laps(&self) -> Option<&SessionLaps> {
+        self.laps(self.active)
+    }
+
+    /// The active session.
+    pub fn session(&self) -> Option<&Session> {
+        self.sessions.get(self.active)
+    }
+
+    /// A driver in the active session, or in any other loaded session.
+    pub fn driver(&self, code: &str) -> Option<&DriverSession> {
+        self.session()
+            .and_then(|s| s.get_driver(code))
+            .or_else(|| self.sessions.sessions().iter().find_map(|s| s.get_driver(code)))
+    }
+
+    /// Driver code, plus the session when several are loaded (e.g. "VER Race").
+    pub fn lap_label(&self, lap: &LapRef) -> String {
+        match self.sessions.short_label(lap.session) {
+            Some(session) if self.sessions.len() > 1 => format!("{} {}", lap.driver_code, session),
+            _ => lap.driver_code.clone(),
+        }
+    }
+}
+
+/// Lap classes and pit stops of one session.
+pub struct SessionLaps {
+    pub classes: LapClasses,
+    pub stops: PitStops,
+}
+
+impl SessionLaps {
+    /// Classify the laps and find the pit stops, which takes a moment on a
+    /// full race; done on the loading task.
+    pub fn analyze(session: &Session) -> Self {
+        let classes = classify::classify_session(session, &ClassifyConfig::default());
+        let stops = pit::detect_pit_stops(session, &classes, &PitConfig::default());
+        Self { classes, stops }
+    }
 }
 
 /// Metadata for a session file found on disk.
@@ -32,17 +175,31 @@ pub struct AvailableSessions {
     pub sessions: Vec<AvailableSession>,
 }
 
+/// Which clock the replay follows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReplayMode {
+    /// `current_time` is session time; one car per driver
+    #[default]
+    SessionTime,
+    /// `current_time` is time since lap start; one car per selected lap
+    LapRelative,
+}
+
 /// Replay control state.
 #[derive(Resource)]
 pub struct ReplayState {
-    /// Current playback time in seconds
+    /// Current playback time in seconds (session or lap-relative, see `mode`)
     pub current_time: f64,
     /// Playback speed multiplier (1.0 = real-time)
     pub speed: f32,
     /// Whether replay is playing or paused
     pub playing: bool,
-    /// Selected drivers to display
+    /// Selected drivers to display (all drivers if empty)
     pub selected_drivers: Vec<String>,
+    /// Clock used for playback
+    pub mode: ReplayMode,
+    /// Laps replayed side-by-side in lap-relative mode
+    pub lap_selections: Vec<LapRef>,
 }
 
 impl Default for ReplayState {
@@ -52,10 +209,79 @@ impl Default for ReplayState {
             speed: 1.0,
             playing: false,
             selected_drivers: Vec::new(),
+            mode: ReplayMode::SessionTime,
+            lap_selections: Vec::new(),
         }
     }
 }
 
+/// A lap in one of the loaded sessions.
+pub type LapRef = SessionLapRef;
+
+/// State of the lap picker panel.
+#[derive(Resource, Default)]
+pub struct LapPicker {
+    pub open: bool,
+    /// Driver whose laps are listed
+    pub driver: Option<String>,
+}
+
+/// Ghost car mode: lap-relative replay with live gaps to a reference lap.
+///
+/// The reference and comparison laps are the `ReplayState::lap_selections`.
+#[derive(Resource, Default)]
+pub struct GhostMode {
+    pub enabled: bool,
+    /// Lap the others are measured against, one of the picked laps (the
+    /// first unless another is marked in the lap picker)
+    pub reference: Option<LapRef>,
+}
+
+/// State of the stint chart.
+#[derive(Resource, Default)]
+pub struct StintChart {
+    pub open: bool,
+    /// Stints of the charted race, by its index in the loaded sessions
+    pub pace: Option<(usize, RacePace)>,
+}
+
+/// Which race history chart is shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryView {
+    /// Position at the end of each lap
+    Positions,
+    /// Time behind the leader at the end of each lap
+    Gaps,
+}
+
+/// State of the race history chart.
+#[derive(Resource, Default)]
+pub struct RaceHistoryChart {
+    /// Shown chart, `None` when closed
+    pub view: Option<HistoryView>,
+    /// History of the charted race, by its index in the loaded sessions
+    pub history: Option<(usize, RaceHistory)>,
+}
+
+/// State of the DRS and slipstream map overlay.
+#[derive(Resource, Default)]
+pub struct DrsMap {
+    pub open: bool,
+    /// Straights and gains in the active session, by its index in the loaded
+    /// sessions
+    pub analysis: Option<(usize, DrsAnalysis)>,
+}
+
+/// State of the overtake list.
+#[derive(Resource, Default)]
+pub struct OvertakeList {
+    pub open: bool,
+    /// Overtakes in the active session, by its index in the loaded sessions
+    pub overtakes: Option<(usize, Overtakes)>,
+    /// Overtake last jumped to
+    pub current: Option<usize>,
+}
+
 /// Turn definitions for the current circuit.
 #[derive(Resource, Default)]
 pub struct TurnData {
@@ -74,6 +300,34 @@ pub struct ComparisonDrivers {
     pub driver_codes: Vec<String>,
 }
 
+/// A session loaded in the background, with its laps analyzed.
+pub struct LoadedSession {
+    pub session: Session,
+    pub laps: SessionLaps,
+    /// Set when the session was aligned to the first loaded session
+    pub registration: Option<Registration>,
+}
+
+/// Background task loading a session file.
+#[derive(Resource)]
+pub struct SessionLoadTask {
+    pub task: Task<Result<LoadedSession, LoadError>>,
+    /// Shared with the loading thread for progress and cancellation
+    pub progress: Arc<LoadProgress>,
+    /// Display name of the session being loaded
+    pub name: String,
+    /// Add to the loaded sessions instead of replacing them
+    pub add: bool,
+}
+
+/// Error from the last failed session load, shown on the session select screen.
+#[derive(Resource, Default)]
+pub struct SessionLoadError(pub Option<String>);
+
 /// Event to trigger loading of a session file.
 #[derive(Event)]
-pub struct LoadSession(pub std::path::PathBuf);
+pub struct LoadSession {
+    pub path: std::path::PathBuf,
+    /// Add to the loaded sessions instead of replacing them
+    pub add: bool,
+}
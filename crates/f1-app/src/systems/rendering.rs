@@ -3,7 +3,7 @@
 use bevy::prelude::*;
 
 use crate::components::{Car, Trail};
-use crate::resources::SessionData;
+use crate::resources::{SessionData, TrackView};
 
 /// Update car trail positions.
 pub fn update_trails(mut query: Query<(&Transform, &mut Trail), With<Car>>) {
@@ -31,8 +31,8 @@ pub fn draw_trails(query: Query<&Trail, With<Car>>, mut gizmos: Gizmos) {
 }
 
 /// Draw the circuit track map using gizmos.
-pub fn draw_circuit(session_data: Res<SessionData>, mut gizmos: Gizmos) {
-    let Some(session) = &session_data.session else {
+pub fn draw_circuit(session_data: Res<SessionData>, track_view: Res<TrackView>, mut gizmos: Gizmos) {
+    let Some(session) = session_data.session() else {
         return;
     };
 
@@ -44,38 +44,36 @@ pub fn draw_circuit(session_data: Res<SessionData>, mut gizmos: Gizmos) {
         .and_then(|d| d.laps.iter().find(|l| !l.samples.is_empty()));
 
     if let Some(lap) = reference_lap {
-        // Calculate bounds to center and scale the track
-        // F1 coordinates: X, Z (horizontal plane), Y (elevation)
-        // Bevy 2D: X (right), Y (up)
-        // Transformation: F1 X -> Bevy X, F1 Z -> Bevy Y
         let points: Vec<Vec2> = lap
             .samples
             .iter()
-            .map(|s| Vec2::new(s.position.x, s.position.z)) // Use Z as Y for 2D top-down
+            .map(|s| track_view.project(s.position))
             .collect();
 
-        if points.is_empty() {
-            return;
+        for window in points.windows(2) {
+            gizmos.line_2d(window[0], window[1], Color::WHITE);
         }
+    }
+}
 
-        let min_x = points.iter().map(|p| p.x).fold(f32::INFINITY, f32::min);
-        let max_x = points.iter().map(|p| p.x).fold(f32::NEG_INFINITY, f32::max);
-        let min_y = points.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
-        let max_y = points.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max);
-
-        let width = max_x - min_x;
-        let height = max_y - min_y;
-        let center = Vec2::new(min_x + width / 2.0, min_y + height / 2.0);
-
-        // Scale to fit window (approx 800px height for 1080p, simplify to fixed scale for now or auto-fit)
-        // Let's assume a desired drawing area of ~800x800
-        let scale = 800.0 / width.max(height);
-
-        for window in points.windows(2) {
-            let p1 = (window[0] - center) * scale;
-            let p2 = (window[1] - center) * scale;
-            
-            gizmos.line_2d(p1, p2, Color::WHITE);
+/// Draw each car as a circle in its team color.
+pub fn draw_cars(
+    session_data: Res<SessionData>,
+    query: Query<(&Car, &Transform, &Visibility)>,
+    mut gizmos: Gizmos,
+) {
+    for (car, transform, visibility) in &query {
+        if *visibility == Visibility::Hidden {
+            continue;
         }
+        let color = session_data
+            .driver(&car.driver_code)
+            .map_or(Color::WHITE, |d| team_color(&d.driver.team_color));
+        gizmos.circle_2d(transform.translation.truncate(), 6.0, color);
     }
 }
+
+/// Parse a team color hex string (e.g. "#3671C6"), falling back to white.
+pub fn team_color(hex: &str) -> Color {
+    Srgba::hex(hex).map(Color::from).unwrap_or(Color::WHITE)
+}
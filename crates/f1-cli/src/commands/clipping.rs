@@ -0,0 +1,93 @@
+use std::process::ExitCode;
+
+use anyhow::Result;
+use f1_data::classify::{self, ClassifyConfig};
+use f1_data::clipping::{self, ClippingConfig};
+use f1_data::Session;
+
+use crate::table::Table;
+use crate::Format;
+
+/// Print each driver's clipping per straight, or with `laps` the time lost
+/// on every push lap and each clip.
+pub fn clipping(
+    session: &Session,
+    codes: &[String],
+    laps: bool,
+    config: &ClippingConfig,
+    format: Format,
+) -> Result<ExitCode> {
+    for code in codes {
+        super::driver(session, code)?;
+    }
+    let classes = classify::classify_session(session, &ClassifyConfig::default());
+    let mut analysis = clipping::analyze_clipping(session, &classes, config);
+    if !codes.is_empty() {
+        let wanted = |code: &str| codes.iter().any(|c| c.eq_ignore_ascii_case(code));
+        analysis.clips.retain(|c| wanted(&c.driver_code));
+        analysis.laps.retain(|l| wanted(&l.driver_code));
+    }
+    if let Some(format) = format.export() {
+        return super::print_export(&analysis, format);
+    }
+    if analysis.straights.is_empty() {
+        println!("No straights found.");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if laps {
+        let mut table = Table::new(["Driver", "Lap", "Clips", "Time lost"]);
+        for lap in &analysis.laps {
+            table.row([
+                lap.driver_code.clone(),
+                lap.lap_number.to_string(),
+                lap.clips.to_string(),
+                format!("{:.3}", lap.time_lost),
+            ]);
+        }
+        print!("{table}");
+        println!();
+
+        let mut table = Table::new([
+            "Driver", "Lap", "Straight", "Onset", "To end", "Duration", "Speed", "Lost",
+        ]);
+        for clip in &analysis.clips {
+            table.row([
+                clip.driver_code.clone(),
+                clip.lap_number.to_string(),
+                clip.straight.to_string(),
+                format!("{:.0} m", clip.onset_distance),
+                format!("{:.0} m", clip.distance_to_end),
+                format!("{:.2}", clip.duration),
+                format!("{:.0} -> {:.0}", clip.onset_speed, clip.end_speed),
+                format!("{:.3}", clip.time_lost),
+            ]);
+        }
+        print!("{table}");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    for straight in &analysis.straights {
+        println!(
+            "Straight {} ({:.0} m from {:.0} m)",
+            straight.number,
+            straight.length(),
+            straight.start_distance
+        );
+        let mut table = Table::new(["Driver", "Laps", "Clipped", "Lost/lap", "Onset"]);
+        for summary in analysis.straight_summary(straight.number) {
+            table.row([
+                summary.driver_code,
+                summary.laps.to_string(),
+                summary.clipped_laps.to_string(),
+                format!("{:.3}", summary.mean_time_lost),
+                summary
+                    .mean_onset_distance
+                    .map_or("-".into(), |d| format!("{d:.0} m")),
+            ]);
+        }
+        print!("{table}");
+        println!();
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,172 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::{bail, Context, Result};
+use f1_data::analysis::{SegmentTime, TurnComparison};
+use f1_data::smooth::Filter;
+use f1_data::{Lap, Session, SessionCollection, Turn, TurnSegment};
+
+use crate::table::Table;
+use crate::Format;
+
+/// Compare each driver's fastest lap through one turn.
+pub fn compare_turn(
+    session: &Session,
+    turns_path: &Path,
+    turn_name: &str,
+    codes: &[String],
+    smooth: Option<Filter>,
+    format: Format,
+) -> Result<ExitCode> {
+    let turns = super::load_turns(turns_path)?;
+    let turn = turns.get_turn(turn_name).with_context(|| {
+        let names: Vec<&str> = turns.turns.iter().map(|t| t.name.as_str()).collect();
+        format!(
+            "no turn {turn_name} in {} (turns: {})",
+            turns_path.display(),
+            names.join(", ")
+        )
+    })?;
+
+    let mut laps = Vec::with_capacity(codes.len());
+    for code in codes {
+        let driver = super::driver(session, code)?;
+        let lap = driver
+            .fastest_lap()
+            .with_context(|| format!("{} has no valid timed lap", driver.driver.code))?;
+        laps.push((driver.driver.code.as_str(), super::smoothed(lap, smooth)));
+    }
+
+    let laps: Vec<(&str, &Lap)> = laps.iter().map(|(code, lap)| (*code, &**lap)).collect();
+    let comparison = TurnComparison::compare_drivers(&laps, turn);
+    if comparison.analyses.is_empty() {
+        bail!("no lap passes through {turn_name}");
+    }
+    if let Some(format) = format.export() {
+        return super::print_export(&comparison, format);
+    }
+
+    println!("{} - fastest laps", comparison.turn_name);
+    print_comparison(&comparison);
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Compare a driver's fastest lap in each session through the turns.
+pub fn compare_sessions(
+    session: Session,
+    others: &[PathBuf],
+    turns_path: &Path,
+    turn_name: Option<&str>,
+    code: &str,
+    format: Format,
+) -> Result<ExitCode> {
+    let code = super::driver(&session, code)?.driver.code.clone();
+    let mut sessions = SessionCollection::new();
+    sessions.push(session);
+    for path in others {
+        sessions.push(super::load(path)?);
+    }
+
+    let turns = super::load_turns(turns_path)?;
+    let selected: Vec<&Turn> = match turn_name {
+        Some(name) => vec![turns
+            .get_turn(name)
+            .with_context(|| format!("no turn {name} in {}", turns_path.display()))?],
+        None => turns.turns.iter().collect(),
+    };
+    let export = format.export();
+    if export.is_some() && selected.len() != 1 {
+        bail!("--format other than table needs --turn");
+    }
+
+    let laps = sessions.fastest_laps(&code);
+    if laps.len() < 2 {
+        bail!(
+            "{code} has a valid timed lap in {} of {} sessions",
+            laps.len(),
+            sessions.len()
+        );
+    }
+
+    for (i, turn) in selected.into_iter().enumerate() {
+        let comparison = sessions.compare_turn(&laps, turn);
+        if let Some(format) = export {
+            return super::print_export(&comparison, format);
+        }
+        if i > 0 {
+            println!();
+        }
+        println!(
+            "{} - {code}'s fastest lap per session",
+            comparison.turn_name
+        );
+        if comparison.analyses.is_empty() {
+            println!("No lap passes through {}.", comparison.turn_name);
+            continue;
+        }
+        print_comparison(&comparison);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Print a comparison table with segment bests marked.
+fn print_comparison(comparison: &TurnComparison) {
+    let bests = [TurnSegment::Approach, TurnSegment::Apex, TurnSegment::Exit]
+        .map(|segment| comparison.segment_best(segment));
+    let mut headers = vec![
+        "Driver",
+        "Lap",
+        "Turn",
+        "Gap",
+        "Approach",
+        "Apex",
+        "Exit",
+        "Entry km/h",
+        "Min km/h",
+        "Exit km/h",
+    ];
+    let cross_session = comparison.is_cross_session();
+    if cross_session {
+        headers.insert(1, "Session");
+    }
+    let mut table = Table::new(headers);
+    let leader = comparison.analyses[0]
+        .total_time
+        .as_ref()
+        .map_or(0.0, |t| t.time);
+    for (i, analysis) in comparison.analyses.iter().enumerate() {
+        // Segment bests are marked with `*`
+        let segment = |segment: usize, time: &Option<SegmentTime>| {
+            let is_best = bests[segment].is_some_and(|best| std::ptr::eq(best, analysis));
+            let mark = if is_best { "*" } else { "" };
+            time.as_ref()
+                .map_or_else(|| "-".into(), |t| format!("{:.3}{mark}", t.time))
+        };
+        let total = analysis.total_time.as_ref();
+        let mut row = vec![
+            analysis.driver_code.clone().unwrap_or_default(),
+            analysis.lap_number.to_string(),
+            total.map_or_else(|| "-".into(), |t| format!("{:.3}", t.time)),
+            total
+                .filter(|_| i > 0)
+                .map_or_else(String::new, |t| format!("+{:.3}", t.time - leader)),
+            segment(0, &analysis.approach),
+            segment(1, &analysis.apex),
+            segment(2, &analysis.exit),
+            total.map_or_else(|| "-".into(), |t| t.entry_speed.to_string()),
+            total.map_or_else(|| "-".into(), |t| t.min_speed.to_string()),
+            total.map_or_else(|| "-".into(), |t| t.exit_speed.to_string()),
+        ];
+        if cross_session {
+            row.insert(1, analysis.session.clone().unwrap_or_default());
+        }
+        table.row(row);
+    }
+    print!("{table}");
+
+    if comparison.uniform_fastest {
+        println!("\nThe fastest lap is also fastest in every segment.");
+    } else {
+        println!("\nSegment bests are split between laps; compare sub-segments.");
+    }
+}
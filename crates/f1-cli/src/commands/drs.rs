@@ -0,0 +1,106 @@
+use std::process::ExitCode;
+
+use anyhow::Result;
+use f1_data::classify::{self, ClassifyConfig};
+use f1_data::drs::{self, DrsConfig};
+use f1_data::Session;
+
+use crate::table::Table;
+use crate::Format;
+
+/// Print the straights with the field's DRS and slipstream gains, then each
+/// driver's gains per straight.
+pub fn drs(
+    session: &Session,
+    codes: &[String],
+    config: &DrsConfig,
+    format: Format,
+) -> Result<ExitCode> {
+    for code in codes {
+        super::driver(session, code)?;
+    }
+    let classes = classify::classify_session(session, &ClassifyConfig::default());
+    let mut analysis = drs::analyze_drs(session, &classes, config);
+    if !codes.is_empty() {
+        analysis
+            .effects
+            .retain(|e| codes.iter().any(|c| c.eq_ignore_ascii_case(&e.driver_code)));
+    }
+    if let Some(format) = format.export() {
+        return super::print_export(&analysis, format);
+    }
+    if analysis.straights.is_empty() {
+        println!("No straights found.");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let mut table = Table::new([
+        "Straight",
+        "Start",
+        "Length",
+        "DRS",
+        "DRS km/h",
+        "DRS s",
+        "Tow km/h",
+        "Tow s",
+        "Both km/h",
+        "Both s",
+    ]);
+    for straight in &analysis.straights {
+        let (drs_speed, drs_time) = analysis.straight_drs_gain(straight.number);
+        let (tow_speed, tow_time) = analysis.straight_tow_gain(straight.number);
+        let (both_speed, both_time) = analysis.straight_drs_tow_gain(straight.number);
+        table.row([
+            straight.number.to_string(),
+            format!("{:.0} m", straight.start_distance),
+            format!("{:.0} m", straight.length()),
+            if straight.drs_zone { "zone" } else { "-" }.to_string(),
+            gain(drs_speed, 1),
+            gain(drs_time, 3),
+            gain(tow_speed, 1),
+            gain(tow_time, 3),
+            gain(both_speed, 1),
+            gain(both_time, 3),
+        ]);
+    }
+    print!("{table}");
+    println!();
+
+    let mut table = Table::new([
+        "Driver",
+        "Straight",
+        "DRS",
+        "Tow",
+        "Both",
+        "Clean",
+        "DRS km/h",
+        "DRS s",
+        "Tow km/h",
+        "Tow s",
+        "Both km/h",
+        "Both s",
+    ]);
+    for effect in &analysis.effects {
+        table.row([
+            effect.driver_code.clone(),
+            effect.straight.to_string(),
+            effect.drs_passes.to_string(),
+            effect.tow_passes.to_string(),
+            effect.drs_tow_passes.to_string(),
+            effect.clean_passes.to_string(),
+            gain(effect.drs_speed_gain, 1),
+            gain(effect.drs_time_gain, 3),
+            gain(effect.tow_speed_gain, 1),
+            gain(effect.tow_time_gain, 3),
+            gain(effect.drs_tow_speed_gain, 1),
+            gain(effect.drs_tow_time_gain, 3),
+        ]);
+    }
+    print!("{table}");
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Signed gain, `-` when there is nothing to compare.
+fn gain(value: Option<f64>, decimals: usize) -> String {
+    value.map_or_else(|| "-".into(), |v| format!("{v:+.decimals$}"))
+}
@@ -0,0 +1,59 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use f1_data::validate::{clean_session, CleanupMode, ValidationConfig};
+use f1_data::Session;
+
+use crate::Clean;
+
+/// Write the session as JSON, optionally filtered to some drivers and cleaned.
+pub fn export(
+    mut session: Session,
+    output: &Path,
+    codes: &[String],
+    clean: Option<Clean>,
+    pretty: bool,
+) -> Result<ExitCode> {
+    if !codes.is_empty() {
+        for code in codes {
+            super::driver(&session, code)?;
+        }
+        session
+            .drivers
+            .retain(|d| codes.iter().any(|c| c.eq_ignore_ascii_case(&d.driver.code)));
+    }
+
+    if let Some(clean) = clean {
+        let mode = match clean {
+            Clean::Repair => CleanupMode::Repair,
+            Clean::Exclude => CleanupMode::Exclude,
+        };
+        let cleanup = clean_session(&mut session, &ValidationConfig::default(), mode);
+        eprintln!(
+            "{} issues: {} laps repaired, {} laps excluded",
+            cleanup.report.issue_count(),
+            cleanup.repaired,
+            cleanup.excluded
+        );
+    }
+
+    let file = File::create(output).with_context(|| format!("creating {}", output.display()))?;
+    let mut writer = BufWriter::new(file);
+    if pretty {
+        serde_json::to_writer_pretty(&mut writer, &session)?;
+    } else {
+        serde_json::to_writer(&mut writer, &session)?;
+    }
+    writer.flush()?;
+
+    let laps: usize = session.drivers.iter().map(|d| d.laps.len()).sum();
+    eprintln!(
+        "Wrote {} ({} drivers, {laps} laps)",
+        output.display(),
+        session.drivers.len()
+    );
+    Ok(ExitCode::SUCCESS)
+}
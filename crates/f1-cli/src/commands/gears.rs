@@ -0,0 +1,130 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use f1_data::classify::{self, ClassifyConfig};
+use f1_data::gear::{self, LapGears, MAX_GEAR};
+use f1_data::Session;
+
+use crate::table::Table;
+use crate::Format;
+
+/// Share of time in each gear per driver and, with turns, the gear each
+/// driver takes every turn in. With a driver, list the shifts of one lap.
+pub fn gears(
+    session: &Session,
+    turns_path: Option<&Path>,
+    code: Option<&str>,
+    lap_number: Option<u32>,
+    format: Format,
+) -> Result<ExitCode> {
+    if let Some(code) = code {
+        let driver = super::driver(session, code)?;
+        let lap = match lap_number {
+            Some(number) => driver
+                .get_lap(number)
+                .with_context(|| format!("{} has no lap {number}", driver.driver.code))?,
+            None => driver
+                .fastest_lap()
+                .with_context(|| format!("{} has no valid timed lap", driver.driver.code))?,
+        };
+        let gears = LapGears::new(&driver.driver.code, lap)
+            .with_context(|| format!("lap {} has no telemetry", lap.number))?;
+        if let Some(format) = format.export() {
+            return super::print_export(&gears, format);
+        }
+        print_shifts(&gears);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let turns = turns_path.map(super::load_turns).transpose()?;
+    let classes = classify::classify_session(session, &ClassifyConfig::default());
+    let usage = gear::analyze_gears(session, &classes, turns.as_ref());
+    if let Some(format) = format.export() {
+        return super::print_export(&usage, format);
+    }
+
+    let mut headers = vec![
+        "Driver".to_string(),
+        "Laps".to_string(),
+        "Shifts/lap".to_string(),
+    ];
+    headers.extend((1..=MAX_GEAR).map(|g| g.to_string()));
+    let mut table = Table::new(headers);
+    for driver in &session.drivers {
+        let code = &driver.driver.code;
+        let Some(shares) = usage.gear_shares(code) else {
+            continue;
+        };
+        let laps: Vec<&LapGears> = usage
+            .laps
+            .iter()
+            .filter(|l| &l.driver_code == code)
+            .collect();
+        let shifts: usize = laps.iter().map(|l| l.shifts.len()).sum();
+        let mut row = vec![
+            code.clone(),
+            laps.len().to_string(),
+            format!("{:.1}", shifts as f64 / laps.len() as f64),
+        ];
+        row.extend(shares[1..].iter().map(|s| format!("{:.0}%", s * 100.0)));
+        table.row(row);
+    }
+    print!("{table}");
+
+    for turn in turns.iter().flat_map(|t| &t.turns) {
+        let summaries = usage.turn_summary(&turn.name);
+        if summaries.is_empty() {
+            continue;
+        }
+        println!();
+        println!("{}", turn.name);
+        let mut table = Table::new(["Driver", "Gear", "Laps by gear", "Min speed"]);
+        for summary in &summaries {
+            let by_gear: Vec<String> = summary
+                .gear_counts
+                .iter()
+                .enumerate()
+                .filter(|(_, &count)| count > 0)
+                .map(|(gear, count)| format!("{gear}:{count}"))
+                .collect();
+            table.row([
+                summary.driver_code.clone(),
+                summary.usual_gear().to_string(),
+                by_gear.join(" "),
+                format!("{:.0}", summary.mean_min_speed),
+            ]);
+        }
+        print!("{table}");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_shifts(gears: &LapGears) {
+    println!(
+        "{} lap {}: {} upshifts, {} downshifts",
+        gears.driver_code,
+        gears.lap_number,
+        gears.upshifts().count(),
+        gears.downshifts().count()
+    );
+    let mut table = Table::new(["Time", "Distance", "Shift", "Speed"]);
+    for shift in &gears.shifts {
+        table.row([
+            format!("{:.2}", shift.lap_time),
+            format!("{:.0} m", shift.distance),
+            format!("{} -> {}", shift.from, shift.to),
+            shift.speed.to_string(),
+        ]);
+    }
+    print!("{table}");
+    let time: Vec<String> = gears
+        .time_in_gear
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, &t)| t > 0.0)
+        .map(|(gear, t)| format!("{gear}: {t:.1} s"))
+        .collect();
+    println!("Time in gear: {}", time.join(", "));
+}
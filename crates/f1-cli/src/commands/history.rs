@@ -0,0 +1,72 @@
+use std::process::ExitCode;
+
+use anyhow::Result;
+use f1_data::classify::{self, ClassifyConfig};
+use f1_data::history::{DriverHistory, RaceHistory};
+use f1_data::pit::{self, PitConfig};
+use f1_data::Session;
+
+use crate::table::Table;
+use crate::Format;
+
+/// Print the lap chart: the running order at the end of every lap, or each
+/// driver's gap to the leader with `gaps`.
+pub fn history(session: &Session, gaps: bool, format: Format) -> Result<ExitCode> {
+    let classes = classify::classify_session(session, &ClassifyConfig::default());
+    let stops = pit::detect_pit_stops(session, &classes, &PitConfig::default());
+    let history = RaceHistory::new(session, &stops);
+    if let Some(format) = format.export() {
+        return super::print_export(&history, format);
+    }
+
+    let laps = history.lap_count();
+    let table = if gaps {
+        let mut headers = vec!["Driver".to_string()];
+        headers.extend((1..=laps).map(|lap| format!("L{lap}")));
+        let mut table = Table::new(headers);
+        // Classified order: most laps completed, then race time
+        let mut drivers: Vec<_> = history.drivers.iter().collect();
+        drivers.sort_by(|a, b| {
+            let last = |d: &&DriverHistory| d.laps.last().map(|l| (l.lap_number, l.race_time));
+            let (a, b) = (last(a), last(b));
+            b.map(|l| l.0)
+                .cmp(&a.map(|l| l.0))
+                .then_with(|| a.map_or(0.0, |l| l.1).total_cmp(&b.map_or(0.0, |l| l.1)))
+        });
+        for driver in drivers {
+            let mut row = vec![driver.driver_code.clone()];
+            row.extend((1..=laps).map(|lap| {
+                driver.lap(lap).map_or_else(String::new, |l| {
+                    let mark = if l.pitted { "*" } else { "" };
+                    format!("{:.1}{mark}", l.gap)
+                })
+            }));
+            table.row(row);
+        }
+        table
+    } else {
+        let positions = history.standings(1).len();
+        let mut headers = vec!["Lap".to_string()];
+        headers.extend((1..=positions).map(|p| format!("P{p}")));
+        let mut table = Table::new(headers);
+        for lap in 1..=laps {
+            let mut row = vec![lap.to_string()];
+            row.extend(history.standings(lap).into_iter().map(|(code, l)| {
+                let mark = if l.pitted { "*" } else { "" };
+                format!("{code}{mark}")
+            }));
+            table.row(row);
+        }
+        table
+    };
+    print!("{table}");
+    if history
+        .drivers
+        .iter()
+        .flat_map(|d| &d.laps)
+        .any(|l| l.pitted)
+    {
+        println!("\n* pitted on this lap");
+    }
+    Ok(ExitCode::SUCCESS)
+}
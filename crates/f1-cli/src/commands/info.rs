@@ -0,0 +1,49 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use f1_data::lazy::LazySession;
+
+use crate::table::{self, Table};
+
+/// Print the session header and a fastest-lap ranking.
+///
+/// Only lap summaries are needed, so the file is indexed lazily rather than
+/// loaded with its telemetry.
+pub fn info(path: &Path) -> Result<ExitCode> {
+    let session = LazySession::open(path).with_context(|| format!("loading {}", path.display()))?;
+    let info = &session.info;
+    let laps: usize = session.drivers.iter().map(|d| d.laps.len()).sum();
+    println!(
+        "{} {} ({}) - {}",
+        info.year,
+        info.circuit,
+        info.circuit_short,
+        info.session_type.as_str()
+    );
+    println!("Date:    {}", info.date.format("%Y-%m-%d %H:%M UTC"));
+    println!("Drivers: {}", session.drivers.len());
+    println!("Laps:    {laps}");
+    println!();
+
+    let fastest = session.fastest_laps();
+    let best = fastest.first().and_then(|(_, lap)| lap.lap_time);
+    let mut table = Table::new(["Pos", "Driver", "Team", "Lap", "Time", "Gap"]);
+    for (i, (driver, lap)) in fastest.iter().enumerate() {
+        let gap = match (i, lap.lap_time, best) {
+            (0, _, _) => String::new(),
+            (_, Some(time), Some(best)) => format!("+{:.3}", time - best),
+            _ => "-".into(),
+        };
+        table.row([
+            (i + 1).to_string(),
+            driver.code.clone(),
+            driver.team.clone(),
+            lap.number.to_string(),
+            table::lap_time(lap.lap_time),
+            gap,
+        ]);
+    }
+    print!("{table}");
+    Ok(ExitCode::SUCCESS)
+}
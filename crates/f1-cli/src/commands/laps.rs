@@ -0,0 +1,48 @@
+use std::process::ExitCode;
+
+use anyhow::Result;
+use f1_data::classify::{self, ClassifyConfig};
+use f1_data::export::LapTable;
+use f1_data::Session;
+
+use crate::table::{self, Table};
+use crate::Format;
+
+/// Print every lap of a driver with sector times and lap class.
+pub fn laps(session: &Session, code: &str, format: Format) -> Result<ExitCode> {
+    let driver = super::driver(session, code)?;
+    let classes = classify::classify_session(session, &ClassifyConfig::default());
+    if let Some(format) = format.export() {
+        return super::print_export(&LapTable::classified(driver, &classes), format);
+    }
+    let fastest = driver.fastest_lap().map(|l| l.number);
+
+    println!("{} - {}", driver.driver.code, driver.driver.name);
+    let mut table = Table::new([
+        "Lap", "Time", "S1", "S2", "S3", "Valid", "Class", "Samples", "",
+    ]);
+    for lap in &driver.laps {
+        let [s1, s2, s3] = lap.sector_times.map(table::seconds);
+        table.row([
+            lap.number.to_string(),
+            table::lap_time(lap.lap_time),
+            s1,
+            s2,
+            s3,
+            if lap.is_valid { "yes" } else { "no" }.into(),
+            classes
+                .get(&driver.driver.code, lap.number)
+                .map_or("-", |c| c.as_str())
+                .into(),
+            lap.samples.len().to_string(),
+            if fastest == Some(lap.number) {
+                "fastest"
+            } else {
+                ""
+            }
+            .into(),
+        ]);
+    }
+    print!("{table}");
+    Ok(ExitCode::SUCCESS)
+}
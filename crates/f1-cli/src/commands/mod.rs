@@ -0,0 +1,86 @@
+//! Subcommand implementations.
+
+mod clipping;
+mod compare;
+mod drs;
+mod export;
+mod gears;
+mod history;
+mod info;
+mod laps;
+mod overtakes;
+mod pits;
+mod register;
+mod render;
+mod report;
+mod stints;
+mod traps;
+mod validate;
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use f1_data::export::{ExportFormat, Exportable};
+use f1_data::smooth::{self, Filter};
+use f1_data::{loader, CircuitTurns, DriverSession, Lap, Session};
+
+pub use clipping::clipping;
+pub use compare::{compare_sessions, compare_turn};
+pub use drs::drs;
+pub use export::export;
+pub use gears::gears;
+pub use history::history;
+pub use info::info;
+pub use laps::laps;
+pub use overtakes::overtakes;
+pub use pits::pits;
+pub use register::register;
+pub use render::render;
+pub use report::report;
+pub use stints::stints;
+pub use traps::speed_traps;
+pub use validate::validate;
+
+/// Load a session through the binary cache.
+pub fn load(path: &Path) -> Result<Session> {
+    loader::load_session_cached(path).with_context(|| format!("loading {}", path.display()))
+}
+
+/// Load turn definitions (`CircuitTurns` JSON).
+fn load_turns(path: &Path) -> Result<CircuitTurns> {
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    serde_json::from_str(&contents).with_context(|| format!("parsing {}", path.display()))
+}
+
+/// Look up a driver, listing the available codes if it doesn't exist.
+fn driver<'a>(session: &'a Session, code: &str) -> Result<&'a DriverSession> {
+    let code = code.to_uppercase();
+    session.get_driver(&code).with_context(|| {
+        let codes: Vec<&str> = session
+            .drivers
+            .iter()
+            .map(|d| d.driver.code.as_str())
+            .collect();
+        format!(
+            "no driver {code} in session (drivers: {})",
+            codes.join(", ")
+        )
+    })
+}
+
+/// A lap smoothed with `filter`, or the lap itself without one.
+fn smoothed(lap: &Lap, filter: Option<Filter>) -> Cow<'_, Lap> {
+    match filter {
+        Some(filter) => Cow::Owned(smooth::smooth_lap(lap, &filter)),
+        None => Cow::Borrowed(lap),
+    }
+}
+
+/// Print an analysis result to stdout in an export format.
+fn print_export(value: &impl Exportable, format: ExportFormat) -> Result<ExitCode> {
+    f1_data::export::export(value, format, std::io::stdout().lock())?;
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,80 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use anyhow::Result;
+use f1_data::classify::{self, ClassifyConfig};
+use f1_data::history::RaceHistory;
+use f1_data::overtake::{self, OvertakeConfig};
+use f1_data::pit::{self, PitConfig};
+use f1_data::Session;
+
+use crate::table::{self, Table};
+use crate::Format;
+
+/// Print every on-track pass, or with `battles` the battles between cars
+/// running together.
+pub fn overtakes(
+    session: &Session,
+    turns_path: Option<&Path>,
+    battles: bool,
+    format: Format,
+) -> Result<ExitCode> {
+    let turns = turns_path.map(super::load_turns).transpose()?;
+    let config = OvertakeConfig::default();
+    let classes = classify::classify_session(session, &ClassifyConfig::default());
+    let stops = pit::detect_pit_stops(session, &classes, &PitConfig::default());
+    let overtakes = overtake::detect_overtakes(session, &stops, turns.as_ref(), &config);
+
+    if battles {
+        let history = RaceHistory::new(session, &stops);
+        let battles = overtake::detect_battles(&history, &overtakes, &config);
+        if let Some(format) = format.export() {
+            return super::print_export(&battles, format);
+        }
+        if battles.battles.is_empty() {
+            println!("No battles found.");
+            return Ok(ExitCode::SUCCESS);
+        }
+        let mut table = Table::new(["Driver", "Rival", "Laps", "Min gap", "Overtakes"]);
+        for battle in &battles.battles {
+            table.row([
+                battle.driver_code.clone(),
+                battle.rival_code.clone(),
+                format!("{}-{}", battle.start_lap, battle.end_lap),
+                format!("{:.3}", battle.min_gap),
+                battle.overtakes.to_string(),
+            ]);
+        }
+        print!("{table}");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(format) = format.export() {
+        return super::print_export(&overtakes, format);
+    }
+    if overtakes.overtakes.is_empty() {
+        println!("No overtakes found.");
+        return Ok(ExitCode::SUCCESS);
+    }
+    let mut table = Table::new(["Time", "Lap", "Driver", "Passed", "Turn"]);
+    for pass in &overtakes.overtakes {
+        table.row([
+            table::seconds(Some(pass.session_time)),
+            pass.lap_number.to_string(),
+            pass.driver_code.clone(),
+            pass.passed_code.clone(),
+            pass.turn.clone().unwrap_or_else(|| "-".into()),
+        ]);
+    }
+    print!("{table}");
+
+    if turns.is_some() {
+        println!();
+        let mut table = Table::new(["Turn", "Overtakes"]);
+        for (turn, count) in overtakes.by_turn() {
+            table.row([turn.unwrap_or("-").to_string(), count.to_string()]);
+        }
+        print!("{table}");
+    }
+    Ok(ExitCode::SUCCESS)
+}
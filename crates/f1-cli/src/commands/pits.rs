@@ -0,0 +1,36 @@
+use std::process::ExitCode;
+
+use anyhow::Result;
+use f1_data::classify::{self, ClassifyConfig};
+use f1_data::pit::{self, PitConfig};
+use f1_data::Session;
+
+use crate::table::{self, Table};
+use crate::Format;
+
+/// Print every detected pit stop with pit-lane time and time lost.
+pub fn pits(session: &Session, format: Format) -> Result<ExitCode> {
+    let classes = classify::classify_session(session, &ClassifyConfig::default());
+    let stops = pit::detect_pit_stops(session, &classes, &PitConfig::default());
+    if let Some(format) = format.export() {
+        return super::print_export(&stops, format);
+    }
+    if stops.is_empty() {
+        println!("No pit stops found.");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let mut table = Table::new(["Driver", "Lap", "Entry", "Pit lane", "Stationary", "Loss"]);
+    for stop in &stops.stops {
+        table.row([
+            stop.driver_code.clone(),
+            stop.lap_number.to_string(),
+            table::seconds(Some(stop.entry_time)),
+            table::seconds(Some(stop.pit_lane_time())),
+            format!("{:.1}", stop.stationary_time),
+            table::seconds(stop.pit_loss),
+        ]);
+    }
+    print!("{table}");
+    Ok(ExitCode::SUCCESS)
+}
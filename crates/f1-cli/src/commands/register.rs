@@ -0,0 +1,51 @@
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use f1_data::registration::{self, RegistrationConfig};
+use f1_data::Session;
+
+/// Fit the transform from this session's frame into a reference session's,
+/// optionally saving it and moving a turn file across.
+pub fn register(
+    session: &Session,
+    reference_path: &Path,
+    output: Option<&Path>,
+    turns_path: Option<&Path>,
+    turns_output: Option<&Path>,
+) -> Result<ExitCode> {
+    let reference = super::load(reference_path)?;
+    let registration =
+        registration::register_sessions(session, &reference, &RegistrationConfig::default())
+            .context("both sessions need a valid timed lap with telemetry")?;
+    let transform = registration.transform;
+    println!("{} -> {}", session.label(), reference.label());
+    println!(
+        "Rotation {:+.3}°, translation ({:+.2}, {:+.2}) m",
+        transform.rotation.to_degrees(),
+        transform.translation.x,
+        transform.translation.y
+    );
+    println!(
+        "RMS error {:.2} m after {} iterations",
+        registration.rms_error, registration.iterations
+    );
+
+    if let Some(output) = output {
+        let json = serde_json::to_string_pretty(&registration)?;
+        fs::write(output, json).with_context(|| format!("writing {}", output.display()))?;
+        println!("Wrote {}", output.display());
+    }
+
+    // Turns authored on the reference, moved into this session's frame
+    if let (Some(turns_path), Some(turns_output)) = (turns_path, turns_output) {
+        let mut turns = super::load_turns(turns_path)?;
+        transform.inverse().apply_to_turns(&mut turns);
+        let json = serde_json::to_string_pretty(&turns)?;
+        fs::write(turns_output, json)
+            .with_context(|| format!("writing {}", turns_output.display()))?;
+        println!("Wrote {}", turns_output.display());
+    }
+    Ok(ExitCode::SUCCESS)
+}
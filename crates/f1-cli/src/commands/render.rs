@@ -0,0 +1,45 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use f1_data::render::{self, RenderOptions};
+use f1_data::smooth::Filter;
+use f1_data::Session;
+
+/// Draw one lap and the turn boxes to an image.
+pub fn render(
+    session: &Session,
+    output: &Path,
+    turns_path: Option<&Path>,
+    code: Option<&str>,
+    lap_number: Option<u32>,
+    smooth: Option<Filter>,
+    options: &RenderOptions,
+) -> Result<ExitCode> {
+    let turns = turns_path.map(super::load_turns).transpose()?;
+    let (code, lap) = match code {
+        Some(code) => {
+            let driver = super::driver(session, code)?;
+            let lap = match lap_number {
+                Some(number) => driver
+                    .get_lap(number)
+                    .with_context(|| format!("{} has no lap {number}", driver.driver.code))?,
+                None => driver
+                    .fastest_lap()
+                    .with_context(|| format!("{} has no valid timed lap", driver.driver.code))?,
+            };
+            (driver.driver.code.as_str(), lap)
+        }
+        None => session
+            .fastest_laps()
+            .first()
+            .map(|(driver, lap)| (driver.code.as_str(), *lap))
+            .context("session has no valid timed lap")?,
+    };
+
+    let lap = super::smoothed(lap, smooth);
+    render::render_to_file(output, &lap, turns.as_ref(), options)
+        .with_context(|| format!("rendering {}", output.display()))?;
+    println!("Wrote {} ({code} lap {})", output.display(), lap.number);
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,30 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use f1_data::report::{self, ReportConfig};
+use f1_data::Session;
+
+/// Write an HTML report of the session.
+pub fn report(
+    session: &Session,
+    output: &Path,
+    turns_path: Option<&Path>,
+    drivers: Vec<String>,
+    mini_sectors: usize,
+) -> Result<ExitCode> {
+    let turns = turns_path.map(super::load_turns).transpose()?;
+    for code in &drivers {
+        super::driver(session, code)?;
+    }
+
+    let config = ReportConfig {
+        drivers,
+        mini_sectors,
+        ..ReportConfig::default()
+    };
+    report::write_session_report(output, session, turns.as_ref(), &config)
+        .with_context(|| format!("writing {}", output.display()))?;
+    println!("Wrote {}", output.display());
+    Ok(ExitCode::SUCCESS)
+}
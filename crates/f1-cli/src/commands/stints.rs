@@ -0,0 +1,60 @@
+use std::process::ExitCode;
+
+use anyhow::Result;
+use f1_data::classify::{self, ClassifyConfig};
+use f1_data::pit::{self, PitConfig};
+use f1_data::stint::{self, StintConfig};
+use f1_data::Session;
+
+use crate::table::{self, Table};
+use crate::Format;
+
+/// Print each driver's stints with median, fuel-corrected pace and
+/// degradation.
+pub fn stints(
+    session: &Session,
+    codes: &[String],
+    config: &StintConfig,
+    format: Format,
+) -> Result<ExitCode> {
+    for code in codes {
+        super::driver(session, code)?;
+    }
+    let classes = classify::classify_session(session, &ClassifyConfig::default());
+    let stops = pit::detect_pit_stops(session, &classes, &PitConfig::default());
+    let mut pace = stint::race_pace(session, &classes, &stops, config);
+    if !codes.is_empty() {
+        pace.stints
+            .retain(|s| codes.iter().any(|c| c.eq_ignore_ascii_case(&s.driver_code)));
+    }
+    if let Some(format) = format.export() {
+        return super::print_export(&pace, format);
+    }
+
+    let mut table = Table::new([
+        "Driver",
+        "Stint",
+        "Laps",
+        "Push",
+        "Median",
+        "Fuel-corr.",
+        "Std dev",
+        "Deg s/lap",
+    ]);
+    for stint in &pace.stints {
+        table.row([
+            stint.driver_code.clone(),
+            stint.number.to_string(),
+            format!("{}-{}", stint.start_lap, stint.end_lap),
+            stint.laps.len().to_string(),
+            table::lap_time(stint.median_lap_time),
+            table::lap_time(stint.fuel_corrected_median),
+            table::seconds(stint.std_dev),
+            stint
+                .degradation
+                .map_or_else(|| "-".into(), |d| format!("{d:+.3}")),
+        ]);
+    }
+    print!("{table}");
+    Ok(ExitCode::SUCCESS)
+}
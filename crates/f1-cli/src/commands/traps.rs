@@ -0,0 +1,113 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use anyhow::Result;
+use f1_data::classify::{self, ClassifyConfig, LapClass};
+use f1_data::trap::{self, SpeedRank, TrapConfig};
+use f1_data::Session;
+
+use crate::table::Table;
+use crate::Format;
+
+/// Rank drivers at each speed trap of the turn file and on each straight
+/// over their push laps (or every lap), or with `laps` print every lap's
+/// speeds.
+pub fn speed_traps(
+    session: &Session,
+    turns_path: Option<&Path>,
+    laps: bool,
+    all_laps: bool,
+    by_distance: bool,
+    format: Format,
+) -> Result<ExitCode> {
+    let turns = turns_path.map(super::load_turns).transpose()?;
+    let gates = turns.map(|t| t.speed_traps).unwrap_or_default();
+    let classes = classify::classify_session(session, &ClassifyConfig::default());
+    let traps = trap::measure_speed_traps(session, &classes, &gates, &TrapConfig::default());
+    if let Some(format) = format.export() {
+        return super::print_export(&traps, format);
+    }
+
+    if laps {
+        let mut headers = vec!["Driver".to_string(), "Lap".to_string(), "Class".to_string()];
+        headers.extend(traps.traps.iter().cloned());
+        headers.extend(traps.straights.iter().map(|s| format!("S{}", s.number)));
+        let mut table = Table::new(headers);
+        for driver in &session.drivers {
+            let code = &driver.driver.code;
+            for lap in &driver.laps {
+                let is_lap = |c: &str, n: u32| c == code && n == lap.number;
+                let class = classes.get(code, lap.number).map_or("-", |c| c.as_str());
+                let mut row = vec![code.clone(), lap.number.to_string(), class.to_string()];
+                let mut measured = false;
+                for name in &traps.traps {
+                    let speed = traps
+                        .trap_speeds
+                        .iter()
+                        .find(|s| is_lap(&s.driver_code, s.lap_number) && &s.trap == name);
+                    measured |= speed.is_some();
+                    row.push(speed.map_or("-".into(), |s| format!("{:.0}", s.speed)));
+                }
+                for straight in &traps.straights {
+                    let speed = traps.straight_speeds.iter().find(|s| {
+                        is_lap(&s.driver_code, s.lap_number) && s.straight == straight.number
+                    });
+                    measured |= speed.is_some();
+                    row.push(speed.map_or("-".into(), |s| {
+                        format!("{:.0} @{:.0} m", s.top_speed, s.distance_to_top)
+                    }));
+                }
+                if measured {
+                    table.row(row);
+                }
+            }
+        }
+        print!("{table}");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let ranked: &[LapClass] = if all_laps { &[] } else { &[LapClass::Push] };
+    if traps.traps.is_empty() && traps.straights.is_empty() {
+        println!("No speed traps or straights found.");
+        return Ok(ExitCode::SUCCESS);
+    }
+    for name in &traps.traps {
+        println!("{name}");
+        print_ranking(&traps.trap_ranking(name, ranked));
+        println!();
+    }
+    for straight in &traps.straights {
+        println!(
+            "Straight {} ({:.0} m from {:.0} m)",
+            straight.number,
+            straight.length(),
+            straight.start_distance
+        );
+        let mut ranking = traps.straight_ranking(straight.number, ranked);
+        if by_distance {
+            ranking.sort_by(|a, b| {
+                let distance = |r: &SpeedRank| r.distance_to_top.unwrap_or(f32::INFINITY);
+                distance(a).total_cmp(&distance(b))
+            });
+        }
+        print_ranking(&ranking);
+        println!();
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_ranking(ranking: &[SpeedRank]) {
+    let mut table = Table::new(["#", "Driver", "Best", "Mean", "Laps", "To top"]);
+    for (position, rank) in ranking.iter().enumerate() {
+        table.row([
+            (position + 1).to_string(),
+            rank.driver_code.clone(),
+            format!("{:.1}", rank.best),
+            format!("{:.1}", rank.mean),
+            rank.laps.to_string(),
+            rank.distance_to_top
+                .map_or("-".into(), |d| format!("{d:.0} m")),
+        ]);
+    }
+    print!("{table}");
+}
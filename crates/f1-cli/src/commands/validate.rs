@@ -0,0 +1,66 @@
+use std::process::ExitCode;
+
+use anyhow::Result;
+use f1_data::validate::{validate_session, ValidationConfig};
+use f1_data::Session;
+
+use crate::table::Table;
+
+/// Print the data-quality report. Fails if any issue is found.
+pub fn validate(session: &Session, verbose: bool, json: bool) -> Result<ExitCode> {
+    let report = validate_session(session, &ValidationConfig::default());
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if verbose {
+        for (code, lap) in report.laps() {
+            println!(
+                "{code} lap {} ({} samples)",
+                lap.lap_number, lap.sample_count
+            );
+            for issue in &lap.issues {
+                match issue.sample_index {
+                    Some(i) => println!("  #{i:<5} {}", issue.kind),
+                    None => println!("  {}", issue.kind),
+                }
+            }
+        }
+    } else if !report.is_clean() {
+        let mut table = Table::new([
+            "Driver",
+            "Lap",
+            "Samples",
+            "Issues",
+            "Repairable",
+            "First issue",
+        ]);
+        for (code, lap) in report.laps() {
+            table.row([
+                code.to_string(),
+                lap.lap_number.to_string(),
+                lap.sample_count.to_string(),
+                lap.issues.len().to_string(),
+                if lap.is_repairable() { "yes" } else { "no" }.into(),
+                lap.issues
+                    .first()
+                    .map(|i| i.kind.to_string())
+                    .unwrap_or_default(),
+            ]);
+        }
+        print!("{table}");
+    }
+
+    if !json {
+        let affected = report.laps().count();
+        println!(
+            "{} issues in {affected} of {} laps",
+            report.issue_count(),
+            report.laps_checked
+        );
+    }
+    Ok(if report.is_clean() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
@@ -0,0 +1,577 @@
+//! F1 Purple Line CLI - session analysis without the Bevy app
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use f1_data::clipping::ClippingConfig;
+use f1_data::drs::DrsConfig;
+use f1_data::export::ExportFormat;
+use f1_data::render::{LineColor, RenderOptions};
+use f1_data::smooth::{Filter, KalmanConfig};
+use f1_data::stint::StintConfig;
+use f1_data::Channel;
+
+mod commands;
+mod table;
+
+#[derive(Parser)]
+#[command(version, about = "Analyze exported F1 sessions from the command line")]
+struct Cli {
+    /// Session JSON exported by tools/fetch_session.py
+    #[arg(short, long)]
+    session: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Session summary and each driver's fastest lap
+    Info,
+    /// Lap table for one driver
+    Laps {
+        /// Driver code (e.g. VER)
+        driver: String,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Pit stops found in the telemetry, with time lost in the pit lane
+    Pits {
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Running order at the end of every lap (lap chart)
+    History {
+        /// Show each driver's gap to the leader instead of positions
+        #[arg(long)]
+        gaps: bool,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// On-track passes, or battles between cars running together
+    Overtakes {
+        /// Turn definitions (`CircuitTurns` JSON) to name the turn of each
+        /// pass
+        #[arg(long)]
+        turns: Option<PathBuf>,
+        /// List battles (cars within a second for several laps) instead
+        #[arg(long)]
+        battles: bool,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Stints between tyre stops with race pace and degradation
+    Stints {
+        /// Only these drivers (comma-separated codes)
+        #[arg(long, value_delimiter = ',')]
+        drivers: Vec<String>,
+        /// Fuel burned per lap (kg)
+        #[arg(long, default_value_t = StintConfig::default().fuel_per_lap)]
+        fuel_per_lap: f64,
+        /// Lap time cost of fuel (s/kg)
+        #[arg(long, default_value_t = StintConfig::default().time_per_kg)]
+        time_per_kg: f64,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Speed-trap speeds and top speed per straight, ranked across drivers
+    SpeedTraps {
+        /// Turn definitions (`CircuitTurns` JSON) with the trap gates
+        #[arg(long)]
+        turns: Option<PathBuf>,
+        /// Print every lap's speeds instead of rankings
+        #[arg(long)]
+        laps: bool,
+        /// Rank every lap with telemetry instead of push laps only
+        #[arg(long)]
+        all_laps: bool,
+        /// Rank straights by distance to top speed instead of top speed
+        #[arg(long)]
+        by_distance: bool,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Straights where cars run out of ERS deployment at full throttle, with
+    /// the time lost
+    Clipping {
+        /// Only these drivers (comma-separated codes)
+        #[arg(long, value_delimiter = ',')]
+        drivers: Vec<String>,
+        /// Print every push lap's time lost and each clip
+        #[arg(long)]
+        laps: bool,
+        /// Closest a clip may start to the end of the straight (m)
+        #[arg(long, default_value_t = ClippingConfig::default().brake_margin)]
+        brake_margin: f32,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Time in each gear, the gear taken through each turn, or the shifts
+    /// of one lap
+    Gears {
+        /// Turn definitions (`CircuitTurns` JSON) to compare gears per turn
+        #[arg(long)]
+        turns: Option<PathBuf>,
+        /// List this driver's shifts
+        #[arg(long)]
+        driver: Option<String>,
+        /// Lap number (default: the driver's fastest lap)
+        #[arg(long, requires = "driver")]
+        lap: Option<u32>,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Top speed and time gained on each straight with DRS and in a tow
+    Drs {
+        /// Only these drivers (comma-separated codes)
+        #[arg(long, value_delimiter = ',')]
+        drivers: Vec<String>,
+        /// Largest distance behind another car that counts as a tow (m)
+        #[arg(long, default_value_t = DrsConfig::default().tow_distance)]
+        tow_distance: f32,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Compare drivers' fastest laps through a turn
+    CompareTurn {
+        /// Turn definitions (`CircuitTurns` JSON)
+        #[arg(long)]
+        turns: PathBuf,
+        /// Turn name (e.g. T1)
+        #[arg(long)]
+        turn: String,
+        /// Comma-separated driver codes
+        #[arg(long, value_delimiter = ',', required = true)]
+        drivers: Vec<String>,
+        /// Smooth the laps before comparing (steadier minimum speeds)
+        #[arg(long, value_enum)]
+        smooth: Option<SmoothArg>,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Compare a driver's fastest lap in this and other sessions through turns
+    CompareSessions {
+        /// Other sessions (e.g. the race of the same weekend, or another year)
+        #[arg(long, value_delimiter = ',', required = true)]
+        with: Vec<PathBuf>,
+        /// Driver code (e.g. VER)
+        #[arg(long)]
+        driver: String,
+        /// Turn definitions (`CircuitTurns` JSON)
+        #[arg(long)]
+        turns: PathBuf,
+        /// Turn name (default: every turn)
+        #[arg(long)]
+        turn: Option<String>,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Write a self-contained HTML report for reading offline
+    Report {
+        /// Output file
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Turn definitions (`CircuitTurns` JSON) for the map and turn tables
+        #[arg(long)]
+        turns: Option<PathBuf>,
+        /// Only include these drivers (comma-separated codes)
+        #[arg(long, value_delimiter = ',')]
+        drivers: Vec<String>,
+        /// Number of mini-sectors on the track map
+        #[arg(long, default_value_t = 25)]
+        mini_sectors: usize,
+    },
+    /// Draw the track and turn boxes to an SVG or PNG image
+    Render {
+        /// Output file (.svg or .png)
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Turn definitions (`CircuitTurns` JSON) to overlay
+        #[arg(long)]
+        turns: Option<PathBuf>,
+        /// Draw this driver's lap (default: the session's fastest lap)
+        #[arg(long)]
+        driver: Option<String>,
+        /// Lap number (default: the driver's fastest lap)
+        #[arg(long, requires = "driver")]
+        lap: Option<u32>,
+        /// Color the line by a channel
+        #[arg(long, value_enum)]
+        channel: Option<ChannelArg>,
+        /// Smooth the lap before drawing (steadier curvature and lateral g)
+        #[arg(long, value_enum)]
+        smooth: Option<SmoothArg>,
+        /// Image width in pixels
+        #[arg(long, default_value_t = 1200)]
+        width: u32,
+    },
+    /// Align this session's coordinates to a reference session (e.g. last year)
+    Register {
+        /// Session whose coordinate frame to align to
+        #[arg(long)]
+        reference: PathBuf,
+        /// Save the fitted transform as JSON
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Turn definitions authored on the reference session
+        #[arg(long, requires = "turns_output")]
+        turns: Option<PathBuf>,
+        /// Write the turns moved into this session's frame
+        #[arg(long, requires = "turns")]
+        turns_output: Option<PathBuf>,
+    },
+    /// Report data-quality issues; exits non-zero if any are found
+    Validate {
+        /// List every issue instead of a count per lap
+        #[arg(short, long)]
+        verbose: bool,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write the session back out as current-schema JSON
+    Export {
+        /// Output file
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Only include these drivers (comma-separated codes)
+        #[arg(long, value_delimiter = ',')]
+        drivers: Vec<String>,
+        /// Repair or drop laps with data-quality issues first
+        #[arg(long, value_enum)]
+        clean: Option<Clean>,
+        /// Indent the JSON
+        #[arg(long)]
+        pretty: bool,
+    },
+}
+
+/// Output format for result tables.
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum Format {
+    /// Aligned plain-text table
+    #[default]
+    Table,
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl Format {
+    /// The library export format, `None` for the plain-text table.
+    fn export(self) -> Option<ExportFormat> {
+        match self {
+            Format::Table => None,
+            Format::Csv => Some(ExportFormat::Csv),
+            Format::Json => Some(ExportFormat::Json),
+            Format::Markdown => Some(ExportFormat::Markdown),
+        }
+    }
+}
+
+/// Channel to color a rendered track by.
+#[derive(Clone, Copy, ValueEnum)]
+enum ChannelArg {
+    Speed,
+    Throttle,
+    Brake,
+    Gear,
+    Curvature,
+    LongitudinalAccel,
+    LateralAccel,
+    CombinedG,
+}
+
+impl From<ChannelArg> for Channel {
+    fn from(channel: ChannelArg) -> Self {
+        match channel {
+            ChannelArg::Speed => Channel::Speed,
+            ChannelArg::Throttle => Channel::Throttle,
+            ChannelArg::Brake => Channel::Brake,
+            ChannelArg::Gear => Channel::Gear,
+            ChannelArg::Curvature => Channel::Curvature,
+            ChannelArg::LongitudinalAccel => Channel::LongitudinalAccel,
+            ChannelArg::LateralAccel => Channel::LateralAccel,
+            ChannelArg::CombinedG => Channel::CombinedG,
+        }
+    }
+}
+
+/// Filter to smooth laps with before analysis.
+#[derive(Clone, Copy, ValueEnum)]
+enum SmoothArg {
+    /// Savitzky–Golay, 7 samples, quadratic
+    SavitzkyGolay,
+    /// Moving average over 5 samples
+    MovingAverage,
+    /// Constant-velocity Kalman filter with default noise
+    Kalman,
+}
+
+impl From<SmoothArg> for Filter {
+    fn from(smooth: SmoothArg) -> Self {
+        match smooth {
+            SmoothArg::SavitzkyGolay => Filter::SavitzkyGolay {
+                window: 7,
+                order: 2,
+            },
+            SmoothArg::MovingAverage => Filter::MovingAverage { window: 5 },
+            SmoothArg::Kalman => Filter::Kalman(KalmanConfig::default()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Clean {
+    Repair,
+    Exclude,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> anyhow::Result<ExitCode> {
+    // Loaded by the commands that need telemetry; `info` reads summaries only
+    let load = || commands::load(&cli.session);
+    match cli.command {
+        Command::Info => commands::info(&cli.session),
+        Command::Laps { driver, format } => commands::laps(&load()?, &driver, format),
+        Command::Pits { format } => commands::pits(&load()?, format),
+        Command::History { gaps, format } => commands::history(&load()?, gaps, format),
+        Command::Overtakes {
+            turns,
+            battles,
+            format,
+        } => commands::overtakes(&load()?, turns.as_deref(), battles, format),
+        Command::Stints {
+            drivers,
+            fuel_per_lap,
+            time_per_kg,
+            format,
+        } => {
+            let config = StintConfig {
+                fuel_per_lap,
+                time_per_kg,
+                ..StintConfig::default()
+            };
+            commands::stints(&load()?, &drivers, &config, format)
+        }
+        Command::SpeedTraps {
+            turns,
+            laps,
+            all_laps,
+            by_distance,
+            format,
+        } => commands::speed_traps(
+            &load()?,
+            turns.as_deref(),
+            laps,
+            all_laps,
+            by_distance,
+            format,
+        ),
+        Command::Clipping {
+            drivers,
+            laps,
+            brake_margin,
+            format,
+        } => {
+            let config = ClippingConfig {
+                brake_margin,
+                ..ClippingConfig::default()
+            };
+            commands::clipping(&load()?, &drivers, laps, &config, format)
+        }
+        Command::Gears {
+            turns,
+            driver,
+            lap,
+            format,
+        } => commands::gears(&load()?, turns.as_deref(), driver.as_deref(), lap, format),
+        Command::Drs {
+            drivers,
+            tow_distance,
+            format,
+        } => {
+            let config = DrsConfig {
+                tow_distance,
+                ..DrsConfig::default()
+            };
+            commands::drs(&load()?, &drivers, &config, format)
+        }
+        Command::CompareTurn {
+            turns,
+            turn,
+            drivers,
+            smooth,
+            format,
+        } => commands::compare_turn(
+            &load()?,
+            &turns,
+            &turn,
+            &drivers,
+            smooth.map(Filter::from),
+            format,
+        ),
+        Command::CompareSessions {
+            with,
+            driver,
+            turns,
+            turn,
+            format,
+        } => commands::compare_sessions(load()?, &with, &turns, turn.as_deref(), &driver, format),
+        Command::Report {
+            output,
+            turns,
+            drivers,
+            mini_sectors,
+        } => commands::report(&load()?, &output, turns.as_deref(), drivers, mini_sectors),
+        Command::Render {
+            output,
+            turns,
+            driver,
+            lap,
+            channel,
+            smooth,
+            width,
+        } => commands::render(
+            &load()?,
+            &output,
+            turns.as_deref(),
+            driver.as_deref(),
+            lap,
+            smooth.map(Filter::from),
+            &RenderOptions {
+                width,
+                line_color: channel.map_or_else(LineColor::default, |channel| {
+                    LineColor::Channel(channel.into())
+                }),
+                ..RenderOptions::default()
+            },
+        ),
+        Command::Register {
+            reference,
+            output,
+            turns,
+            turns_output,
+        } => commands::register(
+            &load()?,
+            &reference,
+            output.as_deref(),
+            turns.as_deref(),
+            turns_output.as_deref(),
+        ),
+        Command::Validate { verbose, json } => commands::validate(&load()?, verbose, json),
+        Command::Export {
+            output,
+            drivers,
+            clean,
+            pretty,
+        } => commands::export(load()?, &output, &drivers, clean, pretty),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+    use clap::CommandFactory;
+
+    fn parse(args: &[&str]) -> Result<Command, clap::Error> {
+        let args = ["f1-cli", "-s", "session.json"].iter().chain(args);
+        Cli::try_parse_from(args).map(|cli| cli.command)
+    }
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_compare_turn_drivers() {
+        let command = parse(&[
+            "compare-turn",
+            "--turns",
+            "t.json",
+            "--turn",
+            "T1",
+            "--drivers",
+            "VER,NOR",
+        ])
+        .unwrap();
+        let Command::CompareTurn { turn, drivers, .. } = command else {
+            panic!("not compare-turn");
+        };
+        assert_eq!(turn, "T1");
+        assert_eq!(drivers, ["VER", "NOR"]);
+
+        let missing = parse(&["compare-turn", "--turns", "t.json", "--turn", "T1"]);
+        assert_eq!(
+            missing.err().map(|e| e.kind()),
+            Some(ErrorKind::MissingRequiredArgument)
+        );
+    }
+
+    #[test]
+    fn test_smooth_filter() {
+        let Ok(Command::Render { smooth, .. }) = parse(&["render", "-o", "t.png"]) else {
+            panic!("not render");
+        };
+        assert!(smooth.is_none());
+
+        let Ok(Command::Render {
+            smooth: Some(smooth),
+            ..
+        }) = parse(&["render", "-o", "t.png", "--smooth", "savitzky-golay"])
+        else {
+            panic!("no --smooth");
+        };
+        assert_eq!(
+            Filter::from(smooth),
+            Filter::SavitzkyGolay {
+                window: 7,
+                order: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_register_turns_need_output() {
+        let reference = ["register", "--reference", "2024.json"];
+        for extra in [&["--turns", "t.json"][..], &["--turns-output", "out.json"]] {
+            let args: Vec<&str> = reference.iter().chain(extra).copied().collect();
+            assert_eq!(
+                parse(&args).err().map(|e| e.kind()),
+                Some(ErrorKind::MissingRequiredArgument),
+                "{args:?}"
+            );
+        }
+
+        let args: Vec<&str> = reference
+            .iter()
+            .chain(&["--turns", "t.json", "--turns-output", "out.json"])
+            .copied()
+            .collect();
+        let Ok(Command::Register {
+            turns,
+            turns_output,
+            ..
+        }) = parse(&args)
+        else {
+            panic!("not register");
+        };
+        assert_eq!(turns, Some(PathBuf::from("t.json")));
+        assert_eq!(turns_output, Some(PathBuf::from("out.json")));
+        assert!(parse(&reference).is_ok());
+    }
+}
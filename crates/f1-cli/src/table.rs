@@ -0,0 +1,99 @@
+//! Plain-text table output.
+
+use std::fmt;
+
+use f1_data::format;
+
+/// A table printed with columns padded to their widest cell.
+#[derive(Debug, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new<S: Into<String>>(headers: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            headers: headers.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn row<S: Into<String>>(&mut self, cells: impl IntoIterator<Item = S>) {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (i, cell) in row.iter().enumerate() {
+                if i < widths.len() {
+                    widths[i] = widths[i].max(cell.chars().count());
+                }
+            }
+        }
+
+        let line = |f: &mut fmt::Formatter, cells: &[String]| {
+            let padded: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect();
+            writeln!(f, "{}", padded.join("  ").trim_end())
+        };
+
+        line(f, &self.headers)?;
+        let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        line(f, &rule)?;
+        for row in &self.rows {
+            line(f, row)?;
+        }
+        Ok(())
+    }
+}
+
+/// Format a lap time as `m:ss.sss`, or `-` if missing.
+pub fn lap_time(seconds: Option<f64>) -> String {
+    seconds.map_or_else(|| "-".into(), format::lap_time)
+}
+
+/// Format seconds with millisecond precision, or `-` if missing.
+pub fn seconds(seconds: Option<f64>) -> String {
+    seconds.map_or_else(|| "-".into(), |s| format!("{s:.3}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns_padded_to_widest_cell() {
+        let mut table = Table::new(["Driver", "Lap"]);
+        table.row(["VER", "1:32.500"]);
+        table.row(["HULKENBERG", "-"]);
+        assert_eq!(
+            table.to_string(),
+            "Driver      Lap\n\
+             ----------  --------\n\
+             VER         1:32.500\n\
+             HULKENBERG  -\n"
+        );
+    }
+
+    #[test]
+    fn test_cells_beyond_headers_dropped() {
+        let mut table = Table::new(["Lap"]);
+        table.row(["12", "pit"]);
+        assert_eq!(table.to_string(), "Lap\n---\n12\n");
+    }
+
+    #[test]
+    fn test_lap_time() {
+        // Rounding is covered by `f1_data::format`
+        assert_eq!(lap_time(None), "-");
+        assert_eq!(seconds(Some(0.1234)), "0.123");
+        assert_eq!(seconds(None), "-");
+    }
+}
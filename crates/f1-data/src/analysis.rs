@@ -1,9 +1,11 @@
 //! Lap and turn analysis functionality.
 
-use crate::{Lap, TelemetrySample, Turn, TurnSegment};
+use serde::Serialize;
+
+use crate::{Lap, LapTrace, TelemetrySample, Turn, TurnSegment};
 
 /// Time spent in a turn or segment.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SegmentTime {
     /// Time in seconds
     pub time: f64,
@@ -16,8 +18,12 @@ pub struct SegmentTime {
 }
 
 /// Complete turn analysis for a single lap.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TurnAnalysis {
+    /// Driver the lap belongs to, when comparing across drivers
+    pub driver_code: Option<String>,
+    /// Session the lap comes from, when comparing across sessions
+    pub session: Option<String>,
     pub lap_number: u32,
     /// Total time through the turn
     pub total_time: Option<SegmentTime>,
@@ -35,6 +41,8 @@ impl TurnAnalysis {
         let samples = turn.filter_samples(&lap.samples);
 
         Self {
+            driver_code: None,
+            session: None,
             lap_number: lap.number,
             total_time: compute_segment_time(&samples),
             approach: compute_segment_time(&turn.filter_segment(&lap.samples, TurnSegment::Approach)),
@@ -42,6 +50,15 @@ impl TurnAnalysis {
             exit: compute_segment_time(&turn.filter_segment(&lap.samples, TurnSegment::Exit)),
         }
     }
+
+    /// Timing for one segment of the turn.
+    pub fn segment(&self, segment: TurnSegment) -> Option<&SegmentTime> {
+        match segment {
+            TurnSegment::Approach => self.approach.as_ref(),
+            TurnSegment::Apex => self.apex.as_ref(),
+            TurnSegment::Exit => self.exit.as_ref(),
+        }
+    }
 }
 
 /// Compute timing and speed data for a set of samples.
@@ -65,7 +82,7 @@ fn compute_segment_time(samples: &[&TelemetrySample]) -> Option<SegmentTime> {
 }
 
 /// Result of comparing laps through a turn.
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct TurnComparison {
     pub turn_name: String,
     /// Analyses sorted by total turn time (fastest first)
@@ -77,7 +94,7 @@ pub struct TurnComparison {
 }
 
 /// Best lap numbers for each segment.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize)]
 pub struct SegmentBests {
     pub approach: Option<u32>,
     pub apex: Option<u32>,
@@ -87,9 +104,31 @@ pub struct SegmentBests {
 impl TurnComparison {
     /// Compare multiple laps through a turn.
     pub fn compare(laps: &[Lap], turn: &Turn) -> Self {
-        let mut analyses: Vec<TurnAnalysis> = laps
+        let analyses = laps
             .iter()
             .map(|lap| TurnAnalysis::analyze(lap, turn))
+            .collect();
+        Self::from_analyses(analyses, turn)
+    }
+
+    /// Compare laps of different drivers through a turn.
+    ///
+    /// Each analysis carries its driver code, since lap numbers alone are
+    /// ambiguous across drivers.
+    pub fn compare_drivers(laps: &[(&str, &Lap)], turn: &Turn) -> Self {
+        let analyses = laps
+            .iter()
+            .map(|(code, lap)| TurnAnalysis {
+                driver_code: Some(code.to_string()),
+                ..TurnAnalysis::analyze(lap, turn)
+            })
+            .collect();
+        Self::from_analyses(analyses, turn)
+    }
+
+    pub(crate) fn from_analyses(analyses: Vec<TurnAnalysis>, turn: &Turn) -> Self {
+        let mut analyses: Vec<TurnAnalysis> = analyses
+            .into_iter()
             .filter(|a| a.total_time.is_some())
             .collect();
 
@@ -100,37 +139,168 @@ impl TurnComparison {
             time_a.partial_cmp(&time_b).unwrap_or(std::cmp::Ordering::Equal)
         });
 
-        let segment_bests = compute_segment_bests(&analyses);
-        let fastest_lap = analyses.first().map(|a| a.lap_number);
+        let mut comparison = Self {
+            turn_name: turn.name.clone(),
+            analyses,
+            uniform_fastest: false,
+            segment_bests: SegmentBests::default(),
+        };
 
-        // Check if overall fastest is also fastest in all segments
-        let uniform_fastest = fastest_lap.map_or(false, |fl| {
-            segment_bests.approach.map_or(true, |l| l == fl)
-                && segment_bests.apex.map_or(true, |l| l == fl)
-                && segment_bests.exit.map_or(true, |l| l == fl)
-        });
+        // Compare by index so laps from different drivers with the same
+        // number aren't confused
+        let best = [TurnSegment::Approach, TurnSegment::Apex, TurnSegment::Exit]
+            .map(|segment| comparison.segment_best_index(segment));
+        comparison.uniform_fastest =
+            !comparison.analyses.is_empty() && best.iter().all(|b| b.is_none_or(|i| i == 0));
+        let [approach, apex, exit] = best.map(|i| i.map(|i| comparison.analyses[i].lap_number));
+        comparison.segment_bests = SegmentBests {
+            approach,
+            apex,
+            exit,
+        };
+        comparison
+    }
+
+    /// Whether the analyses carry the session their lap comes from (see
+    /// `SessionCollection::compare_turn`).
+    pub fn is_cross_session(&self) -> bool {
+        self.analyses.iter().any(|a| a.session.is_some())
+    }
+
+    /// The analysis with the fastest time through a segment.
+    pub fn segment_best(&self, segment: TurnSegment) -> Option<&TurnAnalysis> {
+        self.segment_best_index(segment).map(|i| &self.analyses[i])
+    }
+
+    fn segment_best_index(&self, segment: TurnSegment) -> Option<usize> {
+        self.analyses
+            .iter()
+            .enumerate()
+            .filter_map(|(i, a)| a.segment(segment).map(|s| (i, s.time)))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(i, _)| i)
+    }
+}
+
+/// Laps of different drivers split into equal-distance mini-sectors.
+///
+/// Each lap is cut at the same fractions of its own length, so small
+/// differences in measured lap distance don't shift the boundaries.
+#[derive(Debug, Clone, Serialize)]
+pub struct MiniSectorComparison {
+    /// Driver code of each compared lap
+    pub driver_codes: Vec<String>,
+    /// Mini-sector times in seconds, `times[lap][mini_sector]`
+    pub times: Vec<Vec<f64>>,
+    /// Index into `driver_codes` of the fastest lap in each mini-sector
+    pub fastest: Vec<usize>,
+}
+
+impl MiniSectorComparison {
+    /// Split each lap into `count` mini-sectors and find the fastest in each.
+    ///
+    /// Laps with fewer than two samples are skipped.
+    pub fn compare(laps: &[(&str, &Lap)], count: usize) -> Self {
+        let mut driver_codes = Vec::new();
+        let mut times: Vec<Vec<f64>> = Vec::new();
+        for (code, lap) in laps {
+            let Some(trace) = LapTrace::new(lap) else {
+                continue;
+            };
+            let boundaries: Vec<f64> = (0..=count)
+                .map(|i| trace.time_at_distance(trace.length() * i as f32 / count as f32))
+                .collect();
+            driver_codes.push(code.to_string());
+            times.push(boundaries.windows(2).map(|w| w[1] - w[0]).collect());
+        }
+
+        let fastest = if times.is_empty() {
+            Vec::new()
+        } else {
+            (0..count)
+                .map(|sector| {
+                    (0..times.len())
+                        .min_by(|&a, &b| times[a][sector].total_cmp(&times[b][sector]))
+                        .unwrap_or(0)
+                })
+                .collect()
+        };
 
         Self {
-            turn_name: turn.name.clone(),
-            analyses,
-            uniform_fastest,
-            segment_bests,
+            driver_codes,
+            times,
+            fastest,
         }
     }
+
+    /// Number of mini-sectors each lap was split into.
+    pub fn count(&self) -> usize {
+        self.fastest.len()
+    }
+
+    /// Driver code of the fastest lap in a mini-sector.
+    pub fn fastest_driver(&self, mini_sector: usize) -> Option<&str> {
+        let index = *self.fastest.get(mini_sector)?;
+        Some(&self.driver_codes[index])
+    }
 }
 
-fn compute_segment_bests(analyses: &[TurnAnalysis]) -> SegmentBests {
-    let find_best = |get_time: fn(&TurnAnalysis) -> Option<f64>| -> Option<u32> {
-        analyses
-            .iter()
-            .filter_map(|a| get_time(a).map(|t| (a.lap_number, t)))
-            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
-            .map(|(lap, _)| lap)
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoundingBox, DrsStatus};
+    use glam::Vec3;
+
+    /// A straight run along X at constant speed, sampled every 0.1 s.
+    fn lap(number: u32, speed_mps: f32) -> Lap {
+        let samples = (0..=30)
+            .map(|i| TelemetrySample {
+                position: Vec3::new(i as f32 * 0.1 * speed_mps, 0.0, 0.0),
+                time: i as f64 * 0.1,
+                throttle: 100,
+                brake: 0,
+                gear: 7,
+                speed: (speed_mps * 3.6) as u16,
+                drs: DrsStatus::Off,
+            })
+            .collect();
+        Lap {
+            number,
+            lap_time: None,
+            sector_times: [None; 3],
+            samples,
+            is_valid: true,
+        }
+    }
+
+    #[test]
+    fn test_compare_drivers_with_same_lap_number() {
+        let turn = Turn {
+            name: "T1".into(),
+            bounds: BoundingBox::new(0.0, 150.0, -5.0, 5.0),
+            approach: BoundingBox::new(0.0, 50.0, -5.0, 5.0),
+            apex: BoundingBox::new(50.0, 100.0, -5.0, 5.0),
+            exit: BoundingBox::new(100.0, 150.0, -5.0, 5.0),
+        };
+        let (slow, fast) = (lap(5, 50.0), lap(5, 60.0));
+        let comparison = TurnComparison::compare_drivers(&[("NOR", &slow), ("VER", &fast)], &turn);
+
+        assert_eq!(comparison.analyses[0].driver_code.as_deref(), Some("VER"));
+        assert!(comparison.uniform_fastest);
+        let apex_best = comparison.segment_best(TurnSegment::Apex).unwrap();
+        assert_eq!(apex_best.driver_code.as_deref(), Some("VER"));
+    }
+
+    #[test]
+    fn test_mini_sectors() {
+        // Same line, covered in 2.7 s instead of 3 s
+        let slow = lap(1, 50.0);
+        let mut fast = lap(1, 50.0);
+        fast.samples.iter_mut().for_each(|s| s.time *= 0.9);
+        let minis = MiniSectorComparison::compare(&[("NOR", &slow), ("VER", &fast)], 4);
 
-    SegmentBests {
-        approach: find_best(|a| a.approach.as_ref().map(|s| s.time)),
-        apex: find_best(|a| a.apex.as_ref().map(|s| s.time)),
-        exit: find_best(|a| a.exit.as_ref().map(|s| s.time)),
+        assert_eq!(minis.count(), 4);
+        assert!((minis.times[0].iter().sum::<f64>() - 3.0).abs() < 1e-9);
+        assert!((0..4).all(|i| minis.fastest_driver(i) == Some("VER")));
     }
 }
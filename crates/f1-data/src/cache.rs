@@ -0,0 +1,155 @@
+//! Binary session cache stored alongside the exported JSON.
+//!
+//! Parsing a full race export with `serde_json` takes seconds; the same
+//! `Session` encoded with bincode loads almost instantly. The cache records the
+//! source file's size and modification time and is ignored (and rewritten)
+//! whenever either changes.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::loader::LoadError;
+use crate::Session;
+
+/// File extension used for cache files.
+pub const CACHE_EXTENSION: &str = "f1cache";
+
+/// Identifies a cache file.
+const MAGIC: [u8; 4] = *b"F1PC";
+
+/// Bump whenever the encoded `Session` layout changes.
+const CACHE_VERSION: u32 = 2;
+
+/// Written before the session so stale caches can be detected without
+/// decoding the whole file.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheHeader {
+    magic: [u8; 4],
+    version: u32,
+    /// Size of the source JSON in bytes
+    source_len: u64,
+    /// Modification time of the source JSON, nanoseconds since the Unix epoch
+    source_modified: u128,
+}
+
+impl CacheHeader {
+    fn for_source(source: &Path) -> Result<Self, LoadError> {
+        let metadata = fs::metadata(source)?;
+        let source_modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos());
+        Ok(Self {
+            magic: MAGIC,
+            version: CACHE_VERSION,
+            source_len: metadata.len(),
+            source_modified,
+        })
+    }
+}
+
+/// Path of the cache file for a session JSON (e.g. `2025_austin_q.f1cache`).
+pub fn cache_path(source: impl AsRef<Path>) -> PathBuf {
+    source.as_ref().with_extension(CACHE_EXTENSION)
+}
+
+/// Read the cached session for `source`.
+///
+/// Returns `Ok(None)` if there is no cache or it is stale (source changed or
+/// written by a different cache version).
+pub fn read_cache(source: impl AsRef<Path>) -> Result<Option<Session>, LoadError> {
+    let source = source.as_ref();
+    let path = cache_path(source);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let expected = CacheHeader::for_source(source)?;
+    let mut reader = BufReader::new(File::open(&path)?);
+    let header: CacheHeader = match bincode::deserialize_from(&mut reader) {
+        Ok(header) => header,
+        // Not a cache we understand; treat as stale
+        Err(_) => return Ok(None),
+    };
+    if header != expected {
+        return Ok(None);
+    }
+
+    let session = bincode::deserialize_from(&mut reader)?;
+    Ok(Some(session))
+}
+
+/// Write the cache for `source`.
+///
+/// The cache is written to a temporary file first and renamed into place so
+/// a crash never leaves a truncated cache behind.
+pub fn write_cache(source: impl AsRef<Path>, session: &Session) -> Result<(), LoadError> {
+    let source = source.as_ref();
+    let path = cache_path(source);
+    let tmp_path = path.with_extension(format!("{CACHE_EXTENSION}.tmp"));
+
+    let header = CacheHeader::for_source(source)?;
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    bincode::serialize_into(&mut writer, &header)?;
+    bincode::serialize_into(&mut writer, session)?;
+    writer.flush()?;
+    drop(writer);
+
+    fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::load_session_cached;
+
+    const SESSION_JSON: &str = r##"{
+        "year": 2025,
+        "circuit": "United States Grand Prix",
+        "circuit_short": "Austin",
+        "session_type": "Qualifying",
+        "date": "2025-10-18T21:00:00Z",
+        "drivers": [{
+            "driver": {"code": "VER", "name": "Max Verstappen", "number": 1, "team": "Red Bull Racing", "team_color": "#3671C6"},
+            "laps": [{
+                "number": 1,
+                "lap_time": 92.5,
+                "sector_times": [30.1, 31.2, 31.2],
+                "samples": [{"position": [1.0, 0.0, 2.0], "time": 10.0, "throttle": 100, "brake": 0, "gear": 7, "speed": 290, "drs": "Off"}],
+                "is_valid": true
+            }]
+        }]
+    }"##;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("f1-data-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_cache_roundtrip_and_invalidation() {
+        let dir = temp_dir("cache");
+        let source = dir.join("2025_austin_q.json");
+        fs::write(&source, SESSION_JSON).unwrap();
+
+        assert!(read_cache(&source).unwrap().is_none());
+        let session = load_session_cached(&source).unwrap();
+        let cached = read_cache(&source)
+            .unwrap()
+            .expect("cache written on first load");
+        assert_eq!(cached.drivers[0].laps[0].samples.len(), 1);
+        assert_eq!(cached.date, session.date);
+
+        // Changing the source invalidates the cache
+        fs::write(&source, format!("{SESSION_JSON}\n")).unwrap();
+        assert!(read_cache(&source).unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
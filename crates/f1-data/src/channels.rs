@@ -0,0 +1,299 @@
+//! Per-sample telemetry channels, raw and derived.
+//!
+//! Exports only carry speed, pedals, gear and position. `LapChannels` adds
+//! values derived from them (distance, heading, curvature and accelerations)
+//! and exposes every channel the same way, so charts, heatmaps and turn
+//! analysis can work with any of them through `Channel`.
+//!
+//! Curvature and lateral acceleration come from position differences and are
+//! sensitive to noise; compute them on a smoothed lap (see `smooth`) for
+//! anything but a rough picture.
+
+use serde::{Deserialize, Serialize};
+
+use crate::trace::planar_distance;
+use crate::{Lap, TelemetrySample, Turn, TurnSegment};
+
+/// Standard gravity (m/s²), used to express accelerations in g.
+pub const G: f32 = 9.80665;
+
+/// A per-sample channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Channel {
+    /// Speed (km/h)
+    Speed,
+    /// Throttle (0-100%)
+    Throttle,
+    /// Brake (0-100)
+    Brake,
+    /// Gear
+    Gear,
+    /// Distance driven since the first sample (m)
+    Distance,
+    /// Direction of travel in the X/Z plane (radians, from +X towards +Z)
+    Heading,
+    /// Signed curvature of the path (1/m, positive when turning from +X towards +Z)
+    Curvature,
+    /// Acceleration along the direction of travel (g, negative when braking)
+    LongitudinalAccel,
+    /// Acceleration across the direction of travel, v²·curvature (g, signed like curvature)
+    LateralAccel,
+    /// Magnitude of longitudinal and lateral acceleration combined (g)
+    CombinedG,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 10] = [
+        Channel::Speed,
+        Channel::Throttle,
+        Channel::Brake,
+        Channel::Gear,
+        Channel::Distance,
+        Channel::Heading,
+        Channel::Curvature,
+        Channel::LongitudinalAccel,
+        Channel::LateralAccel,
+        Channel::CombinedG,
+    ];
+
+    /// Human-readable name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Channel::Speed => "Speed",
+            Channel::Throttle => "Throttle",
+            Channel::Brake => "Brake",
+            Channel::Gear => "Gear",
+            Channel::Distance => "Distance",
+            Channel::Heading => "Heading",
+            Channel::Curvature => "Curvature",
+            Channel::LongitudinalAccel => "Longitudinal accel",
+            Channel::LateralAccel => "Lateral accel",
+            Channel::CombinedG => "Combined g",
+        }
+    }
+
+    /// Unit the channel's values are in.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Channel::Speed => "km/h",
+            Channel::Throttle => "%",
+            Channel::Brake | Channel::Gear => "",
+            Channel::Distance => "m",
+            Channel::Heading => "rad",
+            Channel::Curvature => "1/m",
+            Channel::LongitudinalAccel | Channel::LateralAccel | Channel::CombinedG => "g",
+        }
+    }
+
+    /// Whether the channel is computed rather than read from the export.
+    pub fn is_derived(&self) -> bool {
+        !matches!(
+            self,
+            Channel::Speed | Channel::Throttle | Channel::Brake | Channel::Gear
+        )
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Every channel of a lap, one value per sample.
+#[derive(Debug, Clone)]
+pub struct LapChannels {
+    values: [Vec<f32>; Channel::ALL.len()],
+}
+
+impl LapChannels {
+    /// Compute all channels for a lap.
+    pub fn new(lap: &Lap) -> Self {
+        let samples = &lap.samples;
+        let n = samples.len();
+        let raw = |get: fn(&TelemetrySample) -> f32| samples.iter().map(get).collect();
+
+        let mut distance = Vec::with_capacity(n);
+        let mut total = 0.0;
+        for (i, sample) in samples.iter().enumerate() {
+            if i > 0 {
+                total += planar_distance(&samples[i - 1], sample);
+            }
+            distance.push(total);
+        }
+
+        // Central differences, one-sided at the ends
+        let neighbours = |i: usize| (i.saturating_sub(1), (i + 1).min(n - 1));
+
+        let heading = (0..n)
+            .map(|i| {
+                let (a, b) = neighbours(i);
+                let (pa, pb) = (samples[a].position, samples[b].position);
+                (pb.z - pa.z).atan2(pb.x - pa.x)
+            })
+            .collect();
+
+        let curvature: Vec<f32> = (0..n)
+            .map(|i| {
+                let (a, b) = neighbours(i);
+                if a == i || b == i {
+                    return 0.0;
+                }
+                curvature(&samples[a], &samples[i], &samples[b])
+            })
+            .collect();
+
+        let speed_mps = |s: &TelemetrySample| s.speed as f32 / 3.6;
+        let longitudinal: Vec<f32> = (0..n)
+            .map(|i| {
+                let (a, b) = neighbours(i);
+                let dt = (samples[b].time - samples[a].time) as f32;
+                if dt > 0.0 {
+                    (speed_mps(&samples[b]) - speed_mps(&samples[a])) / dt / G
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+
+        let lateral: Vec<f32> = samples
+            .iter()
+            .zip(&curvature)
+            .map(|(s, k)| speed_mps(s).powi(2) * k / G)
+            .collect();
+
+        let combined = longitudinal
+            .iter()
+            .zip(&lateral)
+            .map(|(lon, lat)| lon.hypot(*lat))
+            .collect();
+
+        Self {
+            values: [
+                raw(|s| s.speed as f32),
+                raw(|s| s.throttle as f32),
+                raw(|s| s.brake as f32),
+                raw(|s| s.gear as f32),
+                distance,
+                heading,
+                curvature,
+                longitudinal,
+                lateral,
+                combined,
+            ],
+        }
+    }
+
+    /// Values of a channel, indexed like the lap's samples.
+    pub fn get(&self, channel: Channel) -> &[f32] {
+        &self.values[channel.index()]
+    }
+
+    /// Value of a channel at one sample.
+    pub fn value(&self, channel: Channel, index: usize) -> Option<f32> {
+        self.get(channel).get(index).copied()
+    }
+
+    /// Largest magnitude of a channel over the samples of `lap` inside a
+    /// turn segment (e.g. peak lateral g in the apex).
+    ///
+    /// `lap` must be the lap these channels were computed from. Returns
+    /// `None` if no sample falls in the segment.
+    pub fn segment_peak(
+        &self,
+        lap: &Lap,
+        turn: &Turn,
+        segment: TurnSegment,
+        channel: Channel,
+    ) -> Option<f32> {
+        let bounds = turn.segment_bounds(segment);
+        let values = self.get(channel);
+        lap.samples
+            .iter()
+            .zip(values)
+            .filter(|(s, _)| bounds.contains(s.position))
+            .map(|(_, v)| v.abs())
+            .max_by(f32::total_cmp)
+    }
+}
+
+/// Signed curvature of the circle through three samples (X/Z plane).
+fn curvature(a: &TelemetrySample, b: &TelemetrySample, c: &TelemetrySample) -> f32 {
+    let (ab, bc, ac) = (
+        planar_distance(a, b),
+        planar_distance(b, c),
+        planar_distance(a, c),
+    );
+    let denominator = ab * bc * ac;
+    if denominator <= f32::EPSILON {
+        return 0.0;
+    }
+    let (p, q, r) = (a.position, b.position, c.position);
+    let cross = (q.x - p.x) * (r.z - q.z) - (q.z - p.z) * (r.x - q.x);
+    2.0 * cross / denominator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoundingBox, DrsStatus};
+    use glam::Vec3;
+
+    /// A counter-clockwise quarter circle of radius 50 m at 180 km/h.
+    fn corner_lap() -> Lap {
+        let radius = 50.0_f32;
+        let speed = 50.0_f32; // m/s
+        let step = 0.05_f32; // s
+        let samples = (0..=31)
+            .map(|i| {
+                let angle = i as f32 * step * speed / radius;
+                TelemetrySample {
+                    position: Vec3::new(radius * angle.cos(), 0.0, radius * angle.sin()),
+                    time: (i as f32 * step) as f64,
+                    throttle: 60,
+                    brake: 0,
+                    gear: 5,
+                    speed: 180,
+                    drs: DrsStatus::Off,
+                }
+            })
+            .collect();
+        Lap {
+            number: 1,
+            lap_time: None,
+            sector_times: [None; 3],
+            samples,
+            is_valid: true,
+        }
+    }
+
+    #[test]
+    fn test_constant_radius_corner() {
+        let lap = corner_lap();
+        let channels = LapChannels::new(&lap);
+
+        let mid = lap.samples.len() / 2;
+        let curvature = channels.value(Channel::Curvature, mid).unwrap();
+        assert!((curvature - 0.02).abs() < 1e-3, "{curvature}");
+        let expected_lateral = 50.0 * 50.0 / 50.0 / G;
+        let lateral = channels.value(Channel::LateralAccel, mid).unwrap();
+        assert!((lateral - expected_lateral).abs() < 0.1, "{lateral}");
+        assert!(
+            channels
+                .value(Channel::LongitudinalAccel, mid)
+                .unwrap()
+                .abs()
+                < 1e-3
+        );
+
+        let turn = Turn {
+            name: "T1".into(),
+            bounds: BoundingBox::new(-60.0, 60.0, -60.0, 60.0),
+            approach: BoundingBox::new(40.0, 60.0, -10.0, 20.0),
+            apex: BoundingBox::new(20.0, 45.0, 20.0, 45.0),
+            exit: BoundingBox::new(-10.0, 20.0, 40.0, 60.0),
+        };
+        let peak = channels
+            .segment_peak(&lap, &turn, TurnSegment::Apex, Channel::LateralAccel)
+            .unwrap();
+        assert!((peak - expected_lateral).abs() < 0.1, "{peak}");
+    }
+}
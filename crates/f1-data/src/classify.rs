@@ -0,0 +1,421 @@
+//! Lap classification: out-laps, in-laps, push laps and the rest.
+//!
+//! `Lap` only records whether a lap time was deleted. For pace work only
+//! push laps are representative, so `classify_session` labels every lap from
+//! its telemetry:
+//!
+//! - A sustained run at pit-limiter speed at the start of the lap makes it an
+//!   out-lap, at the end an in-lap, at both ends a pit-through lap.
+//! - Of the remaining laps, those much slower than the driver's typical lap
+//!   are cool-down laps, or safety-car laps when most of the field is slow
+//!   at the same moment of a race.
+//! - Everything else is a push lap.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::{DriverSession, Lap, Session, SessionType, TelemetrySample};
+
+/// What a lap was driven for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LapClass {
+    /// Flying lap at race or qualifying pace
+    Push,
+    /// Starts in the pit lane
+    OutLap,
+    /// Ends in the pit lane
+    InLap,
+    /// Starts and ends in the pit lane
+    PitThrough,
+    /// Slow lap on a clear track (e.g. between qualifying runs)
+    CoolDown,
+    /// Slow lap while most of the field is slow (safety car, VSC, red flag)
+    SafetyCar,
+}
+
+impl LapClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Push => "push",
+            Self::OutLap => "out",
+            Self::InLap => "in",
+            Self::PitThrough => "pit-through",
+            Self::CoolDown => "cool-down",
+            Self::SafetyCar => "SC",
+        }
+    }
+
+    /// Whether the lap enters or leaves the pit lane.
+    pub fn is_pit(&self) -> bool {
+        matches!(self, Self::OutLap | Self::InLap | Self::PitThrough)
+    }
+}
+
+/// Thresholds for `classify_session`.
+#[derive(Debug, Clone, Copy)]
+pub struct ClassifyConfig {
+    /// Highest speed counted as pit-lane driving (km/h); pit limits are 60 or
+    /// 80 km/h
+    pub pit_speed: u16,
+    /// Lowest speed counted as pit-lane driving (km/h), so standing starts
+    /// and stationary cars don't count
+    pub min_pit_speed: u16,
+    /// Shortest run at pit speed that counts as being in the pit lane (s);
+    /// slow corners are over sooner
+    pub min_pit_time: f64,
+    /// How far into and before the end of a lap pit driving is looked for (s)
+    pub pit_window: f64,
+    /// Laps slower than the driver's typical lap by this factor are slow
+    pub slow_factor: f64,
+    /// Share of the drivers on track that must be slow at the same moment
+    /// for a slow race lap to count as a safety-car lap
+    pub safety_car_fraction: f64,
+}
+
+impl Default for ClassifyConfig {
+    fn default() -> Self {
+        Self {
+            pit_speed: 90,
+            min_pit_speed: 20,
+            min_pit_time: 4.0,
+            pit_window: 30.0,
+            slow_factor: 1.12,
+            safety_car_fraction: 0.6,
+        }
+    }
+}
+
+/// The class of every lap in a session.
+#[derive(Debug, Clone, Default)]
+pub struct LapClasses {
+    classes: HashMap<String, HashMap<u32, LapClass>>,
+}
+
+impl LapClasses {
+    /// Class of a driver's lap, `None` for laps with neither a time nor
+    /// telemetry.
+    pub fn get(&self, driver_code: &str, lap_number: u32) -> Option<LapClass> {
+        self.classes.get(driver_code)?.get(&lap_number).copied()
+    }
+
+    /// A driver's valid push laps.
+    pub fn representative_laps<'a>(&self, driver: &'a DriverSession) -> Vec<&'a Lap> {
+        driver
+            .laps
+            .iter()
+            .filter(|lap| {
+                lap.is_valid && self.get(&driver.driver.code, lap.number) == Some(LapClass::Push)
+            })
+            .collect()
+    }
+
+    /// Number of laps of each class.
+    pub fn counts(&self) -> HashMap<LapClass, usize> {
+        let mut counts = HashMap::new();
+        for class in self.classes.values().flat_map(|laps| laps.values()) {
+            *counts.entry(*class).or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// A lap's duration and session time span, as far as they are known.
+struct LapSpan {
+    duration: Option<f64>,
+    /// First and last sample time
+    span: Option<(f64, f64)>,
+}
+
+impl LapSpan {
+    fn new(lap: &Lap) -> Self {
+        let span = lap
+            .samples
+            .first()
+            .zip(lap.samples.last())
+            .map(|(first, last)| (first.time, last.time));
+        Self {
+            duration: lap
+                .lap_time
+                .or_else(|| span.map(|(start, end)| end - start).filter(|d| *d > 0.0)),
+            span,
+        }
+    }
+}
+
+/// Classify every lap of every driver.
+pub fn classify_session(session: &Session, config: &ClassifyConfig) -> LapClasses {
+    let mut classes = LapClasses::default();
+    // Slow laps (and pit laps, which are slow too) by driver, for the
+    // safety-car check
+    let mut slow: Vec<(usize, u32, bool)> = Vec::new();
+    let spans: Vec<Vec<LapSpan>> = session
+        .drivers
+        .iter()
+        .map(|d| d.laps.iter().map(LapSpan::new).collect())
+        .collect();
+
+    for (driver_index, driver) in session.drivers.iter().enumerate() {
+        let spans = &spans[driver_index];
+        let reference = typical_lap_time(driver, spans, config);
+        let laps = classes
+            .classes
+            .entry(driver.driver.code.clone())
+            .or_default();
+
+        for (lap, span) in driver.laps.iter().zip(spans) {
+            let pit_out = pit_run(&lap.samples, true, config);
+            let pit_in = pit_run(&lap.samples, false, config);
+            let class = match (pit_out, pit_in) {
+                (true, true) => LapClass::PitThrough,
+                (true, false) => LapClass::OutLap,
+                (false, true) => LapClass::InLap,
+                (false, false) => {
+                    let Some(duration) = span.duration else {
+                        continue;
+                    };
+                    let is_slow = reference.is_some_and(|r| duration > r * config.slow_factor);
+                    if is_slow {
+                        LapClass::CoolDown
+                    } else {
+                        LapClass::Push
+                    }
+                }
+            };
+            if class != LapClass::Push {
+                slow.push((driver_index, lap.number, class == LapClass::CoolDown));
+            }
+            laps.insert(lap.number, class);
+        }
+    }
+
+    if matches!(
+        session.session_type,
+        SessionType::Race | SessionType::Sprint
+    ) {
+        mark_safety_car(session, &spans, &slow, config, &mut classes);
+    }
+    classes
+}
+
+/// Median duration of a driver's laps within 110% of their best, leaving
+/// out pit laps.
+fn typical_lap_time(
+    driver: &DriverSession,
+    spans: &[LapSpan],
+    config: &ClassifyConfig,
+) -> Option<f64> {
+    let mut durations: Vec<f64> = driver
+        .laps
+        .iter()
+        .zip(spans)
+        .filter(|(lap, _)| {
+            !pit_run(&lap.samples, true, config) && !pit_run(&lap.samples, false, config)
+        })
+        .filter_map(|(_, span)| span.duration)
+        .collect();
+    let best = durations.iter().copied().reduce(f64::min)?;
+    durations.retain(|d| *d <= best * 1.1);
+    durations.sort_by(f64::total_cmp);
+    Some(durations[durations.len() / 2])
+}
+
+/// Whether the lap has a run at pit-lane speed near its start (`at_start`)
+/// or end.
+fn pit_run(samples: &[TelemetrySample], at_start: bool, config: &ClassifyConfig) -> bool {
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        return false;
+    };
+    let window = if at_start {
+        first.time..=first.time + config.pit_window
+    } else {
+        last.time - config.pit_window..=last.time
+    };
+    let in_pit_range = |s: &TelemetrySample| {
+        window.contains(&s.time) && (config.min_pit_speed..=config.pit_speed).contains(&s.speed)
+    };
+
+    let mut run_start: Option<f64> = None;
+    for sample in samples {
+        if !in_pit_range(sample) {
+            run_start = None;
+            continue;
+        }
+        let start = *run_start.get_or_insert(sample.time);
+        if sample.time - start >= config.min_pit_time {
+            return true;
+        }
+    }
+    false
+}
+
+/// Turn slow race laps into safety-car laps where most drivers on track are
+/// slow (or pitting) at the middle of the lap.
+fn mark_safety_car(
+    session: &Session,
+    spans: &[Vec<LapSpan>],
+    slow: &[(usize, u32, bool)],
+    config: &ClassifyConfig,
+    classes: &mut LapClasses,
+) {
+    let is_slow = |driver: usize, lap: u32| slow.iter().any(|s| s.0 == driver && s.1 == lap);
+    for &(driver_index, lap_number, is_cool_down) in slow {
+        if !is_cool_down {
+            continue;
+        }
+        let driver = &session.drivers[driver_index];
+        let Some((start, end)) = driver
+            .laps
+            .iter()
+            .position(|l| l.number == lap_number)
+            .and_then(|i| spans[driver_index][i].span)
+        else {
+            continue;
+        };
+        let middle = (start + end) / 2.0;
+
+        // The lap each driver was on at that moment
+        let (mut on_track, mut slowed) = (0, 0);
+        for (other_index, other) in session.drivers.iter().enumerate() {
+            let lap = other
+                .laps
+                .iter()
+                .zip(&spans[other_index])
+                .find(|(_, span)| {
+                    span.span
+                        .is_some_and(|(start, end)| (start..=end).contains(&middle))
+                });
+            if let Some((lap, _)) = lap {
+                on_track += 1;
+                if is_slow(other_index, lap.number) {
+                    slowed += 1;
+                }
+            }
+        }
+        if on_track >= 3 && slowed as f64 >= on_track as f64 * config.safety_car_fraction {
+            classes
+                .classes
+                .entry(driver.driver.code.clone())
+                .or_default()
+                .insert(lap_number, LapClass::SafetyCar);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Driver, DrsStatus};
+    use chrono::Utc;
+    use glam::Vec3;
+
+    /// A lap from `start` made of `(seconds, km/h)` phases, sampled at 2 Hz.
+    fn lap(number: u32, start: f64, phases: &[(f64, u16)]) -> Lap {
+        let mut samples = Vec::new();
+        let mut time = start;
+        for &(seconds, speed) in phases {
+            let end = time + seconds;
+            while time < end {
+                samples.push(TelemetrySample {
+                    position: Vec3::new(time as f32, 0.0, 0.0),
+                    time,
+                    throttle: 50,
+                    brake: 0,
+                    gear: 4,
+                    speed,
+                    drs: DrsStatus::Off,
+                });
+                time += 0.5;
+            }
+        }
+        Lap {
+            number,
+            lap_time: Some(time - start),
+            sector_times: [None; 3],
+            samples,
+            is_valid: true,
+        }
+    }
+
+    fn driver(code: &str, laps: Vec<Lap>) -> DriverSession {
+        DriverSession {
+            driver: Driver {
+                code: code.into(),
+                name: String::new(),
+                number: 1,
+                team: String::new(),
+                team_color: String::new(),
+            },
+            laps,
+        }
+    }
+
+    fn session(session_type: SessionType, drivers: Vec<DriverSession>) -> Session {
+        Session {
+            schema_version: crate::loader::CURRENT_SCHEMA_VERSION,
+            year: 2025,
+            circuit: "Austin".into(),
+            circuit_short: "austin".into(),
+            session_type,
+            date: Utc::now(),
+            drivers,
+        }
+    }
+
+    #[test]
+    fn test_qualifying_run() {
+        let laps = vec![
+            // Pit lane at 80 km/h, then building tyre temperature
+            lap(1, 0.0, &[(15.0, 80), (100.0, 180)]),
+            // A slow corner is too short to count as pit-lane driving
+            lap(2, 115.0, &[(40.0, 250), (2.5, 60), (50.0, 250)]),
+            lap(3, 207.5, &[(130.0, 160)]),
+            lap(4, 337.5, &[(90.0, 250)]),
+            lap(5, 427.5, &[(100.0, 170), (12.0, 80)]),
+        ];
+        let session = session(SessionType::Qualifying, vec![driver("VER", laps)]);
+        let classes = classify_session(&session, &ClassifyConfig::default());
+
+        let expected = [
+            LapClass::OutLap,
+            LapClass::Push,
+            LapClass::CoolDown,
+            LapClass::Push,
+            LapClass::InLap,
+        ];
+        for (number, class) in (1..).zip(expected) {
+            assert_eq!(classes.get("VER", number), Some(class), "lap {number}");
+        }
+        let representative = classes.representative_laps(&session.drivers[0]);
+        assert_eq!(
+            representative.iter().map(|l| l.number).collect::<Vec<_>>(),
+            [2, 4]
+        );
+    }
+
+    #[test]
+    fn test_safety_car_laps() {
+        // Three drivers lap together; on lap 3 everyone is slow, on lap 2
+        // only HAM is
+        let drivers = ["VER", "NOR", "HAM"]
+            .into_iter()
+            .map(|code| {
+                let lap_2 = if code == "HAM" { 130.0 } else { 95.0 };
+                let laps = vec![
+                    lap(1, 0.0, &[(95.0, 220)]),
+                    lap(2, 95.0, &[(lap_2, 220)]),
+                    lap(3, 95.0 + lap_2, &[(140.0, 150)]),
+                    lap(4, 235.0 + lap_2, &[(95.0, 220)]),
+                ];
+                driver(code, laps)
+            })
+            .collect();
+        let session = session(SessionType::Race, drivers);
+        let classes = classify_session(&session, &ClassifyConfig::default());
+
+        assert_eq!(classes.get("VER", 3), Some(LapClass::SafetyCar));
+        assert_eq!(classes.get("HAM", 2), Some(LapClass::CoolDown));
+        assert_eq!(classes.get("NOR", 4), Some(LapClass::Push));
+    }
+}
@@ -0,0 +1,525 @@
+//! ERS clipping on the straights.
+//!
+//! When the battery runs out of deployment a car "clips": with the throttle
+//! still flat, acceleration collapses and the speed plateaus or falls well
+//! before the braking point. Straights are found as for DRS (see
+//! `straight.rs`). On each push lap the acceleration is taken over
+//! `ClippingConfig::window` seconds; a clip is a full-throttle run where it
+//! stays at or below `flat_accel` for at least `min_duration`, that starts
+//! at least `brake_margin` before the end of the straight and follows real
+//! acceleration (`min_accel_before`).
+//!
+//! A car that is drag-limited also stops accelerating, at its own top
+//! speed, so a flat run only counts if the speed actually drops over it (by
+//! `min_speed_drop`) or it plateaus `plateau_margin` below the driver's own
+//! top speed on that straight over their push laps.
+//!
+//! Time lost is measured against the car carrying on at the acceleration it
+//! had just before the clip, up to the driver's own top speed on that
+//! straight. Clips under `min_time_lost` are dropped.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::classify::{LapClass, LapClasses};
+use crate::export::Exportable;
+use crate::straight::{self, Straight, StraightConfig};
+use crate::{LapTrace, Session, TelemetrySample};
+
+/// Clipping detection thresholds.
+#[derive(Debug, Clone, Copy)]
+pub struct ClippingConfig {
+    pub straights: StraightConfig,
+    /// Time over which acceleration is measured (s)
+    pub window: f64,
+    /// Highest acceleration counted as clipping (m/s²)
+    pub flat_accel: f64,
+    /// Lowest acceleration just before a clip (m/s²)
+    pub min_accel_before: f64,
+    /// Shortest clip (s)
+    pub min_duration: f64,
+    /// Closest a clip may start to the end of the straight (m)
+    pub brake_margin: f32,
+    /// Smallest fall from the highest speed of a flat run to its end that
+    /// makes it a clip (km/h)
+    pub min_speed_drop: f64,
+    /// How far below the driver's own top speed on the straight a flat run
+    /// must peak to be a clip without a drop (km/h)
+    pub plateau_margin: f64,
+    /// Smallest time lost for a clip to count (s)
+    pub min_time_lost: f64,
+}
+
+impl Default for ClippingConfig {
+    fn default() -> Self {
+        Self {
+            straights: StraightConfig::default(),
+            window: 1.0,
+            flat_accel: 0.3,
+            min_accel_before: 1.0,
+            min_duration: 0.75,
+            brake_margin: 100.0,
+            min_speed_drop: 3.0,
+            plateau_margin: 8.0,
+            min_time_lost: 0.02,
+        }
+    }
+}
+
+/// One clip on one straight.
+#[derive(Debug, Clone, Serialize)]
+pub struct Clip {
+    pub driver_code: String,
+    pub lap_number: u32,
+    pub straight: usize,
+    /// Distance from the start of the straight to the onset (m)
+    pub onset_distance: f32,
+    /// Distance left to the end of the straight at the onset (m)
+    pub distance_to_end: f32,
+    /// s
+    pub duration: f64,
+    /// km/h
+    pub onset_speed: f64,
+    /// km/h
+    pub end_speed: f64,
+    /// Time lost against carrying on accelerating (s)
+    pub time_lost: f64,
+}
+
+/// Clipping over one push lap.
+#[derive(Debug, Clone, Serialize)]
+pub struct LapClipping {
+    pub driver_code: String,
+    pub lap_number: u32,
+    pub clips: usize,
+    /// s
+    pub time_lost: f64,
+}
+
+/// One driver's clipping on one straight over their push laps.
+#[derive(Debug, Clone, Serialize)]
+pub struct StraightClipping {
+    pub driver_code: String,
+    pub straight: usize,
+    /// Push laps measured
+    pub laps: usize,
+    pub clipped_laps: usize,
+    /// Mean time lost per measured lap (s)
+    pub mean_time_lost: f64,
+    /// Mean onset distance of the clips (m)
+    pub mean_onset_distance: Option<f32>,
+}
+
+/// Clips on every push lap.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ClippingAnalysis {
+    pub straights: Vec<Straight>,
+    pub clips: Vec<Clip>,
+    /// Every push lap measured, clipped or not
+    pub laps: Vec<LapClipping>,
+}
+
+impl ClippingAnalysis {
+    pub fn for_driver<'a>(&'a self, driver_code: &'a str) -> impl Iterator<Item = &'a Clip> {
+        self.clips
+            .iter()
+            .filter(move |c| c.driver_code == driver_code)
+    }
+
+    /// Per-driver clipping on a straight, in session order.
+    pub fn straight_summary(&self, straight: usize) -> Vec<StraightClipping> {
+        let mut summaries: Vec<StraightClipping> = Vec::new();
+        for lap in &self.laps {
+            let index = match summaries
+                .iter()
+                .position(|s| s.driver_code == lap.driver_code)
+            {
+                Some(index) => index,
+                None => {
+                    summaries.push(StraightClipping {
+                        driver_code: lap.driver_code.clone(),
+                        straight,
+                        laps: 0,
+                        clipped_laps: 0,
+                        mean_time_lost: 0.0,
+                        mean_onset_distance: None,
+                    });
+                    summaries.len() - 1
+                }
+            };
+            let summary = &mut summaries[index];
+            let clips: Vec<&Clip> = self
+                .clips
+                .iter()
+                .filter(|c| c.driver_code == lap.driver_code && c.lap_number == lap.lap_number)
+                .filter(|c| c.straight == straight)
+                .collect();
+            summary.laps += 1;
+            summary.clipped_laps += usize::from(!clips.is_empty());
+            summary.mean_time_lost += clips.iter().map(|c| c.time_lost).sum::<f64>();
+        }
+        for summary in &mut summaries {
+            summary.mean_time_lost /= summary.laps as f64;
+            let onsets: Vec<f32> = self
+                .for_driver(&summary.driver_code)
+                .filter(|c| c.straight == straight)
+                .map(|c| c.onset_distance)
+                .collect();
+            summary.mean_onset_distance =
+                (!onsets.is_empty()).then(|| onsets.iter().sum::<f32>() / onsets.len() as f32);
+        }
+        summaries
+    }
+}
+
+impl Exportable for ClippingAnalysis {
+    fn title(&self) -> String {
+        "ERS clipping".into()
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        vec![
+            "Driver",
+            "Lap",
+            "Straight",
+            "Onset (m)",
+            "To end (m)",
+            "Duration (s)",
+            "Onset speed (km/h)",
+            "End speed (km/h)",
+            "Time lost (s)",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.clips
+            .iter()
+            .map(|c| {
+                vec![
+                    c.driver_code.clone(),
+                    c.lap_number.to_string(),
+                    c.straight.to_string(),
+                    format!("{:.0}", c.onset_distance),
+                    format!("{:.0}", c.distance_to_end),
+                    format!("{:.2}", c.duration),
+                    format!("{:.0}", c.onset_speed),
+                    format!("{:.0}", c.end_speed),
+                    format!("{:.3}", c.time_lost),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// One push lap down one straight.
+struct Run<'a> {
+    driver_code: &'a str,
+    lap_number: u32,
+    straight: usize,
+    trace: &'a LapTrace,
+    /// Distance along the lap where the straight starts and ends (m)
+    start_distance: f32,
+    end_distance: f32,
+    samples: Vec<&'a TelemetrySample>,
+}
+
+/// Find clips on every push lap.
+pub fn analyze_clipping(
+    session: &Session,
+    classes: &LapClasses,
+    config: &ClippingConfig,
+) -> ClippingAnalysis {
+    let Some(reference) = straight::reference_lap(session) else {
+        return ClippingAnalysis::default();
+    };
+    let straights = straight::find_straights(&reference, &config.straights);
+
+    let mut laps = Vec::new();
+    let mut traced = Vec::new();
+    for driver in &session.drivers {
+        let code = &driver.driver.code;
+        for lap in &driver.laps {
+            if classes.get(code, lap.number) != Some(LapClass::Push) {
+                continue;
+            }
+            let Some(trace) = LapTrace::new(lap) else {
+                continue;
+            };
+            laps.push(LapClipping {
+                driver_code: code.clone(),
+                lap_number: lap.number,
+                clips: 0,
+                time_lost: 0.0,
+            });
+            traced.push((code.as_str(), lap, trace));
+        }
+    }
+
+    let mut runs = Vec::new();
+    // Each driver's top speed on each straight over their push laps (km/h)
+    let mut top_speeds: HashMap<(&str, usize), u16> = HashMap::new();
+    for (code, lap, trace) in &traced {
+        let scale = trace.length() / reference.length();
+        for straight in &straights {
+            let start_distance = straight.start_distance * scale;
+            let end_distance = straight.end_distance * scale;
+            let from = trace.start_time() + trace.time_at_distance(start_distance);
+            let to = trace.start_time() + trace.time_at_distance(end_distance);
+            let run = Run {
+                driver_code: code,
+                lap_number: lap.number,
+                straight: straight.number,
+                trace,
+                start_distance,
+                end_distance,
+                samples: lap
+                    .samples
+                    .iter()
+                    .filter(|s| (from..=to).contains(&s.time))
+                    .collect(),
+            };
+            let top = top_speeds.entry((code, straight.number)).or_default();
+            *top = run.samples.iter().map(|s| s.speed).fold(*top, u16::max);
+            runs.push(run);
+        }
+    }
+
+    let mut clips = Vec::new();
+    for run in &runs {
+        let own_top = top_speeds[&(run.driver_code, run.straight)];
+        clips.extend(find_clips(run, own_top as f64, config));
+    }
+    for clip in &clips {
+        let lap = laps
+            .iter_mut()
+            .find(|l| l.driver_code == clip.driver_code && l.lap_number == clip.lap_number)
+            .expect("clips are found on measured laps");
+        lap.clips += 1;
+        lap.time_lost += clip.time_lost;
+    }
+
+    ClippingAnalysis {
+        straights,
+        clips,
+        laps,
+    }
+}
+
+/// Clips on one run, against the driver's `own_top` speed on the straight
+/// (km/h).
+fn find_clips(run: &Run, own_top: f64, config: &ClippingConfig) -> Vec<Clip> {
+    let samples = &run.samples;
+    let half = config.window / 2.0;
+    let accel = |time: f64| {
+        (speed_at(samples, time + half) - speed_at(samples, time - half)) / config.window
+    };
+    let flat: Vec<bool> = samples
+        .iter()
+        .map(|s| s.throttle >= config.straights.full_throttle && accel(s.time) <= config.flat_accel)
+        .collect();
+
+    let mut clips = Vec::new();
+    let mut index = 0;
+    while index < samples.len() {
+        if !flat[index] {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        while index < samples.len() && flat[index] {
+            index += 1;
+        }
+        let run_samples = &samples[start..index];
+        let (first, last) = (run_samples[0], run_samples[run_samples.len() - 1]);
+        let duration = last.time - first.time;
+        let onset = run.trace.distance_at(first.time - run.trace.start_time());
+        let accel_before = accel(first.time - config.window);
+        if duration < config.min_duration
+            || run.end_distance - onset < config.brake_margin
+            || first.time - config.window < samples[0].time
+            || accel_before < config.min_accel_before
+        {
+            continue;
+        }
+        // Drag-limited at its own top speed rather than out of deployment
+        let peak = run_samples.iter().map(|s| s.speed).max().unwrap_or(0) as f64;
+        let dropped = peak - last.speed as f64 >= config.min_speed_drop;
+        if !dropped && peak > own_top - config.plateau_margin {
+            continue;
+        }
+
+        // Time taken against carrying on at `accel_before`, each interval
+        // covered at the projected speed instead
+        let onset_speed = first.speed as f64 / 3.6;
+        let mut time_lost = 0.0;
+        for pair in run_samples.windows(2) {
+            let dt = pair[1].time - pair[0].time;
+            let actual = (pair[0].speed as f64 + pair[1].speed as f64) / 7.2;
+            let elapsed = (pair[0].time + pair[1].time) / 2.0 - first.time;
+            let projected = (onset_speed + accel_before * elapsed)
+                .min(own_top / 3.6)
+                .max(actual);
+            if projected > 0.0 {
+                time_lost += dt - dt * actual / projected;
+            }
+        }
+        if time_lost < config.min_time_lost {
+            continue;
+        }
+        clips.push(Clip {
+            driver_code: run.driver_code.to_string(),
+            lap_number: run.lap_number,
+            straight: run.straight,
+            onset_distance: (onset - run.start_distance).max(0.0),
+            distance_to_end: run.end_distance - onset,
+            duration,
+            onset_speed: first.speed as f64,
+            end_speed: last.speed as f64,
+            time_lost,
+        });
+    }
+    clips
+}
+
+/// Speed at a session time (m/s), interpolated and held at the ends.
+fn speed_at(samples: &[&TelemetrySample], time: f64) -> f64 {
+    let after = samples.partition_point(|s| s.time < time);
+    let speed = match (after.checked_sub(1).map(|i| samples[i]), samples.get(after)) {
+        (Some(a), Some(b)) if b.time > a.time => {
+            let t = (time - a.time) / (b.time - a.time);
+            a.speed as f64 + (b.speed as f64 - a.speed as f64) * t
+        }
+        (Some(a), _) => a.speed as f64,
+        (None, Some(b)) => b.speed as f64,
+        (None, None) => 0.0,
+    };
+    speed / 3.6
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::{self, ClassifyConfig};
+    use crate::{Driver, DriverSession, DrsStatus, Lap, SessionType};
+    use chrono::Utc;
+    use glam::Vec3;
+    use std::f32::consts::{PI, TAU};
+
+    const RADIUS: f32 = 300.0;
+
+    /// Accelerating from 60 m/s by 0.05 m/s per meter up to 90 m/s.
+    fn full(distance: f32) -> f32 {
+        60.0 + 0.05 * distance.min(600.0)
+    }
+
+    /// As `full`, holding the speed from 300 m on.
+    fn capped(distance: f32) -> f32 {
+        full(distance.min(300.0))
+    }
+
+    /// As `capped` a meter per second faster, e.g. in a tow.
+    fn towed(distance: f32) -> f32 {
+        full(distance.min(320.0))
+    }
+
+    /// A lap of a circle: full throttle over the first half at `straight`
+    /// (m/s) by distance along it, then 50 m/s.
+    fn lap(number: u32, straight: fn(f32) -> f32) -> Lap {
+        let start_time = number as f64 * 100.0;
+        let (mut angle, mut time) = (0.0f32, start_time);
+        let mut samples = Vec::new();
+        while angle < TAU {
+            let on_straight = angle < PI;
+            let speed = if on_straight {
+                straight(angle * RADIUS)
+            } else {
+                50.0
+            };
+            samples.push(TelemetrySample {
+                position: Vec3::new(RADIUS * angle.cos(), 0.0, RADIUS * angle.sin()),
+                time,
+                throttle: if on_straight { 100 } else { 40 },
+                brake: 0,
+                gear: 8,
+                speed: (speed * 3.6).round() as u16,
+                drs: DrsStatus::Off,
+            });
+            angle += speed * 0.1 / RADIUS;
+            time += 0.1;
+        }
+        Lap {
+            number,
+            lap_time: Some(time - start_time),
+            sector_times: [None; 3],
+            samples,
+            is_valid: true,
+        }
+    }
+
+    fn driver(code: &str, straights: [fn(f32) -> f32; 3]) -> DriverSession {
+        DriverSession {
+            driver: Driver {
+                code: code.into(),
+                name: String::new(),
+                number: 1,
+                team: String::new(),
+                team_color: String::new(),
+            },
+            laps: (1..=3).zip(straights).map(|(n, s)| lap(n, s)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_clipping() {
+        // LEC peaks at 80 m/s 400 m in, then fades by 0.01 m/s per meter
+        let fading = |d: f32| full(d.min(400.0)) - 0.01 * (d - 400.0).max(0.0);
+        let session = Session {
+            schema_version: crate::loader::CURRENT_SCHEMA_VERSION,
+            year: 2025,
+            circuit: "Monza".into(),
+            circuit_short: "monza".into(),
+            session_type: SessionType::Qualifying,
+            date: Utc::now(),
+            drivers: vec![
+                driver("PIA", [full; 3]),
+                driver("SAI", [towed, capped, capped]),
+                driver("NOR", [full, capped, full]),
+                driver("LEC", [fading; 3]),
+            ],
+        };
+        let classes = classify::classify_session(&session, &ClassifyConfig::default());
+        let analysis = analyze_clipping(&session, &classes, &ClippingConfig::default());
+        assert_eq!(analysis.straights.len(), 1);
+
+        // PIA reaches 90 m/s and holds it, SAI levels off at 75-76 m/s on
+        // every lap: both drag, not clipping
+        assert_eq!(analysis.for_driver("PIA").count(), 0);
+        assert_eq!(analysis.for_driver("SAI").count(), 0);
+
+        // NOR stops accelerating at 75 m/s 300 m in on lap 2 and takes
+        // about 8.6 s over the rest of the straight against 7.45 s
+        let clips: Vec<&Clip> = analysis.for_driver("NOR").collect();
+        assert_eq!(clips.len(), 1);
+        let clip = clips[0];
+        assert_eq!(clip.lap_number, 2);
+        assert!((clip.onset_distance - 320.0).abs() < 30.0, "{clip:?}");
+        assert!((clip.onset_speed - 270.0).abs() < 1.0);
+        assert!((0.9..1.3).contains(&clip.time_lost), "{clip:?}");
+
+        // LEC's speed falls on every lap even though 80 m/s is their top
+        let clips: Vec<&Clip> = analysis.for_driver("LEC").collect();
+        assert_eq!(clips.len(), 3, "{clips:?}");
+        assert!(
+            clips[0].end_speed < clips[0].onset_speed - 15.0,
+            "{clips:?}"
+        );
+        assert!(clips[0].time_lost > 0.1, "{clips:?}");
+
+        let summary = analysis.straight_summary(1);
+        let nor = summary.iter().find(|s| s.driver_code == "NOR").unwrap();
+        assert_eq!((nor.laps, nor.clipped_laps), (3, 1));
+        assert_eq!(summary[0].mean_time_lost, 0.0);
+        assert_eq!(analysis.laps.iter().filter(|l| l.clips == 1).count(), 4);
+    }
+}
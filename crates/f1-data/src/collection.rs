@@ -0,0 +1,318 @@
+//! Several sessions loaded together.
+//!
+//! A `SessionCollection` holds e.g. qualifying and the race of one weekend,
+//! or the same circuit across years, so a driver's laps can be compared
+//! across them: VER's pole lap against his best race lap, turn by turn.
+//! Laps are addressed with a `SessionLapRef`, which adds the session's index
+//! to the driver code and lap number.
+//!
+//! Turn boxes only line up across sessions that share a coordinate frame.
+//! Sessions of one weekend normally do; `align_to` registers the others
+//! (e.g. another year) onto one of them.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::analysis::{TurnAnalysis, TurnComparison};
+use crate::loader::{self, LoadError};
+use crate::registration::{self, Registration, RegistrationConfig};
+use crate::{Lap, Session, Turn};
+
+/// A lap in one of the sessions of a `SessionCollection`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct SessionLapRef {
+    /// Index of the session in the collection
+    pub session: usize,
+    pub driver_code: String,
+    pub lap_number: u32,
+}
+
+/// Sessions loaded side by side.
+#[derive(Debug, Default)]
+pub struct SessionCollection {
+    sessions: Vec<Session>,
+}
+
+impl SessionCollection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load several session files (through the binary cache).
+    pub fn load<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> Result<Self, LoadError> {
+        let mut collection = Self::new();
+        for path in paths {
+            collection.push(loader::load_session_cached(path)?);
+        }
+        Ok(collection)
+    }
+
+    /// Add a session and return its index.
+    ///
+    /// A session with the same year, circuit and type replaces the one
+    /// already loaded, keeping its index.
+    pub fn push(&mut self, session: Session) -> usize {
+        let existing = self.sessions.iter().position(|s| {
+            s.year == session.year
+                && s.circuit == session.circuit
+                && s.session_type == session.session_type
+        });
+        match existing {
+            Some(index) => {
+                self.sessions[index] = session;
+                index
+            }
+            None => {
+                self.sessions.push(session);
+                self.sessions.len() - 1
+            }
+        }
+    }
+
+    /// Remove every session.
+    pub fn clear(&mut self) {
+        self.sessions.clear();
+    }
+
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Session> {
+        self.sessions.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Shortest label that tells a session apart from the others loaded:
+    /// the session type, plus the year and circuit when those differ
+    /// (e.g. "Q" and "Race", or "2024 Q" and "2025 Q").
+    pub fn short_label(&self, index: usize) -> Option<String> {
+        let session = self.get(index)?;
+        let differs =
+            |get: fn(&Session) -> String| self.sessions.iter().any(|s| get(s) != get(session));
+        let mut parts = Vec::new();
+        if differs(|s| s.year.to_string()) {
+            parts.push(session.year.to_string());
+        }
+        if differs(|s| s.circuit.clone()) {
+            let circuit = if session.circuit_short.is_empty() {
+                &session.circuit
+            } else {
+                &session.circuit_short
+            };
+            parts.push(circuit.clone());
+        }
+        parts.push(session.session_type.as_str().to_string());
+        Some(parts.join(" "))
+    }
+
+    /// Move every other session at the same circuit into the coordinate frame
+    /// of the session at `reference`.
+    ///
+    /// Returns each session's registration, `None` for the reference itself,
+    /// other circuits, and sessions without a usable lap.
+    pub fn align_to(
+        &mut self,
+        reference: usize,
+        config: &RegistrationConfig,
+    ) -> Vec<Option<Registration>> {
+        if reference >= self.sessions.len() {
+            return vec![None; self.sessions.len()];
+        }
+        // Borrow the reference's fastest lap while moving the others
+        let (before, rest) = self.sessions.split_at_mut(reference);
+        let (target, after) = rest.split_first_mut().expect("reference is in bounds");
+        let target_lap = target.fastest_laps().into_iter().next().map(|(_, lap)| lap);
+        let align = |session: &mut Session| {
+            if session.circuit != target.circuit {
+                return None;
+            }
+            let (_, lap) = session.fastest_laps().into_iter().next()?;
+            let registration = registration::register_laps(lap, target_lap?, config)?;
+            registration.transform.apply_to_session(session);
+            Some(registration)
+        };
+        let mut registrations: Vec<_> = before.iter_mut().map(align).collect();
+        registrations.push(None);
+        registrations.extend(after.iter_mut().map(align));
+        registrations
+    }
+
+    /// Driver codes appearing in any session, sorted.
+    pub fn driver_codes(&self) -> Vec<&str> {
+        let mut codes: Vec<&str> = self
+            .sessions
+            .iter()
+            .flat_map(|s| s.drivers.iter().map(|d| d.driver.code.as_str()))
+            .collect();
+        codes.sort_unstable();
+        codes.dedup();
+        codes
+    }
+
+    /// Look up a lap.
+    pub fn lap(&self, lap: &SessionLapRef) -> Option<&Lap> {
+        self.get(lap.session)?
+            .get_driver(&lap.driver_code)?
+            .get_lap(lap.lap_number)
+    }
+
+    /// A driver's fastest lap in each session they took part in.
+    pub fn fastest_laps(&self, driver_code: &str) -> Vec<SessionLapRef> {
+        self.sessions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, session)| {
+                let lap = session.get_driver(driver_code)?.fastest_lap()?;
+                Some(SessionLapRef {
+                    session: index,
+                    driver_code: driver_code.to_string(),
+                    lap_number: lap.number,
+                })
+            })
+            .collect()
+    }
+
+    /// Compare laps from any of the sessions through a turn.
+    ///
+    /// Each analysis carries its driver code and, when the laps come from
+    /// more than one session, the session's short label. Laps that can't be
+    /// found are skipped.
+    pub fn compare_turn(&self, laps: &[SessionLapRef], turn: &Turn) -> TurnComparison {
+        let cross_session = laps.iter().any(|l| l.session != laps[0].session);
+        let analyses = laps
+            .iter()
+            .filter_map(|lap_ref| {
+                let lap = self.lap(lap_ref)?;
+                Some(TurnAnalysis {
+                    driver_code: Some(lap_ref.driver_code.clone()),
+                    session: self.short_label(lap_ref.session).filter(|_| cross_session),
+                    ..TurnAnalysis::analyze(lap, turn)
+                })
+            })
+            .collect();
+        TurnComparison::from_analyses(analyses, turn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registration::RigidTransform;
+    use crate::{BoundingBox, Driver, DriverSession, DrsStatus, SessionType, TelemetrySample};
+    use chrono::Utc;
+    use glam::{Vec2, Vec3};
+
+    /// A session where VER drives one straight-line lap at `speed_mps`.
+    fn session(session_type: SessionType, lap_number: u32, speed_mps: f32) -> Session {
+        let samples = (0..=30)
+            .map(|i| TelemetrySample {
+                position: Vec3::new(i as f32 * 0.1 * speed_mps, 0.0, 0.0),
+                time: i as f64 * 0.1,
+                throttle: 100,
+                brake: 0,
+                gear: 7,
+                speed: (speed_mps * 3.6) as u16,
+                drs: DrsStatus::Off,
+            })
+            .collect();
+        Session {
+            schema_version: loader::CURRENT_SCHEMA_VERSION,
+            year: 2025,
+            circuit: "Austin".into(),
+            circuit_short: "austin".into(),
+            session_type,
+            date: Utc::now(),
+            drivers: vec![DriverSession {
+                driver: Driver {
+                    code: "VER".into(),
+                    name: String::new(),
+                    number: 1,
+                    team: String::new(),
+                    team_color: String::new(),
+                },
+                laps: vec![Lap {
+                    number: lap_number,
+                    lap_time: Some(90.0),
+                    sector_times: [None; 3],
+                    samples,
+                    is_valid: true,
+                }],
+            }],
+        }
+    }
+
+    /// VER's lap round an irregular loop, in a frame moved by `frame`.
+    fn looped(year: u32, circuit: &str, frame: RigidTransform) -> Session {
+        let mut session = session(SessionType::Race, 1, 50.0);
+        session.year = year;
+        session.circuit = circuit.into();
+        for (i, sample) in session.drivers[0].laps[0].samples.iter_mut().enumerate() {
+            let t = i as f32 / 30.0 * std::f32::consts::TAU;
+            let radius = 600.0 + 120.0 * (3.0 * t).cos() + 80.0 * t.sin();
+            sample.position = frame.apply(Vec3::new(radius * t.cos(), 0.0, 0.6 * radius * t.sin()));
+        }
+        session
+    }
+
+    #[test]
+    fn test_align_to() {
+        let shift = RigidTransform {
+            rotation: 3f32.to_radians(),
+            translation: Vec2::new(-25.0, 40.0),
+        };
+        let mut sessions = SessionCollection::new();
+        sessions.push(looped(2024, "Austin", RigidTransform::IDENTITY));
+        sessions.push(looped(2025, "Austin", shift));
+        sessions.push(looped(2025, "Monza", shift));
+
+        let config = RegistrationConfig::default();
+        let registrations = sessions.align_to(0, &config);
+        assert_eq!(registrations.len(), 3);
+        assert!(registrations[0].is_none() && registrations[2].is_none());
+        assert!(registrations[1].unwrap().rms_error < 2.0);
+
+        let position =
+            |index: usize| sessions.get(index).unwrap().drivers[0].laps[0].samples[10].position;
+        assert!(position(1).distance(position(0)) < 2.0, "{}", position(1));
+        // Other circuits are left alone
+        let monza = looped(2025, "Monza", shift);
+        assert_eq!(position(2), monza.drivers[0].laps[0].samples[10].position);
+
+        assert_eq!(sessions.align_to(3, &config), vec![None; 3]);
+    }
+
+    #[test]
+    fn test_compare_across_sessions() {
+        let mut sessions = SessionCollection::new();
+        sessions.push(session(SessionType::Qualifying, 12, 60.0));
+        sessions.push(session(SessionType::Race, 40, 50.0));
+        // Reloading a session replaces it
+        assert_eq!(sessions.push(session(SessionType::Race, 40, 50.0)), 1);
+        assert_eq!(sessions.len(), 2);
+
+        let turn = Turn {
+            name: "T1".into(),
+            bounds: BoundingBox::new(0.0, 150.0, -5.0, 5.0),
+            approach: BoundingBox::new(0.0, 50.0, -5.0, 5.0),
+            apex: BoundingBox::new(50.0, 100.0, -5.0, 5.0),
+            exit: BoundingBox::new(100.0, 150.0, -5.0, 5.0),
+        };
+        let laps = sessions.fastest_laps("VER");
+        assert_eq!(laps.len(), 2);
+        let comparison = sessions.compare_turn(&laps, &turn);
+
+        assert!(comparison.is_cross_session());
+        assert_eq!(comparison.analyses[0].session.as_deref(), Some("Q"));
+        assert_eq!(comparison.analyses[1].session.as_deref(), Some("Race"));
+        assert_eq!(comparison.analyses[1].lap_number, 40);
+    }
+}
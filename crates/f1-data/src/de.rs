@@ -0,0 +1,235 @@
+//! Tolerant deserializers for exported session JSON.
+//!
+//! Exporters (and FastF1 versions) disagree on how dates and positions are
+//! written. These helpers accept every variant we have seen in the wild.
+//! The binary session cache is not self-describing, so each helper falls back
+//! to the type's own encoding when the format isn't human readable.
+
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use glam::Vec3;
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+
+/// Epoch values above this are taken to be milliseconds rather than seconds
+/// (1e11 seconds is the year 5138).
+const EPOCH_MILLIS_THRESHOLD: f64 = 1e11;
+
+/// A session date as written in the JSON, before a timezone is applied.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum SessionDate {
+    /// Date carried its own offset (or was an epoch timestamp)
+    Aware(DateTime<Utc>),
+    /// Date without timezone information
+    Naive(NaiveDateTime),
+}
+
+impl SessionDate {
+    /// Resolve to UTC, interpreting naive dates at `offset` (UTC if `None`).
+    pub(crate) fn resolve(self, offset: Option<FixedOffset>) -> DateTime<Utc> {
+        match (self, offset) {
+            (Self::Aware(date), _) => date,
+            (Self::Naive(date), None) => date.and_utc(),
+            (Self::Naive(date), Some(offset)) => offset
+                .from_local_datetime(&date)
+                .single()
+                .map_or_else(|| date.and_utc(), |d| d.with_timezone(&Utc)),
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if let Ok(date) = DateTime::parse_from_rfc3339(s) {
+            return Some(Self::Aware(date.with_timezone(&Utc)));
+        }
+        for format in ["%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%d %H:%M:%S%.f%z"] {
+            if let Ok(date) = DateTime::parse_from_str(s, format) {
+                return Some(Self::Aware(date.with_timezone(&Utc)));
+            }
+        }
+        for format in [
+            "%Y-%m-%dT%H:%M:%S%.f",
+            "%Y-%m-%d %H:%M:%S%.f",
+            "%Y-%m-%dT%H:%M",
+        ] {
+            if let Ok(date) = NaiveDateTime::parse_from_str(s, format) {
+                return Some(Self::Naive(date));
+            }
+        }
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(Self::Naive)
+    }
+
+    fn from_epoch(value: f64) -> Option<Self> {
+        let seconds = if value.abs() > EPOCH_MILLIS_THRESHOLD {
+            value / 1000.0
+        } else {
+            value
+        };
+        let whole = seconds.floor();
+        let nanos = ((seconds - whole) * 1e9).round() as u32;
+        DateTime::from_timestamp(whole as i64, nanos.min(999_999_999)).map(Self::Aware)
+    }
+}
+
+impl<'de> Deserialize<'de> for SessionDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DateVisitor;
+
+        impl Visitor<'_> for DateVisitor {
+            type Value = SessionDate;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an ISO 8601 date (with or without offset) or epoch seconds")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<SessionDate, E> {
+                SessionDate::parse(v).ok_or_else(|| {
+                    E::custom(format!(
+                        "invalid date {v:?}: expected ISO 8601 (e.g. \"2025-10-18T21:00:00Z\" or \"2025-10-18T21:00:00\") or epoch seconds"
+                    ))
+                })
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<SessionDate, E> {
+                self.visit_f64(v as f64)
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<SessionDate, E> {
+                self.visit_f64(v as f64)
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<SessionDate, E> {
+                SessionDate::from_epoch(v)
+                    .ok_or_else(|| E::custom(format!("epoch timestamp {v} is out of range")))
+            }
+        }
+
+        deserializer.deserialize_any(DateVisitor)
+    }
+}
+
+/// Deserialize a date in any supported format, assuming UTC for naive dates.
+pub(crate) fn datetime_utc<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<Utc>, D::Error> {
+    if !deserializer.is_human_readable() {
+        return DateTime::<Utc>::deserialize(deserializer);
+    }
+    SessionDate::deserialize(deserializer).map(|d| d.resolve(None))
+}
+
+/// Deserialize a UTC offset written as `"+02:00"`, `"-05:00:00"` or seconds east of UTC.
+pub(crate) fn utc_offset<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<FixedOffset>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Seconds(i32),
+        Text(String),
+    }
+
+    let Some(repr) = Option::<Repr>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    let seconds = match repr {
+        Repr::Seconds(seconds) => seconds,
+        Repr::Text(text) => parse_offset(&text)
+            .ok_or_else(|| de::Error::custom(format!("invalid UTC offset {text:?}")))?,
+    };
+    FixedOffset::east_opt(seconds)
+        .map(Some)
+        .ok_or_else(|| de::Error::custom(format!("UTC offset {seconds}s is out of range")))
+}
+
+fn parse_offset(text: &str) -> Option<i32> {
+    let text = text.trim();
+    let (sign, rest) = match text.as_bytes().first()? {
+        b'-' => (-1, &text[1..]),
+        b'+' => (1, &text[1..]),
+        _ => (1, text),
+    };
+    let mut parts = rest.split(':').map(|p| p.parse::<i32>().ok());
+    let hours = parts.next()??;
+    let minutes = parts.next().unwrap_or(Some(0))?;
+    let seconds = parts.next().unwrap_or(Some(0))?;
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+/// Deserialize a position written as `[x, y, z]` or `{"x": .., "y": .., "z": ..}`.
+pub(crate) fn position<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec3, D::Error> {
+    if !deserializer.is_human_readable() {
+        return Vec3::deserialize(deserializer);
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Array([f32; 3]),
+        Object {
+            x: f32,
+            #[serde(default)]
+            y: f32,
+            z: f32,
+        },
+    }
+
+    match Repr::deserialize(deserializer) {
+        Ok(Repr::Array([x, y, z])) | Ok(Repr::Object { x, y, z }) => Ok(Vec3::new(x, y, z)),
+        Err(_) => Err(de::Error::custom(
+            "invalid position: expected [x, y, z] or {\"x\": .., \"y\": .., \"z\": ..}",
+        )),
+    }
+}
+
+/// Serde default for flags that should be `true` when missing.
+pub(crate) fn default_true() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_date_formats() {
+        let expected = Utc.with_ymd_and_hms(2025, 10, 18, 21, 0, 0).unwrap();
+        for input in [
+            "2025-10-18T21:00:00Z",
+            "2025-10-18T21:00:00",
+            "2025-10-18 21:00:00.000",
+            "2025-10-18T16:00:00-05:00",
+        ] {
+            assert_eq!(
+                SessionDate::parse(input).unwrap().resolve(None),
+                expected,
+                "{input}"
+            );
+        }
+        assert_eq!(
+            SessionDate::from_epoch(1760821200.0).unwrap().resolve(None),
+            expected
+        );
+        assert_eq!(
+            SessionDate::from_epoch(1760821200000.0)
+                .unwrap()
+                .resolve(None),
+            expected
+        );
+
+        let austin = FixedOffset::west_opt(5 * 3600);
+        let local = SessionDate::parse("2025-10-18T16:00:00").unwrap();
+        assert_eq!(local.resolve(austin), expected);
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(parse_offset("-05:00:00"), Some(-18000));
+        assert_eq!(parse_offset("+02:00"), Some(7200));
+        assert_eq!(parse_offset("bogus"), None);
+    }
+}
@@ -0,0 +1,485 @@
+//! DRS effectiveness and slipstream on the straights.
+//!
+//! Straights are found on the session's fastest lap (see `straight.rs`) as
+//! distances along that lap. Each push lap is measured on every straight
+//! (scaled to the lap's own length): the top speed, the time from one end
+//! to the other, whether DRS was open and how far behind another car it
+//! started. A straight on which anyone opened DRS is a DRS zone.
+//!
+//! Gains compare a driver's passes with their own clean passes (no DRS, no
+//! car within `tow_distance` ahead) on the same straight: DRS passes without
+//! a tow give the DRS gain, towed passes without DRS the slipstream gain,
+//! and passes with both their combined gain. DRS is only granted within a
+//! second of the car ahead, so the last bucket is often the biggest. A
+//! straight across the finish line is measured as its two parts.
+
+use serde::Serialize;
+
+use crate::classify::{LapClass, LapClasses};
+use crate::export::{number, Exportable};
+use crate::straight::{self, Straight, StraightConfig};
+use crate::{DrsStatus, Lap, LapTrace, Session};
+
+/// Straight detection and slipstream thresholds.
+#[derive(Debug, Clone, Copy)]
+pub struct DrsConfig {
+    pub straights: StraightConfig,
+    /// Largest distance to the car ahead at the start of a straight for a
+    /// tow (m)
+    pub tow_distance: f32,
+}
+
+impl Default for DrsConfig {
+    fn default() -> Self {
+        Self {
+            straights: StraightConfig::default(),
+            tow_distance: 50.0,
+        }
+    }
+}
+
+/// One push lap driven down one straight.
+#[derive(Debug, Clone, Serialize)]
+pub struct StraightPass {
+    pub driver_code: String,
+    pub lap_number: u32,
+    pub straight: usize,
+    /// Highest speed on the straight (km/h)
+    pub top_speed: f64,
+    /// Time from one end of the straight to the other (s)
+    pub time: f64,
+    /// DRS was open on the straight
+    pub drs: bool,
+    /// Distance to the nearest car ahead at the start of the straight, if
+    /// within `tow_distance` (m)
+    pub tow_gap: Option<f32>,
+}
+
+impl StraightPass {
+    fn is_clean(&self) -> bool {
+        !self.drs && self.tow_gap.is_none()
+    }
+}
+
+/// What DRS and a tow gave one driver on one straight, against their clean
+/// passes.
+#[derive(Debug, Clone, Serialize)]
+pub struct DrsEffect {
+    pub driver_code: String,
+    pub straight: usize,
+    /// Passes with DRS and no tow
+    pub drs_passes: usize,
+    /// Passes with a tow and no DRS
+    pub tow_passes: usize,
+    /// Passes with both DRS and a tow
+    pub drs_tow_passes: usize,
+    /// Passes with neither
+    pub clean_passes: usize,
+    /// Top speed gained with DRS (km/h)
+    pub drs_speed_gain: Option<f64>,
+    /// Time saved on the straight with DRS (s)
+    pub drs_time_gain: Option<f64>,
+    /// Top speed gained in a tow (km/h)
+    pub tow_speed_gain: Option<f64>,
+    /// Time saved on the straight in a tow (s)
+    pub tow_time_gain: Option<f64>,
+    /// Top speed gained with DRS in a tow (km/h)
+    pub drs_tow_speed_gain: Option<f64>,
+    /// Time saved on the straight with DRS in a tow (s)
+    pub drs_tow_time_gain: Option<f64>,
+}
+
+/// Straights, every pass down them and the per-driver gains.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DrsAnalysis {
+    pub straights: Vec<Straight>,
+    pub passes: Vec<StraightPass>,
+    /// By driver in session order, then straight
+    pub effects: Vec<DrsEffect>,
+}
+
+impl DrsAnalysis {
+    pub fn for_driver<'a>(&'a self, driver_code: &'a str) -> impl Iterator<Item = &'a DrsEffect> {
+        self.effects
+            .iter()
+            .filter(move |e| e.driver_code == driver_code)
+    }
+
+    /// Mean DRS top-speed and time gain on a straight across the drivers
+    /// with both DRS and clean passes.
+    pub fn straight_drs_gain(&self, straight: usize) -> (Option<f64>, Option<f64>) {
+        self.straight_gain(straight, |e| (e.drs_speed_gain, e.drs_time_gain))
+    }
+
+    /// Mean slipstream top-speed and time gain on a straight.
+    pub fn straight_tow_gain(&self, straight: usize) -> (Option<f64>, Option<f64>) {
+        self.straight_gain(straight, |e| (e.tow_speed_gain, e.tow_time_gain))
+    }
+
+    /// Mean top-speed and time gain with DRS in a tow on a straight.
+    pub fn straight_drs_tow_gain(&self, straight: usize) -> (Option<f64>, Option<f64>) {
+        self.straight_gain(straight, |e| (e.drs_tow_speed_gain, e.drs_tow_time_gain))
+    }
+
+    fn straight_gain(
+        &self,
+        straight: usize,
+        gain: impl Fn(&DrsEffect) -> (Option<f64>, Option<f64>),
+    ) -> (Option<f64>, Option<f64>) {
+        let gains: Vec<(Option<f64>, Option<f64>)> = self
+            .effects
+            .iter()
+            .filter(|e| e.straight == straight)
+            .map(gain)
+            .collect();
+        (
+            mean(gains.iter().filter_map(|g| g.0)),
+            mean(gains.iter().filter_map(|g| g.1)),
+        )
+    }
+}
+
+impl Exportable for DrsAnalysis {
+    fn title(&self) -> String {
+        "DRS and slipstream by straight".into()
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        vec![
+            "Driver",
+            "Straight",
+            "DRS zone",
+            "DRS passes",
+            "Tow passes",
+            "DRS+tow passes",
+            "Clean passes",
+            "DRS speed gain (km/h)",
+            "DRS time gain (s)",
+            "Tow speed gain (km/h)",
+            "Tow time gain (s)",
+            "DRS+tow speed gain (km/h)",
+            "DRS+tow time gain (s)",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.effects
+            .iter()
+            .map(|e| {
+                let drs_zone = self
+                    .straights
+                    .iter()
+                    .any(|s| s.number == e.straight && s.drs_zone);
+                vec![
+                    e.driver_code.clone(),
+                    e.straight.to_string(),
+                    drs_zone.to_string(),
+                    e.drs_passes.to_string(),
+                    e.tow_passes.to_string(),
+                    e.drs_tow_passes.to_string(),
+                    e.clean_passes.to_string(),
+                    number(e.drs_speed_gain, 1),
+                    number(e.drs_time_gain, 3),
+                    number(e.tow_speed_gain, 1),
+                    number(e.tow_time_gain, 3),
+                    number(e.drs_tow_speed_gain, 1),
+                    number(e.drs_tow_time_gain, 3),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// A lap's trace with whether it was a push lap.
+struct TracedLap<'a> {
+    lap: &'a Lap,
+    trace: LapTrace,
+    push: bool,
+    /// Enters or leaves the pit lane (a car there gives no tow)
+    pit: bool,
+}
+
+/// Find the straights and measure every push lap on them.
+pub fn analyze_drs(session: &Session, classes: &LapClasses, config: &DrsConfig) -> DrsAnalysis {
+    let Some(reference) = straight::reference_lap(session) else {
+        return DrsAnalysis::default();
+    };
+    let mut straights = straight::find_straights(&reference, &config.straights);
+    let reference_length = reference.length();
+
+    let drivers: Vec<Vec<TracedLap>> = session
+        .drivers
+        .iter()
+        .map(|driver| {
+            driver
+                .laps
+                .iter()
+                .filter_map(|lap| {
+                    let class = classes.get(&driver.driver.code, lap.number);
+                    Some(TracedLap {
+                        lap,
+                        trace: LapTrace::new(lap)?,
+                        push: class == Some(LapClass::Push),
+                        pit: class.is_some_and(|c| c.is_pit()),
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    // Fraction of a lap driven by another car at a session time
+    let progress = |laps: &[TracedLap], time: f64| {
+        let traced = laps.iter().find(|l| {
+            let start = l.trace.start_time();
+            (start..=start + l.trace.duration()).contains(&time)
+        })?;
+        if traced.pit {
+            return None;
+        }
+        let trace = &traced.trace;
+        Some(trace.distance_at(time - trace.start_time()) / trace.length())
+    };
+
+    let mut passes = Vec::new();
+    for (index, laps) in drivers.iter().enumerate() {
+        let code = &session.drivers[index].driver.code;
+        for traced in laps.iter().filter(|l| l.push) {
+            let trace = &traced.trace;
+            let scale = trace.length() / reference_length;
+            for straight in &straights {
+                let start = trace.time_at_distance(straight.start_distance * scale);
+                let end = trace.time_at_distance(straight.end_distance * scale);
+                let (from, to) = (trace.start_time() + start, trace.start_time() + end);
+                let samples: Vec<_> = traced
+                    .lap
+                    .samples
+                    .iter()
+                    .filter(|s| (from..=to).contains(&s.time))
+                    .collect();
+                let Some(top_speed) = samples.iter().map(|s| s.speed).max() else {
+                    continue;
+                };
+
+                let fraction = straight.start_distance / reference_length;
+                let tow_gap = drivers
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != index)
+                    .filter_map(|(_, other)| progress(other, from))
+                    .map(|ahead| (ahead - fraction).rem_euclid(1.0) * reference_length)
+                    .filter(|gap| *gap > 0.0 && *gap <= config.tow_distance)
+                    .min_by(f32::total_cmp);
+
+                passes.push(StraightPass {
+                    driver_code: code.clone(),
+                    lap_number: traced.lap.number,
+                    straight: straight.number,
+                    top_speed: top_speed as f64,
+                    time: end - start,
+                    drs: samples.iter().any(|s| s.drs == DrsStatus::Active),
+                    tow_gap,
+                });
+            }
+        }
+    }
+
+    for straight in &mut straights {
+        straight.drs_zone = passes
+            .iter()
+            .any(|p| p.straight == straight.number && p.drs);
+    }
+
+    let mut effects = Vec::new();
+    for driver in &session.drivers {
+        let code = &driver.driver.code;
+        for straight in &straights {
+            let own: Vec<&StraightPass> = passes
+                .iter()
+                .filter(|p| &p.driver_code == code && p.straight == straight.number)
+                .collect();
+            if own.is_empty() {
+                continue;
+            }
+            let clean: Vec<&StraightPass> = own.iter().copied().filter(|p| p.is_clean()).collect();
+            let drs: Vec<&StraightPass> = own
+                .iter()
+                .copied()
+                .filter(|p| p.drs && p.tow_gap.is_none())
+                .collect();
+            let tow: Vec<&StraightPass> = own
+                .iter()
+                .copied()
+                .filter(|p| !p.drs && p.tow_gap.is_some())
+                .collect();
+            let drs_tow: Vec<&StraightPass> = own
+                .iter()
+                .copied()
+                .filter(|p| p.drs && p.tow_gap.is_some())
+                .collect();
+            let (drs_speed_gain, drs_time_gain) = gains(&drs, &clean);
+            let (tow_speed_gain, tow_time_gain) = gains(&tow, &clean);
+            let (drs_tow_speed_gain, drs_tow_time_gain) = gains(&drs_tow, &clean);
+            effects.push(DrsEffect {
+                driver_code: code.clone(),
+                straight: straight.number,
+                drs_passes: drs.len(),
+                tow_passes: tow.len(),
+                drs_tow_passes: drs_tow.len(),
+                clean_passes: clean.len(),
+                drs_speed_gain,
+                drs_time_gain,
+                tow_speed_gain,
+                tow_time_gain,
+                drs_tow_speed_gain,
+                drs_tow_time_gain,
+            });
+        }
+    }
+
+    DrsAnalysis {
+        straights,
+        passes,
+        effects,
+    }
+}
+
+/// Mean top-speed gain and time saved of `passes` against `clean`.
+fn gains(passes: &[&StraightPass], clean: &[&StraightPass]) -> (Option<f64>, Option<f64>) {
+    let speed = |p: &[&StraightPass]| mean(p.iter().map(|p| p.top_speed));
+    let time = |p: &[&StraightPass]| mean(p.iter().map(|p| p.time));
+    (
+        speed(passes).zip(speed(clean)).map(|(a, b)| a - b),
+        time(clean).zip(time(passes)).map(|(a, b)| a - b),
+    )
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::{self, ClassifyConfig};
+    use crate::{Driver, DriverSession, SessionType, TelemetrySample};
+    use chrono::Utc;
+    use glam::Vec3;
+    use std::f32::consts::{PI, TAU};
+
+    const RADIUS: f32 = 300.0;
+
+    /// A lap of a circle: full throttle at `straight_speed` over the first
+    /// half, 50 m/s and part throttle over the second.
+    fn lap(number: u32, start_time: f64, straight_speed: f32, drs: bool) -> Lap {
+        let (mut angle, mut time) = (0.0f32, start_time);
+        let mut samples = Vec::new();
+        while angle < TAU {
+            let straight = angle < PI;
+            let speed = if straight { straight_speed } else { 50.0 };
+            samples.push(TelemetrySample {
+                position: Vec3::new(RADIUS * angle.cos(), 0.0, RADIUS * angle.sin()),
+                time,
+                throttle: if straight { 100 } else { 40 },
+                brake: 0,
+                gear: 7,
+                speed: (speed * 3.6).round() as u16,
+                drs: if drs && straight {
+                    DrsStatus::Active
+                } else {
+                    DrsStatus::Off
+                },
+            });
+            angle += speed * 0.1 / RADIUS;
+            time += 0.1;
+        }
+        Lap {
+            number,
+            lap_time: Some(time - start_time),
+            sector_times: [None; 3],
+            samples,
+            is_valid: true,
+        }
+    }
+
+    fn driver(code: &str, laps: Vec<Lap>) -> DriverSession {
+        DriverSession {
+            driver: Driver {
+                code: code.into(),
+                name: String::new(),
+                number: 1,
+                team: String::new(),
+                team_color: String::new(),
+            },
+            laps,
+        }
+    }
+
+    #[test]
+    fn test_drs_and_tow() {
+        // LEC opens DRS on lap 1; SAI starts lap 1 ~34 m behind LEC's lap 2
+        // and gets 3 m/s more from the tow; HAM starts lap 1 as close behind
+        // LEC's lap 3 with DRS open and gets 7 m/s from both
+        let session = Session {
+            schema_version: crate::loader::CURRENT_SCHEMA_VERSION,
+            year: 2025,
+            circuit: "Austin".into(),
+            circuit_short: "austin".into(),
+            session_type: SessionType::Race,
+            date: Utc::now(),
+            drivers: vec![
+                driver(
+                    "LEC",
+                    vec![
+                        lap(1, 0.0, 90.0, true),
+                        lap(2, 100.0, 85.0, false),
+                        lap(3, 200.0, 85.0, false),
+                    ],
+                ),
+                driver(
+                    "SAI",
+                    vec![lap(1, 100.4, 88.0, false), lap(2, 300.0, 85.0, false)],
+                ),
+                driver(
+                    "HAM",
+                    vec![lap(1, 200.4, 92.0, true), lap(2, 400.0, 85.0, false)],
+                ),
+            ],
+        };
+        let classes = classify::classify_session(&session, &ClassifyConfig::default());
+        let analysis = analyze_drs(&session, &classes, &DrsConfig::default());
+
+        assert_eq!(analysis.straights.len(), 1);
+        let straight = &analysis.straights[0];
+        assert!(straight.drs_zone);
+        assert!((straight.length() - PI * RADIUS).abs() < 20.0);
+
+        let lec = analysis.for_driver("LEC").next().unwrap();
+        assert_eq!((lec.drs_passes, lec.clean_passes), (1, 2));
+        assert!((lec.drs_speed_gain.unwrap() - 18.0).abs() < 0.5);
+        let expected = (PI * RADIUS / 85.0 - PI * RADIUS / 90.0) as f64;
+        assert!((lec.drs_time_gain.unwrap() - expected).abs() < 0.1);
+
+        let sai = analysis.for_driver("SAI").next().unwrap();
+        assert_eq!((sai.tow_passes, sai.clean_passes), (1, 1));
+        assert!((sai.tow_speed_gain.unwrap() - 11.0).abs() < 0.5);
+        assert!(sai.tow_time_gain.unwrap() > 0.0);
+
+        // Passes with DRS and a tow are counted in their own bucket
+        let ham = analysis.for_driver("HAM").next().unwrap();
+        assert_eq!(
+            (
+                ham.drs_passes,
+                ham.tow_passes,
+                ham.drs_tow_passes,
+                ham.clean_passes
+            ),
+            (0, 0, 1, 1)
+        );
+        assert!((ham.drs_tow_speed_gain.unwrap() - 25.2).abs() < 0.5);
+        assert_eq!(
+            analysis.straight_drs_tow_gain(straight.number),
+            (ham.drs_tow_speed_gain, ham.drs_tow_time_gain)
+        );
+    }
+}
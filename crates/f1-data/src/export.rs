@@ -0,0 +1,460 @@
+//! Export analysis results as CSV, JSON and Markdown.
+//!
+//! JSON is the result's own `Serialize` output (pretty-printed). CSV and
+//! Markdown are rendered from a flat table the result provides through
+//! [`Exportable`], so spreadsheets and reports get one row per lap or point.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::analysis::{SegmentTime, TurnAnalysis, TurnComparison};
+use crate::classify::{LapClass, LapClasses};
+use crate::trace::DeltaTrace;
+use crate::{DriverSession, TurnSegment};
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Failed to write export: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to serialize export: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Unknown export format for {0} (expected .csv, .json or .md)")]
+    UnknownFormat(String),
+}
+
+/// Output format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [
+        ExportFormat::Csv,
+        ExportFormat::Json,
+        ExportFormat::Markdown,
+    ];
+
+    /// File extension, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Markdown => "md",
+        }
+    }
+
+    /// Format for a file path, from its extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            "md" | "markdown" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// An analysis result that can be exported.
+pub trait Exportable: Serialize {
+    /// Heading for Markdown output.
+    fn title(&self) -> String;
+    /// Column headers for CSV and Markdown.
+    fn headers(&self) -> Vec<&'static str>;
+    /// One row per item, cells already formatted.
+    fn rows(&self) -> Vec<Vec<String>>;
+}
+
+/// Write `value` to `writer` in `format`.
+pub fn export<T: Exportable>(
+    value: &T,
+    format: ExportFormat,
+    mut writer: impl Write,
+) -> Result<(), ExportError> {
+    match format {
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, value)?;
+            writeln!(writer)?;
+        }
+        ExportFormat::Csv => {
+            write_csv_row(&mut writer, value.headers())?;
+            for row in value.rows() {
+                write_csv_row(&mut writer, &row)?;
+            }
+        }
+        ExportFormat::Markdown => {
+            writeln!(writer, "## {}\n", value.title())?;
+            let headers = value.headers();
+            write_markdown_row(&mut writer, &headers)?;
+            write_markdown_row(&mut writer, headers.iter().map(|_| "---"))?;
+            for row in value.rows() {
+                write_markdown_row(&mut writer, &row)?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Export to a string.
+pub fn export_to_string<T: Exportable>(
+    value: &T,
+    format: ExportFormat,
+) -> Result<String, ExportError> {
+    let mut buffer = Vec::new();
+    export(value, format, &mut buffer)?;
+    Ok(String::from_utf8(buffer).expect("exports are UTF-8"))
+}
+
+/// Export to a file, choosing the format from its extension.
+pub fn export_to_file<T: Exportable>(value: &T, path: impl AsRef<Path>) -> Result<(), ExportError> {
+    let path = path.as_ref();
+    let format = ExportFormat::from_path(path)
+        .ok_or_else(|| ExportError::UnknownFormat(path.display().to_string()))?;
+    export(value, format, BufWriter::new(File::create(path)?))
+}
+
+fn write_csv_row<S: AsRef<str>>(
+    writer: &mut impl Write,
+    cells: impl IntoIterator<Item = S>,
+) -> io::Result<()> {
+    let cells: Vec<String> = cells
+        .into_iter()
+        .map(|cell| {
+            let cell = cell.as_ref();
+            if cell.contains([',', '"', '\n']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_string()
+            }
+        })
+        .collect();
+    writeln!(writer, "{}", cells.join(","))
+}
+
+fn write_markdown_row<S: AsRef<str>>(
+    writer: &mut impl Write,
+    cells: impl IntoIterator<Item = S>,
+) -> io::Result<()> {
+    let cells: Vec<String> = cells
+        .into_iter()
+        .map(|cell| cell.as_ref().replace('|', "\\|"))
+        .collect();
+    writeln!(writer, "| {} |", cells.join(" | "))
+}
+
+/// Format an optional number with `decimals`, empty if missing.
+pub(crate) fn number(value: Option<f64>, decimals: usize) -> String {
+    value.map_or_else(String::new, |v| format!("{v:.decimals$}"))
+}
+
+/// Lap times of one driver.
+#[derive(Debug, Clone, Serialize)]
+pub struct LapTable {
+    pub driver_code: String,
+    pub driver_name: String,
+    pub laps: Vec<LapRow>,
+}
+
+/// One lap in a `LapTable`.
+#[derive(Debug, Clone, Serialize)]
+pub struct LapRow {
+    pub lap_number: u32,
+    pub lap_time: Option<f64>,
+    pub sector_times: [Option<f64>; 3],
+    pub is_valid: bool,
+    pub sample_count: usize,
+    /// Whether this is the driver's fastest lap
+    pub is_fastest: bool,
+    /// Out-lap, push lap, etc. (see `LapTable::classified`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<LapClass>,
+}
+
+impl LapTable {
+    pub fn new(driver: &DriverSession) -> Self {
+        let fastest = driver.fastest_lap().map(|l| l.number);
+        Self {
+            driver_code: driver.driver.code.clone(),
+            driver_name: driver.driver.name.clone(),
+            laps: driver
+                .laps
+                .iter()
+                .map(|lap| LapRow {
+                    lap_number: lap.number,
+                    lap_time: lap.lap_time,
+                    sector_times: lap.sector_times,
+                    is_valid: lap.is_valid,
+                    sample_count: lap.samples.len(),
+                    is_fastest: fastest == Some(lap.number),
+                    class: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Lap table with each lap's class from `classify::classify_session`.
+    pub fn classified(driver: &DriverSession, classes: &LapClasses) -> Self {
+        let mut table = Self::new(driver);
+        for row in &mut table.laps {
+            row.class = classes.get(&driver.driver.code, row.lap_number);
+        }
+        table
+    }
+
+    fn has_classes(&self) -> bool {
+        self.laps.iter().any(|lap| lap.class.is_some())
+    }
+}
+
+impl Exportable for LapTable {
+    fn title(&self) -> String {
+        format!("{} laps", self.driver_code)
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        let mut headers = vec![
+            "Lap", "Time (s)", "S1 (s)", "S2 (s)", "S3 (s)", "Valid", "Samples", "Fastest",
+        ];
+        if self.has_classes() {
+            headers.push("Class");
+        }
+        headers
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let has_classes = self.has_classes();
+        self.laps
+            .iter()
+            .map(|lap| {
+                let [s1, s2, s3] = lap.sector_times.map(|s| number(s, 3));
+                let mut row = vec![
+                    lap.lap_number.to_string(),
+                    number(lap.lap_time, 3),
+                    s1,
+                    s2,
+                    s3,
+                    lap.is_valid.to_string(),
+                    lap.sample_count.to_string(),
+                    lap.is_fastest.to_string(),
+                ];
+                if has_classes {
+                    row.push(lap.class.map_or("", |c| c.as_str()).to_string());
+                }
+                row
+            })
+            .collect()
+    }
+}
+
+const ANALYSIS_HEADERS: [&str; 9] = [
+    "Driver",
+    "Lap",
+    "Turn (s)",
+    "Approach (s)",
+    "Apex (s)",
+    "Exit (s)",
+    "Entry (km/h)",
+    "Min (km/h)",
+    "Exit (km/h)",
+];
+
+fn analysis_row(analysis: &TurnAnalysis) -> Vec<String> {
+    let time = |segment: &Option<SegmentTime>| number(segment.as_ref().map(|s| s.time), 3);
+    let total = analysis.total_time.as_ref();
+    let speed =
+        |get: fn(&SegmentTime) -> u16| total.map_or_else(String::new, |t| get(t).to_string());
+    vec![
+        analysis.driver_code.clone().unwrap_or_default(),
+        analysis.lap_number.to_string(),
+        time(&analysis.total_time),
+        time(&analysis.approach),
+        time(&analysis.apex),
+        time(&analysis.exit),
+        speed(|t| t.entry_speed),
+        speed(|t| t.min_speed),
+        speed(|t| t.exit_speed),
+    ]
+}
+
+impl Exportable for TurnAnalysis {
+    fn title(&self) -> String {
+        let title = match &self.driver_code {
+            Some(code) => format!("{code} lap {}", self.lap_number),
+            None => format!("Lap {}", self.lap_number),
+        };
+        match &self.session {
+            Some(session) => format!("{title} ({session})"),
+            None => title,
+        }
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        ANALYSIS_HEADERS.to_vec()
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        vec![analysis_row(self)]
+    }
+}
+
+impl Exportable for TurnComparison {
+    fn title(&self) -> String {
+        self.turn_name.clone()
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        let mut headers = ANALYSIS_HEADERS.to_vec();
+        headers.insert(3, "Gap (s)");
+        headers.push("Segment bests");
+        if self.is_cross_session() {
+            headers.insert(1, "Session");
+        }
+        headers
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let leader = self.analyses.first().and_then(|a| a.total_time.as_ref());
+        let segments = [
+            (TurnSegment::Approach, "approach"),
+            (TurnSegment::Apex, "apex"),
+            (TurnSegment::Exit, "exit"),
+        ];
+        self.analyses
+            .iter()
+            .map(|analysis| {
+                let gap = analysis
+                    .total_time
+                    .as_ref()
+                    .zip(leader)
+                    .map(|(t, leader)| t.time - leader.time);
+                let bests: Vec<&str> = segments
+                    .iter()
+                    .filter(|(segment, _)| {
+                        self.segment_best(*segment)
+                            .is_some_and(|best| std::ptr::eq(best, analysis))
+                    })
+                    .map(|(_, name)| *name)
+                    .collect();
+
+                let mut row = analysis_row(analysis);
+                row.insert(3, number(gap, 3));
+                row.push(bests.join(" "));
+                if self.is_cross_session() {
+                    row.insert(1, analysis.session.clone().unwrap_or_default());
+                }
+                row
+            })
+            .collect()
+    }
+}
+
+impl Exportable for DeltaTrace {
+    fn title(&self) -> String {
+        format!(
+            "{} lap {} vs {} lap {}",
+            self.driver_code,
+            self.lap_number,
+            self.reference_driver_code,
+            self.reference_lap_number
+        )
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        vec![
+            "Distance (m)",
+            "Delta (s)",
+            "Speed (km/h)",
+            "Reference speed (km/h)",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.points
+            .iter()
+            .map(|p| {
+                vec![
+                    format!("{:.1}", p.distance),
+                    format!("{:.3}", p.delta),
+                    p.speed.to_string(),
+                    p.reference_speed.to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Driver, DrsStatus, Lap, TelemetrySample};
+    use glam::Vec3;
+
+    fn driver() -> DriverSession {
+        let sample = |time| TelemetrySample {
+            position: Vec3::ZERO,
+            time,
+            throttle: 100,
+            brake: 0,
+            gear: 7,
+            speed: 280,
+            drs: DrsStatus::Off,
+        };
+        let lap = |number, lap_time: Option<f64>| Lap {
+            number,
+            lap_time,
+            sector_times: [Some(30.1), None, Some(31.2)],
+            samples: lap_time.map_or_else(Vec::new, |t| vec![sample(0.0), sample(t)]),
+            is_valid: true,
+        };
+        DriverSession {
+            driver: Driver {
+                code: "VER".into(),
+                name: "Max Verstappen, Jr.".into(),
+                number: 1,
+                team: "Red Bull Racing".into(),
+                team_color: "#3671C6".into(),
+            },
+            laps: vec![lap(1, None), lap(2, Some(92.5))],
+        }
+    }
+
+    #[test]
+    fn test_lap_table_formats() {
+        let table = LapTable::new(&driver());
+
+        let csv = export_to_string(&table, ExportFormat::Csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("Lap,Time (s),S1 (s),S2 (s),S3 (s),Valid,Samples,Fastest")
+        );
+        assert_eq!(lines.nth(1), Some("2,92.500,30.100,,31.200,true,2,true"));
+
+        let markdown = export_to_string(&table, ExportFormat::Markdown).unwrap();
+        assert!(markdown.starts_with("## VER laps\n\n| Lap | Time (s) |"));
+        assert!(markdown.contains("| --- | --- |"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&export_to_string(&table, ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["driver_name"], "Max Verstappen, Jr.");
+        assert_eq!(json["laps"][1]["lap_time"], 92.5);
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ExportFormat::from_path("out/T1.MD"),
+            Some(ExportFormat::Markdown)
+        );
+        assert_eq!(ExportFormat::from_path("laps.csv"), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::from_path("laps.xlsx"), None);
+    }
+}
@@ -0,0 +1,34 @@
+//! Display formatting shared by the CLI, the HTML report and the app.
+
+/// `m:ss.sss`.
+pub fn lap_time(seconds: f64) -> String {
+    lap_time_decimals(seconds, 3)
+}
+
+/// `m:ss` with `decimals` places, rounded before splitting off the minutes
+/// so 59.9996 s reads "1:00.000" rather than "0:60.000".
+pub fn lap_time_decimals(seconds: f64, decimals: usize) -> String {
+    let scale = 10f64.powi(decimals as i32);
+    let total = (seconds.max(0.0) * scale).round();
+    let minutes = (total / (60.0 * scale)).floor();
+    let rest = (total - minutes * 60.0 * scale) / scale;
+    let width = if decimals > 0 { decimals + 3 } else { 2 };
+    format!("{}:{rest:0width$.decimals$}", minutes as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lap_time() {
+        assert_eq!(lap_time(92.5), "1:32.500");
+        assert_eq!(lap_time(61.0234), "1:01.023");
+        assert_eq!(lap_time(0.0), "0:00.000");
+        assert_eq!(lap_time(59.9996), "1:00.000");
+        assert_eq!(lap_time(119.9994), "1:59.999");
+        assert_eq!(lap_time_decimals(59.96, 1), "1:00.0");
+        assert_eq!(lap_time_decimals(83.44, 1), "1:23.4");
+        assert_eq!(lap_time_decimals(89.6, 0), "1:30");
+    }
+}
@@ -0,0 +1,409 @@
+//! Gear usage and shift points.
+//!
+//! A shift is a change of `gear` from one sample to the next, placed at the
+//! later sample with its speed, position and distance into the lap. Gear 0
+//! is a dropout in the data rather than neutral: shifts compare against the
+//! last real gear, so 4 -> 0 -> 5 is one upshift, and 4 -> 0 -> 4 none.
+//! Time in gear adds each sample interval to the gear the car was in at its
+//! start, a dropout's time going to the gear before it.
+//!
+//! For turns, the gear is the one at the lowest-speed sample inside the
+//! turn's bounds, so drivers can be compared on e.g. taking T11 in second
+//! rather than third.
+
+use glam::Vec3;
+use serde::Serialize;
+
+use crate::classify::{LapClass, LapClasses};
+use crate::export::Exportable;
+use crate::{CircuitTurns, Lap, LapTrace, Session, Turn};
+
+/// Highest gear counted; time in gear is indexed 0 (neutral) to this.
+pub const MAX_GEAR: usize = 8;
+
+/// A change of gear.
+#[derive(Debug, Clone, Serialize)]
+pub struct GearShift {
+    /// Time since the lap start (s)
+    pub lap_time: f64,
+    /// Distance into the lap (m)
+    pub distance: f32,
+    pub from: u8,
+    pub to: u8,
+    /// Speed at the shift (km/h)
+    pub speed: u16,
+    pub position: Vec3,
+}
+
+impl GearShift {
+    pub fn is_upshift(&self) -> bool {
+        self.to > self.from
+    }
+}
+
+/// Shifts and time in each gear over one lap.
+#[derive(Debug, Clone, Serialize)]
+pub struct LapGears {
+    pub driver_code: String,
+    pub lap_number: u32,
+    pub shifts: Vec<GearShift>,
+    /// Seconds in each gear, indexed by gear (0 only for a lap without any
+    /// gear data)
+    pub time_in_gear: [f64; MAX_GEAR + 1],
+}
+
+impl LapGears {
+    /// `None` for laps with fewer than two samples.
+    pub fn new(driver_code: &str, lap: &Lap) -> Option<Self> {
+        let trace = LapTrace::new(lap)?;
+        let mut shifts = Vec::new();
+        let mut time_in_gear = [0.0; MAX_GEAR + 1];
+        // Last real gear, starting from the first one if the lap opens on a
+        // dropout
+        let mut gear = lap
+            .samples
+            .iter()
+            .map(|s| s.gear)
+            .find(|&g| g != 0)
+            .unwrap_or(0);
+        for pair in lap.samples.windows(2) {
+            let (before, after) = (&pair[0], &pair[1]);
+            if before.gear != 0 {
+                gear = before.gear;
+            }
+            time_in_gear[(gear as usize).min(MAX_GEAR)] += after.time - before.time;
+            if after.gear == 0 || after.gear == gear {
+                continue;
+            }
+            let lap_time = after.time - trace.start_time();
+            shifts.push(GearShift {
+                lap_time,
+                distance: trace.distance_at(lap_time),
+                from: gear,
+                to: after.gear,
+                speed: after.speed,
+                position: after.position,
+            });
+        }
+        Some(Self {
+            driver_code: driver_code.to_string(),
+            lap_number: lap.number,
+            shifts,
+            time_in_gear,
+        })
+    }
+
+    pub fn upshifts(&self) -> impl Iterator<Item = &GearShift> {
+        self.shifts.iter().filter(|s| s.is_upshift())
+    }
+
+    pub fn downshifts(&self) -> impl Iterator<Item = &GearShift> {
+        self.shifts.iter().filter(|s| !s.is_upshift())
+    }
+}
+
+impl Exportable for LapGears {
+    fn title(&self) -> String {
+        format!("{} lap {} shifts", self.driver_code, self.lap_number)
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        vec![
+            "Time (s)",
+            "Distance (m)",
+            "From",
+            "To",
+            "Speed (km/h)",
+            "X",
+            "Z",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.shifts
+            .iter()
+            .map(|s| {
+                vec![
+                    format!("{:.3}", s.lap_time),
+                    format!("{:.0}", s.distance),
+                    s.from.to_string(),
+                    s.to.to_string(),
+                    s.speed.to_string(),
+                    format!("{:.1}", s.position.x),
+                    format!("{:.1}", s.position.z),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Gear at a turn's lowest-speed point on one lap.
+#[derive(Debug, Clone, Serialize)]
+pub struct TurnGear {
+    pub turn: String,
+    pub driver_code: String,
+    pub lap_number: u32,
+    /// km/h
+    pub min_speed: u16,
+    pub gear: u8,
+}
+
+impl TurnGear {
+    /// `None` if the lap has no samples inside the turn.
+    pub fn new(driver_code: &str, lap: &Lap, turn: &Turn) -> Option<Self> {
+        let slowest = turn
+            .filter_samples(&lap.samples)
+            .into_iter()
+            .min_by_key(|s| s.speed)?;
+        Some(Self {
+            turn: turn.name.clone(),
+            driver_code: driver_code.to_string(),
+            lap_number: lap.number,
+            min_speed: slowest.speed,
+            gear: slowest.gear,
+        })
+    }
+}
+
+/// How one driver takes one turn over their push laps.
+#[derive(Debug, Clone, Serialize)]
+pub struct TurnGearSummary {
+    pub turn: String,
+    pub driver_code: String,
+    /// Laps at the min-speed point in each gear, indexed by gear
+    pub gear_counts: [usize; MAX_GEAR + 1],
+    /// Mean minimum speed (km/h)
+    pub mean_min_speed: f64,
+}
+
+impl TurnGearSummary {
+    /// The gear used most often (the lower one on a tie).
+    pub fn usual_gear(&self) -> u8 {
+        let mut best = 0;
+        for (gear, &count) in self.gear_counts.iter().enumerate() {
+            if count > self.gear_counts[best] {
+                best = gear;
+            }
+        }
+        best as u8
+    }
+
+    pub fn laps(&self) -> usize {
+        self.gear_counts.iter().sum()
+    }
+}
+
+/// Gear use over a session's push laps.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GearUsage {
+    pub laps: Vec<LapGears>,
+    pub turns: Vec<TurnGear>,
+}
+
+impl GearUsage {
+    pub fn lap(&self, driver_code: &str, lap_number: u32) -> Option<&LapGears> {
+        self.laps
+            .iter()
+            .find(|l| l.driver_code == driver_code && l.lap_number == lap_number)
+    }
+
+    /// Each driver's share of time in each gear over their laps.
+    pub fn gear_shares(&self, driver_code: &str) -> Option<[f64; MAX_GEAR + 1]> {
+        let mut total = [0.0; MAX_GEAR + 1];
+        for lap in self.laps.iter().filter(|l| l.driver_code == driver_code) {
+            for (sum, time) in total.iter_mut().zip(lap.time_in_gear) {
+                *sum += time;
+            }
+        }
+        let lap_time: f64 = total.iter().sum();
+        (lap_time > 0.0).then(|| total.map(|t| t / lap_time))
+    }
+
+    /// Per-driver gears at a turn's min-speed point, in session order.
+    pub fn turn_summary(&self, turn: &str) -> Vec<TurnGearSummary> {
+        let mut summaries: Vec<TurnGearSummary> = Vec::new();
+        for lap in self.turns.iter().filter(|t| t.turn == turn) {
+            let index = match summaries
+                .iter()
+                .position(|s| s.driver_code == lap.driver_code)
+            {
+                Some(index) => index,
+                None => {
+                    summaries.push(TurnGearSummary {
+                        turn: turn.to_string(),
+                        driver_code: lap.driver_code.clone(),
+                        gear_counts: [0; MAX_GEAR + 1],
+                        mean_min_speed: 0.0,
+                    });
+                    summaries.len() - 1
+                }
+            };
+            let summary = &mut summaries[index];
+            summary.gear_counts[(lap.gear as usize).min(MAX_GEAR)] += 1;
+            summary.mean_min_speed += lap.min_speed as f64;
+        }
+        for summary in &mut summaries {
+            summary.mean_min_speed /= summary.laps() as f64;
+        }
+        summaries
+    }
+}
+
+impl Exportable for GearUsage {
+    fn title(&self) -> String {
+        "Time in gear".into()
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        vec!["Driver", "Lap", "Gear", "Time (s)", "Turns"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        for lap in &self.laps {
+            for (gear, time) in lap.time_in_gear.iter().enumerate() {
+                // Turns taken in this gear at their min-speed point
+                let turns: Vec<&str> = self
+                    .turns
+                    .iter()
+                    .filter(|t| t.driver_code == lap.driver_code && t.lap_number == lap.lap_number)
+                    .filter(|t| t.gear as usize == gear)
+                    .map(|t| t.turn.as_str())
+                    .collect();
+                if *time > 0.0 || !turns.is_empty() {
+                    rows.push(vec![
+                        lap.driver_code.clone(),
+                        lap.lap_number.to_string(),
+                        gear.to_string(),
+                        format!("{time:.3}"),
+                        turns.join(" "),
+                    ]);
+                }
+            }
+        }
+        rows
+    }
+}
+
+/// Shifts and time in gear for every push lap, plus the gear at each turn's
+/// min-speed point when turns are given.
+pub fn analyze_gears(
+    session: &Session,
+    classes: &LapClasses,
+    turns: Option<&CircuitTurns>,
+) -> GearUsage {
+    let mut usage = GearUsage::default();
+    for driver in &session.drivers {
+        let code = &driver.driver.code;
+        let push_laps = driver
+            .laps
+            .iter()
+            .filter(|lap| classes.get(code, lap.number) == Some(LapClass::Push));
+        for lap in push_laps {
+            usage.laps.extend(LapGears::new(code, lap));
+            for turn in turns.map(|t| t.turns.as_slice()).unwrap_or_default() {
+                usage.turns.extend(TurnGear::new(code, lap, turn));
+            }
+        }
+    }
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoundingBox, DrsStatus, TelemetrySample};
+
+    /// A straight line along X, one sample per second, in the given gears
+    /// and speeds.
+    fn lap(gears_and_speeds: &[(u8, u16)]) -> Lap {
+        Lap {
+            number: 4,
+            lap_time: None,
+            sector_times: [None; 3],
+            samples: gears_and_speeds
+                .iter()
+                .enumerate()
+                .map(|(i, &(gear, speed))| TelemetrySample {
+                    position: Vec3::new(i as f32 * 50.0, 0.0, 0.0),
+                    time: 100.0 + i as f64,
+                    throttle: 100,
+                    brake: 0,
+                    gear,
+                    speed,
+                    drs: DrsStatus::Off,
+                })
+                .collect(),
+            is_valid: true,
+        }
+    }
+
+    #[test]
+    fn test_shifts_and_time_in_gear() {
+        // 3 -> 4, a neutral dropout, 4 -> 2 in one go, then 2 -> 3
+        let lap = lap(&[
+            (3, 150),
+            (4, 180),
+            (4, 200),
+            (0, 200),
+            (4, 190),
+            (2, 100),
+            (2, 90),
+            (3, 130),
+        ]);
+        let gears = LapGears::new("HUL", &lap).unwrap();
+
+        let shifts: Vec<(u8, u8)> = gears.shifts.iter().map(|s| (s.from, s.to)).collect();
+        assert_eq!(shifts, [(3, 4), (4, 2), (2, 3)]);
+        assert_eq!(gears.upshifts().count(), 2);
+        let downshift = gears.downshifts().next().unwrap();
+        assert_eq!(downshift.speed, 100);
+        assert!((downshift.lap_time - 5.0).abs() < 1e-9);
+        assert!((downshift.distance - 250.0).abs() < 1e-3);
+
+        // The dropout's second counts towards fourth
+        assert_eq!(gears.time_in_gear[2], 2.0);
+        assert_eq!(gears.time_in_gear[4], 4.0);
+        assert_eq!(gears.time_in_gear[0], 0.0);
+        assert_eq!(gears.time_in_gear.iter().sum::<f64>(), 7.0);
+
+        // The slowest point inside the box is the 90 km/h sample in 2nd
+        let bounds = BoundingBox::new(180.0, 320.0, -10.0, 10.0);
+        let turn = Turn {
+            name: "T11".into(),
+            bounds: bounds.clone(),
+            approach: bounds.clone(),
+            apex: bounds.clone(),
+            exit: bounds,
+        };
+        let at_turn = TurnGear::new("HUL", &lap, &turn).unwrap();
+        assert_eq!((at_turn.gear, at_turn.min_speed), (2, 90));
+
+        let usage = GearUsage {
+            laps: vec![gears],
+            turns: vec![
+                at_turn.clone(),
+                TurnGear {
+                    gear: 3,
+                    ..at_turn.clone()
+                },
+                at_turn,
+            ],
+        };
+        let summary = &usage.turn_summary("T11")[0];
+        assert_eq!((summary.usual_gear(), summary.laps()), (2, 3));
+    }
+
+    #[test]
+    fn test_shift_across_dropout() {
+        let lap = lap(&[(4, 200), (0, 210), (5, 220), (5, 230)]);
+        let gears = LapGears::new("HUL", &lap).unwrap();
+
+        let shifts: Vec<(u8, u8)> = gears.shifts.iter().map(|s| (s.from, s.to)).collect();
+        assert_eq!(shifts, [(4, 5)]);
+        assert!((gears.shifts[0].lap_time - 2.0).abs() < 1e-9);
+        assert_eq!(gears.time_in_gear[4], 2.0);
+        assert_eq!(gears.time_in_gear[5], 1.0);
+        assert_eq!(gears.time_in_gear[0], 0.0);
+    }
+}
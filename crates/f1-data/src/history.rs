@@ -0,0 +1,253 @@
+//! Race history: positions and gaps lap by lap.
+//!
+//! Every driver's cumulative race time at the end of each lap is the sum of
+//! their lap times so far, so at lap `n` the drivers who completed it are
+//! ordered by that sum and the gap to the leader is the difference. A lap
+//! without a recorded time falls back to the span of its telemetry; a lap
+//! with neither ends the driver's history, as after a retirement.
+
+use serde::Serialize;
+
+use crate::export::Exportable;
+use crate::pit::PitStops;
+use crate::{DriverSession, Session};
+
+/// Where a driver stood at the end of a lap.
+#[derive(Debug, Clone, Serialize)]
+pub struct LapStanding {
+    pub lap_number: u32,
+    /// 1 for the leader
+    pub position: usize,
+    /// Race time at the end of the lap (s)
+    pub race_time: f64,
+    /// Time behind the leader crossing the line at the end of the same lap
+    /// (s)
+    pub gap: f64,
+    /// Entered the pit lane on this lap
+    pub pitted: bool,
+}
+
+/// One driver's standings, lap by lap.
+#[derive(Debug, Clone, Serialize)]
+pub struct DriverHistory {
+    pub driver_code: String,
+    pub laps: Vec<LapStanding>,
+}
+
+impl DriverHistory {
+    pub fn lap(&self, lap_number: u32) -> Option<&LapStanding> {
+        self.laps.iter().find(|l| l.lap_number == lap_number)
+    }
+}
+
+/// Positions and gaps of every driver over a race.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RaceHistory {
+    /// In session driver order
+    pub drivers: Vec<DriverHistory>,
+}
+
+impl RaceHistory {
+    /// Build the history from lap times, marking laps with a pit stop.
+    pub fn new(session: &Session, stops: &PitStops) -> Self {
+        let race_times: Vec<Vec<(u32, f64)>> = session.drivers.iter().map(race_times).collect();
+        let laps = race_times
+            .iter()
+            .flat_map(|times| times.last().map(|t| t.0))
+            .max()
+            .unwrap_or(0);
+
+        let mut drivers: Vec<DriverHistory> = session
+            .drivers
+            .iter()
+            .map(|d| DriverHistory {
+                driver_code: d.driver.code.clone(),
+                laps: Vec::new(),
+            })
+            .collect();
+        for lap_number in 1..=laps {
+            let mut finishers: Vec<(usize, f64)> = race_times
+                .iter()
+                .enumerate()
+                .filter_map(|(i, times)| {
+                    let (_, time) = times.iter().find(|t| t.0 == lap_number)?;
+                    Some((i, *time))
+                })
+                .collect();
+            finishers.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let Some(&(_, leader)) = finishers.first() else {
+                continue;
+            };
+            for (position, (i, race_time)) in finishers.into_iter().enumerate() {
+                let driver = &mut drivers[i];
+                let pitted = stops
+                    .for_driver(&driver.driver_code)
+                    .any(|stop| stop.lap_number == lap_number);
+                driver.laps.push(LapStanding {
+                    lap_number,
+                    position: position + 1,
+                    race_time,
+                    gap: race_time - leader,
+                    pitted,
+                });
+            }
+        }
+        Self { drivers }
+    }
+
+    pub fn get_driver(&self, driver_code: &str) -> Option<&DriverHistory> {
+        self.drivers.iter().find(|d| d.driver_code == driver_code)
+    }
+
+    /// Last lap completed by anyone.
+    pub fn lap_count(&self) -> u32 {
+        self.drivers
+            .iter()
+            .flat_map(|d| d.laps.last())
+            .map(|l| l.lap_number)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Drivers in order at the end of a lap.
+    pub fn standings(&self, lap_number: u32) -> Vec<(&str, &LapStanding)> {
+        let mut standings: Vec<(&str, &LapStanding)> = self
+            .drivers
+            .iter()
+            .filter_map(|d| Some((d.driver_code.as_str(), d.lap(lap_number)?)))
+            .collect();
+        standings.sort_by_key(|(_, lap)| lap.position);
+        standings
+    }
+}
+
+impl Exportable for RaceHistory {
+    fn title(&self) -> String {
+        "Race history".into()
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        vec![
+            "Lap",
+            "Position",
+            "Driver",
+            "Race time (s)",
+            "Gap (s)",
+            "Pit",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        (1..=self.lap_count())
+            .flat_map(|lap| self.standings(lap))
+            .map(|(code, lap)| {
+                vec![
+                    lap.lap_number.to_string(),
+                    lap.position.to_string(),
+                    code.to_string(),
+                    format!("{:.3}", lap.race_time),
+                    format!("{:.3}", lap.gap),
+                    lap.pitted.to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Race time at the end of each lap, from lap 1 until the first lap whose
+/// duration is unknown.
+fn race_times(driver: &DriverSession) -> Vec<(u32, f64)> {
+    let mut times = Vec::new();
+    let mut race_time = 0.0;
+    for (expected, lap) in (1..).zip(&driver.laps) {
+        if lap.number != expected {
+            break;
+        }
+        let span = lap
+            .samples
+            .first()
+            .zip(lap.samples.last())
+            .map(|(first, last)| last.time - first.time)
+            .filter(|d| *d > 0.0);
+        let Some(duration) = lap.lap_time.or(span) else {
+            break;
+        };
+        race_time += duration;
+        times.push((lap.number, race_time));
+    }
+    times
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pit::PitStop;
+    use crate::{Driver, Lap, SessionType};
+    use chrono::Utc;
+
+    fn driver(code: &str, lap_times: &[Option<f64>]) -> DriverSession {
+        DriverSession {
+            driver: Driver {
+                code: code.into(),
+                name: String::new(),
+                number: 1,
+                team: String::new(),
+                team_color: String::new(),
+            },
+            laps: lap_times
+                .iter()
+                .zip(1..)
+                .map(|(&lap_time, number)| Lap {
+                    number,
+                    lap_time,
+                    sector_times: [None; 3],
+                    samples: Vec::new(),
+                    is_valid: true,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_race_history() {
+        // NOR passes VER when VER pits on lap 2; HAM retires after lap 1
+        let session = Session {
+            schema_version: crate::loader::CURRENT_SCHEMA_VERSION,
+            year: 2025,
+            circuit: "Austin".into(),
+            circuit_short: "austin".into(),
+            session_type: SessionType::Race,
+            date: Utc::now(),
+            drivers: vec![
+                driver("VER", &[Some(100.0), Some(121.0), Some(95.0)]),
+                driver("NOR", &[Some(100.5), Some(99.0), Some(96.0)]),
+                driver("HAM", &[Some(102.0), None]),
+            ],
+        };
+        let stops = PitStops {
+            stops: vec![PitStop {
+                driver_code: "VER".into(),
+                lap_number: 2,
+                entry_time: 0.0,
+                exit_time: 20.0,
+                stationary_time: 2.5,
+                pit_loss: None,
+            }],
+        };
+        let history = RaceHistory::new(&session, &stops);
+        assert_eq!(history.lap_count(), 3);
+
+        let lap = |code: &str, n| history.get_driver(code).unwrap().lap(n).unwrap();
+        assert_eq!(lap("VER", 1).position, 1);
+        assert_eq!(lap("HAM", 1).position, 3);
+        assert!((lap("HAM", 1).gap - 2.0).abs() < 1e-9);
+        assert_eq!(history.get_driver("HAM").unwrap().laps.len(), 1);
+
+        assert_eq!(lap("NOR", 2).position, 1);
+        assert!(lap("VER", 2).pitted);
+        assert!((lap("VER", 2).gap - 21.5).abs() < 1e-9);
+
+        let order: Vec<&str> = history.standings(3).iter().map(|s| s.0).collect();
+        assert_eq!(order, ["NOR", "VER"]);
+    }
+}
@@ -0,0 +1,498 @@
+//! Lazy session loading.
+//!
+//! `load_session` decodes every telemetry sample of every driver up front,
+//! which for a full race is most of the file. `LazySession::open` instead
+//! keeps session metadata, drivers and lap summaries in memory and records
+//! where each lap's `samples` array lives in the file. Samples are decoded
+//! from disk only when a lap is requested, so memory stays bounded by what
+//! the caller holds on to.
+//!
+//! Indexing streams the file once through a small buffer: samples are
+//! skipped as they are tokenized, with their byte range taken from a count
+//! of the bytes read so far. `read_info` stops at the `drivers` array, so
+//! listing sessions costs only the header.
+
+use std::cell::Cell;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::de;
+use crate::loader::{self, LoadError};
+use crate::{Driver, Lap, SessionType, TelemetrySample};
+
+/// Session metadata, available without decoding any telemetry.
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub year: u32,
+    pub circuit: String,
+    pub circuit_short: String,
+    pub session_type: SessionType,
+    pub date: DateTime<Utc>,
+}
+
+impl SessionInfo {
+    /// Display label, as `Session::label`.
+    pub fn label(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.year,
+            self.circuit,
+            self.session_type.as_str()
+        )
+    }
+}
+
+/// Read a session file's metadata without reading its drivers.
+pub fn read_info(path: impl AsRef<Path>) -> Result<SessionInfo, LoadError> {
+    let document = read_document(path.as_ref(), false)?;
+    loader::parse_header(document.schema_version, document.header)
+}
+
+/// A lap without its telemetry samples.
+#[derive(Debug, Clone)]
+pub struct LapSummary {
+    pub number: u32,
+    pub lap_time: Option<f64>,
+    pub sector_times: [Option<f64>; 3],
+    pub is_valid: bool,
+    /// Number of telemetry samples in the file
+    pub sample_count: usize,
+    /// Byte range of the lap's `samples` array in the source file
+    samples_offset: u64,
+    samples_len: usize,
+}
+
+/// A driver with lap summaries only.
+#[derive(Debug, Clone)]
+pub struct LazyDriver {
+    pub driver: Driver,
+    pub laps: Vec<LapSummary>,
+}
+
+impl LazyDriver {
+    /// Get a lap summary by its number.
+    pub fn get_lap(&self, number: u32) -> Option<&LapSummary> {
+        self.laps.iter().find(|l| l.number == number)
+    }
+
+    /// Fastest valid, timed lap that has telemetry, as
+    /// `DriverSession::fastest_lap`.
+    pub fn fastest_lap(&self) -> Option<&LapSummary> {
+        self.laps
+            .iter()
+            .filter(|l| l.is_valid && l.sample_count > 0)
+            .filter_map(|l| l.lap_time.map(|t| (l, t)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(lap, _)| lap)
+    }
+}
+
+/// A session whose telemetry is decoded on demand.
+#[derive(Debug, Clone)]
+pub struct LazySession {
+    path: PathBuf,
+    /// Schema version of the file; samples are migrated as they are decoded
+    schema_version: u32,
+    pub info: SessionInfo,
+    pub drivers: Vec<LazyDriver>,
+}
+
+thread_local! {
+    /// Bytes read from the file being indexed on this thread.
+    static POSITION: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Counts bytes into `POSITION`. `serde_json` reads its input a byte at a
+/// time, so the count is exactly how far it has got (checked against a full
+/// load by `test_lazy_laps_match_full_load`).
+struct CountingReader<R>(R);
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.0.read(buf)?;
+        POSITION.with(|p| p.set(p.get() + n as u64));
+        Ok(n)
+    }
+}
+
+/// The top level of a session file, decoded in one pass. Every field other
+/// than `schema_version` and `drivers` goes into `header`.
+#[derive(Default)]
+struct Document {
+    schema_version: u32,
+    header: Map<String, Value>,
+    drivers: Vec<RawDriver>,
+    /// Reading stopped at `drivers` with the header already read
+    stopped: bool,
+}
+
+/// Fills a `Document`, skipping the drivers unless `index_laps`.
+struct DocumentSeed<'a> {
+    document: &'a mut Document,
+    index_laps: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for DocumentSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DocumentSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a session object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "schema_version" => self.document.schema_version = map.next_value()?,
+                "drivers" if self.index_laps => self.document.drivers = map.next_value()?,
+                // Exporters write the header first; stop without reading on
+                "drivers" if self.document.header.contains_key("year") => {
+                    self.document.stopped = true;
+                    return Ok(());
+                }
+                "drivers" => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+                _ => {
+                    let value = map.next_value()?;
+                    self.document.header.insert(key, value);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_document(path: &Path, index_laps: bool) -> Result<Document, LoadError> {
+    if !path.exists() {
+        return Err(LoadError::NotFound(path.display().to_string()));
+    }
+    POSITION.with(|p| p.set(0));
+    let reader = CountingReader(BufReader::new(File::open(path)?));
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let mut document = Document::default();
+    let seed = DocumentSeed {
+        document: &mut document,
+        index_laps,
+    };
+    let result = seed
+        .deserialize(&mut deserializer)
+        .and_then(|()| deserializer.end());
+    match result {
+        // Stopping early leaves the object unterminated
+        Err(_) if document.stopped => {}
+        result => result?,
+    }
+    Ok(document)
+}
+
+#[derive(Deserialize)]
+struct RawDriver {
+    driver: Driver,
+    #[serde(default)]
+    laps: Vec<RawLap>,
+}
+
+#[derive(Deserialize)]
+struct RawLap {
+    number: u32,
+    lap_time: Option<f64>,
+    #[serde(default)]
+    sector_times: [Option<f64>; 3],
+    #[serde(default)]
+    samples: SampleRange,
+    #[serde(default = "de::default_true")]
+    is_valid: bool,
+}
+
+/// Where a lap's `samples` array is in the file, found while skipping it.
+#[derive(Default)]
+struct SampleRange {
+    offset: u64,
+    len: usize,
+    count: usize,
+}
+
+impl<'de> Deserialize<'de> for SampleRange {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Count;
+
+        impl<'de> Visitor<'de> for Count {
+            type Value = usize;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an array of samples")
+            }
+
+            fn visit_unit<E>(self) -> Result<usize, E> {
+                Ok(0)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<usize, A::Error> {
+                let mut count = 0;
+                while seq.next_element::<IgnoredAny>()?.is_some() {
+                    count += 1;
+                }
+                Ok(count)
+            }
+        }
+
+        // From just after the `:` to just after the closing `]`
+        let offset = POSITION.with(Cell::get);
+        let count = deserializer.deserialize_any(Count)?;
+        let len = (POSITION.with(Cell::get) - offset) as usize;
+        Ok(Self { offset, len, count })
+    }
+}
+
+impl LazySession {
+    /// Index a session file: read metadata, drivers and lap summaries.
+    ///
+    /// The file is streamed once; telemetry samples are skipped, not decoded
+    /// or kept.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let document = read_document(path, true)?;
+        let info = loader::parse_header(document.schema_version, document.header)?;
+        let drivers = document
+            .drivers
+            .into_iter()
+            .map(|d| LazyDriver {
+                driver: d.driver,
+                laps: d
+                    .laps
+                    .into_iter()
+                    .map(|l| LapSummary {
+                        number: l.number,
+                        lap_time: l.lap_time,
+                        sector_times: l.sector_times,
+                        is_valid: l.is_valid,
+                        sample_count: l.samples.count,
+                        samples_offset: l.samples.offset,
+                        samples_len: l.samples.len,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            schema_version: document.schema_version,
+            info,
+            drivers,
+        })
+    }
+
+    /// Get a driver by their code.
+    pub fn get_driver(&self, code: &str) -> Option<&LazyDriver> {
+        self.drivers.iter().find(|d| d.driver.code == code)
+    }
+
+    /// Get all driver codes in this session.
+    pub fn driver_codes(&self) -> Vec<&str> {
+        self.drivers
+            .iter()
+            .map(|d| d.driver.code.as_str())
+            .collect()
+    }
+
+    /// Each driver's fastest lap, sorted fastest first, as
+    /// `Session::fastest_laps`.
+    pub fn fastest_laps(&self) -> Vec<(&Driver, &LapSummary)> {
+        let mut laps: Vec<_> = self
+            .drivers
+            .iter()
+            .filter_map(|d| Some((&d.driver, d.fastest_lap()?)))
+            .collect();
+        laps.sort_by(|a, b| {
+            a.1.lap_time
+                .unwrap_or(f64::MAX)
+                .total_cmp(&b.1.lap_time.unwrap_or(f64::MAX))
+        });
+        laps
+    }
+
+    /// Decode the telemetry samples of a lap from disk, migrating them to the
+    /// current schema.
+    pub fn load_samples(&self, lap: &LapSummary) -> Result<Vec<TelemetrySample>, LoadError> {
+        if lap.sample_count == 0 {
+            return Ok(Vec::new());
+        }
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(lap.samples_offset))?;
+        let mut buffer = vec![0; lap.samples_len];
+        file.read_exact(&mut buffer)?;
+        loader::parse_samples(self.schema_version, &buffer)
+    }
+
+    /// Load a complete lap (summary plus samples) for a driver.
+    ///
+    /// Returns `Ok(None)` if the driver or lap doesn't exist.
+    pub fn load_lap(&self, driver_code: &str, lap_number: u32) -> Result<Option<Lap>, LoadError> {
+        let Some(summary) = self
+            .get_driver(driver_code)
+            .and_then(|d| d.get_lap(lap_number))
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(Lap {
+            number: summary.number,
+            lap_time: summary.lap_time,
+            sector_times: summary.sector_times,
+            samples: self.load_samples(summary).map_err(|e| match e {
+                LoadError::Json(source) => LoadError::Malformed {
+                    driver: driver_code.to_string(),
+                    lap: Some(lap_number),
+                    source,
+                },
+                e => e,
+            })?,
+            is_valid: summary.is_valid,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION_JSON: &str = r##"{
+        "year": 2025,
+        "circuit": "United States Grand Prix",
+        "circuit_short": "Austin",
+        "session_type": "Race",
+        "date": "2025-10-19T19:00:00Z",
+        "drivers": [{
+            "driver": {"code": "NOR", "name": "Lando Norris", "number": 4, "team": "McLaren", "team_color": "#FF8000"},
+            "laps": [
+                {"number": 1, "lap_time": null, "sector_times": [null, 33.0, 32.1],
+                 "samples": [{"position": [1.0, 0.0, 2.0], "time": 10.0, "throttle": 100, "brake": 0, "gear": 7, "speed": 290, "drs": "Off"}],
+                 "is_valid": false},
+                {"number": 2, "lap_time": 98.7, "sector_times": [32.0, 33.5, 33.2],
+                 "samples": [
+                    {"position": [3.0, 0.0, 4.0], "time": 110.0, "throttle": 0, "brake": 100, "gear": 3, "speed": 120, "drs": "Off"},
+                    {"position": [5.0, 0.0, 6.0], "time": 110.2, "throttle": 40, "brake": 0, "gear": 3, "speed": 118, "drs": "Off"}
+                 ],
+                 "is_valid": true},
+                {"number": 3, "lap_time": 97.9, "is_valid": true}
+            ]
+        }]
+    }"##;
+
+    #[test]
+    fn test_lazy_session_loads_laps_on_demand() {
+        let dir = std::env::temp_dir().join(format!("f1-data-lazy-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("2025_austin_r.json");
+        std::fs::write(&path, SESSION_JSON).unwrap();
+
+        let session = LazySession::open(&path).unwrap();
+        assert_eq!(session.info.circuit_short, "Austin");
+        assert_eq!(session.driver_codes(), vec!["NOR"]);
+        assert_eq!(session.drivers[0].laps[1].lap_time, Some(98.7));
+
+        assert_eq!(session.drivers[0].laps[1].sample_count, 2);
+        // Lap 3 is quicker but has no telemetry, as for a full session
+        assert_eq!(session.fastest_laps()[0].1.number, 2);
+
+        let lap = session.load_lap("NOR", 2).unwrap().unwrap();
+        assert_eq!(lap.samples.len(), 2);
+        assert_eq!(lap.samples[1].speed, 118);
+        assert!(session
+            .load_lap("NOR", 3)
+            .unwrap()
+            .unwrap()
+            .samples
+            .is_empty());
+        assert!(session.load_lap("NOR", 4).unwrap().is_none());
+
+        let info = read_info(&path).unwrap();
+        assert_eq!(info.label(), "2025 United States Grand Prix Race");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lazy_laps_match_full_load() {
+        // Several drivers with laps far larger than the read buffer, written
+        // pretty-printed so every `samples` array starts after whitespace
+        let sample = |lap: u32, i: u32| {
+            serde_json::json!({
+                "position": [i as f32 * 1.5, 0.25, lap as f32 - i as f32 / 3.0],
+                "time": lap as f64 * 90.0 + i as f64 * 0.27,
+                "throttle": (i * 7) % 101,
+                "brake": if i.is_multiple_of(9) { 100 } else { 0 },
+                "gear": 1 + i % 8,
+                "speed": 80 + (i * 13 + lap) % 250,
+                "drs": "Off"
+            })
+        };
+        let drivers: Vec<Value> = ["NOR", "VER", "LEC"]
+            .iter()
+            .enumerate()
+            .map(|(d, code)| {
+                let laps: Vec<Value> = (1..=4)
+                    .map(|lap| {
+                        let count = if lap == 3 {
+                            0
+                        } else {
+                            150 + 40 * d as u32 + lap
+                        };
+                        serde_json::json!({
+                            "number": lap,
+                            "lap_time": 95.0 + lap as f64 / 10.0,
+                            "samples": (0..count).map(|i| sample(lap, i)).collect::<Vec<_>>(),
+                            "is_valid": lap != 1
+                        })
+                    })
+                    .collect();
+                serde_json::json!({
+                    "driver": {"code": code, "name": format!("Driver \"{code}\""), "number": d,
+                               "team": "Team", "team_color": "#FFFFFF"},
+                    "laps": laps
+                })
+            })
+            .collect();
+        let mut document: Value = serde_json::from_str(SESSION_JSON).unwrap();
+        document["drivers"] = Value::Array(drivers);
+
+        let dir = std::env::temp_dir().join(format!("f1-data-lazy-full-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("2025_austin_r.json");
+        std::fs::write(&path, serde_json::to_string_pretty(&document).unwrap()).unwrap();
+
+        let full = loader::load_session(&path).unwrap();
+        let lazy = LazySession::open(&path).unwrap();
+        assert_eq!(lazy.drivers.len(), full.drivers.len());
+        for driver in &full.drivers {
+            let code = driver.driver.code.as_str();
+            assert_eq!(lazy.get_driver(code).unwrap().laps.len(), driver.laps.len());
+            for lap in &driver.laps {
+                let loaded = lazy.load_lap(code, lap.number).unwrap().unwrap();
+                assert_eq!(
+                    serde_json::to_value(&loaded).unwrap(),
+                    serde_json::to_value(lap).unwrap(),
+                    "{code} lap {}",
+                    lap.number
+                );
+            }
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -4,9 +4,37 @@
 //! and analysis tools for turn segmentation and lap comparison.
 
 pub mod analysis;
+pub mod cache;
+pub mod channels;
+pub mod classify;
+pub mod clipping;
+pub mod collection;
+mod de;
+pub mod drs;
+pub mod export;
+pub mod format;
+pub mod gear;
+pub mod history;
+pub mod lazy;
 pub mod loader;
+pub mod overtake;
+pub mod pit;
+pub mod registration;
+pub mod render;
+pub mod report;
+pub mod smooth;
+pub mod stint;
+pub mod straight;
+pub mod trace;
+pub mod trap;
 pub mod turn;
 pub mod types;
+pub mod validate;
 
-pub use turn::{BoundingBox, CircuitTurns, Turn, TurnSegment};
+pub use channels::{Channel, LapChannels};
+pub use classify::{LapClass, LapClasses};
+pub use collection::{SessionCollection, SessionLapRef};
+pub use registration::{Registration, RigidTransform};
+pub use trace::{session_sample_at, DeltaTrace, LapTrace};
+pub use turn::{BoundingBox, CircuitTurns, SpeedTrap, Turn, TurnSegment};
 pub use types::*;
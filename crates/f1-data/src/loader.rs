@@ -1,7 +1,19 @@
 //! Session data loading from exported files.
 
-use crate::Session;
+mod migrate;
+
+pub use migrate::CURRENT_SCHEMA_VERSION;
+
+use crate::de::SessionDate;
+use crate::lazy::SessionInfo;
+use crate::{cache, de, DriverSession, Lap, Session, SessionType, TelemetrySample};
+use chrono::FixedOffset;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::{Map, Value};
+use std::io::Read;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -12,23 +24,314 @@ pub enum LoadError {
     Json(#[from] serde_json::Error),
     #[error("Session file not found: {0}")]
     NotFound(String),
+    #[error("Failed to read/write session cache: {0}")]
+    Cache(#[from] bincode::Error),
+    #[error("Session loading was cancelled")]
+    Cancelled,
+    #[error("Malformed data for driver {driver}{}: {source}", lap.map(|l| format!(", lap {l}")).unwrap_or_default())]
+    Malformed {
+        /// Driver code, or `#<index>` if the code itself is unreadable
+        driver: String,
+        /// Lap number (or 1-based position in the laps array), if the error is within a lap
+        lap: Option<u32>,
+        source: serde_json::Error,
+    },
+    #[error("Session schema version {found} is newer than this build supports (up to {supported}); update f1-purple-line")]
+    UnsupportedSchemaVersion { found: u32, supported: u32 },
+}
+
+/// Progress of a session load, shared with the thread doing the loading.
+///
+/// All counters are atomics so a UI thread can poll them while another
+/// thread loads, and request cancellation with [`LoadProgress::cancel`].
+#[derive(Debug, Default)]
+pub struct LoadProgress {
+    bytes_read: AtomicU64,
+    total_bytes: AtomicU64,
+    drivers_decoded: AtomicUsize,
+    total_drivers: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl LoadProgress {
+    /// Bytes of the source file read so far, and the file size.
+    pub fn bytes(&self) -> (u64, u64) {
+        (
+            self.bytes_read.load(Ordering::Relaxed),
+            self.total_bytes.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Drivers decoded so far, and the number of drivers in the session
+    /// (0 until the file has been read).
+    pub fn drivers(&self) -> (usize, usize) {
+        (
+            self.drivers_decoded.load(Ordering::Relaxed),
+            self.total_drivers.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Overall progress from 0.0 to 1.0 (reading and decoding weighted equally).
+    pub fn fraction(&self) -> f32 {
+        let ratio = |done: f64, total: f64| {
+            if total > 0.0 {
+                (done / total).min(1.0)
+            } else {
+                0.0
+            }
+        };
+        let (read, total_bytes) = self.bytes();
+        let (decoded, total_drivers) = self.drivers();
+        (0.5 * ratio(read as f64, total_bytes as f64)
+            + 0.5 * ratio(decoded as f64, total_drivers as f64)) as f32
+    }
+
+    /// Ask the loader to stop at the next checkpoint.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn check_cancelled(&self) -> Result<(), LoadError> {
+        if self.is_cancelled() {
+            Err(LoadError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Session document with each driver left as raw JSON so drivers can be
+/// decoded (and migrated) one at a time.
+#[derive(Deserialize)]
+struct RawSession<'a> {
+    /// Missing in documents written before versioning (version 0)
+    #[serde(default)]
+    schema_version: u32,
+    #[serde(borrow, default)]
+    drivers: Vec<&'a RawValue>,
+    /// Every other top-level field, decoded as [`RawHeader`] after migration
+    #[serde(flatten)]
+    header: Map<String, Value>,
+}
+
+/// Top-level session fields in the current schema.
+#[derive(Deserialize)]
+struct RawHeader {
+    year: u32,
+    circuit: String,
+    #[serde(default)]
+    circuit_short: String,
+    session_type: SessionType,
+    date: SessionDate,
+    /// Circuit offset from UTC, used to interpret naive dates
+    #[serde(default, deserialize_with = "de::utc_offset")]
+    utc_offset: Option<FixedOffset>,
 }
 
+/// Just enough of a driver to locate a decoding error.
+#[derive(Deserialize)]
+struct DriverLocator<'a> {
+    #[serde(default)]
+    driver: Option<DriverCode>,
+    #[serde(borrow, default)]
+    laps: Vec<&'a RawValue>,
+}
+
+#[derive(Deserialize)]
+struct DriverCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct LapNumber {
+    number: u32,
+}
+
+/// Size of each read from the session file; progress is updated per chunk.
+const READ_CHUNK_SIZE: usize = 256 * 1024;
+
 /// Load a session from a JSON file.
 pub fn load_session(path: impl AsRef<Path>) -> Result<Session, LoadError> {
     let path = path.as_ref();
     if !path.exists() {
         return Err(LoadError::NotFound(path.display().to_string()));
     }
-    let contents = std::fs::read_to_string(path)?;
-    let session: Session = serde_json::from_str(&contents)?;
+    let contents = std::fs::read(path)?;
+    parse_session(&contents, &LoadProgress::default())
+}
+
+/// Load a session, using the binary cache next to the JSON when it is fresh.
+///
+/// On a cache miss the JSON is parsed and a new cache is written. Failing to
+/// write the cache is not an error; the session is still returned.
+pub fn load_session_cached(path: impl AsRef<Path>) -> Result<Session, LoadError> {
+    load_session_with_progress(path, &LoadProgress::default())
+}
+
+/// Load a session through the cache, reporting progress and honoring
+/// cancellation via `progress`.
+///
+/// Intended to run off the main thread while a UI polls `progress`.
+pub fn load_session_with_progress(
+    path: impl AsRef<Path>,
+    progress: &LoadProgress,
+) -> Result<Session, LoadError> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(LoadError::NotFound(path.display().to_string()));
+    }
+    // A corrupt cache falls back to the JSON and gets rewritten
+    if let Ok(Some(session)) = cache::read_cache(path) {
+        let size = std::fs::metadata(path)?.len();
+        progress.total_bytes.store(size, Ordering::Relaxed);
+        progress.bytes_read.store(size, Ordering::Relaxed);
+        progress
+            .total_drivers
+            .store(session.drivers.len(), Ordering::Relaxed);
+        progress
+            .drivers_decoded
+            .store(session.drivers.len(), Ordering::Relaxed);
+        return Ok(session);
+    }
+
+    let mut file = std::fs::File::open(path)?;
+    let total_bytes = file.metadata()?.len();
+    progress.total_bytes.store(total_bytes, Ordering::Relaxed);
+
+    let mut contents = Vec::with_capacity(total_bytes as usize);
+    let mut chunk = vec![0; READ_CHUNK_SIZE];
+    loop {
+        progress.check_cancelled()?;
+        let n = file.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        contents.extend_from_slice(&chunk[..n]);
+        progress.bytes_read.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    let session = parse_session(&contents, progress)?;
+    let _ = cache::write_cache(path, &session);
     Ok(session)
 }
 
 /// Load a session from JSON string.
 pub fn load_session_from_str(json: &str) -> Result<Session, LoadError> {
-    let session: Session = serde_json::from_str(json)?;
-    Ok(session)
+    parse_session(json.as_bytes(), &LoadProgress::default())
+}
+
+/// Reject documents written by a newer exporter than this build understands.
+fn check_schema_version(version: u32) -> Result<(), LoadError> {
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(LoadError::UnsupportedSchemaVersion {
+            found: version,
+            supported: CURRENT_SCHEMA_VERSION,
+        });
+    }
+    Ok(())
+}
+
+/// Migrate and decode the top-level session fields of a `schema_version` document.
+pub(crate) fn parse_header(
+    schema_version: u32,
+    mut header: Map<String, Value>,
+) -> Result<SessionInfo, LoadError> {
+    check_schema_version(schema_version)?;
+    migrate::migrate_header(&mut header, schema_version);
+    let raw: RawHeader = serde_json::from_value(Value::Object(header))?;
+    Ok(SessionInfo {
+        year: raw.year,
+        circuit: raw.circuit,
+        circuit_short: raw.circuit_short,
+        session_type: raw.session_type,
+        date: raw.date.resolve(raw.utc_offset),
+    })
+}
+
+/// Decode a lap's `samples` array written at `schema_version`.
+pub(crate) fn parse_samples(
+    schema_version: u32,
+    json: &[u8],
+) -> Result<Vec<TelemetrySample>, LoadError> {
+    if schema_version == CURRENT_SCHEMA_VERSION {
+        return Ok(serde_json::from_slice(json)?);
+    }
+    let mut samples: Value = serde_json::from_slice(json)?;
+    migrate::migrate_samples(&mut samples, schema_version);
+    Ok(serde_json::from_value(samples)?)
+}
+
+/// Decode one driver, migrating it first if the document is outdated.
+fn parse_driver(schema_version: u32, raw_driver: &RawValue) -> serde_json::Result<DriverSession> {
+    if schema_version == CURRENT_SCHEMA_VERSION {
+        return serde_json::from_str(raw_driver.get());
+    }
+    let mut driver: Value = serde_json::from_str(raw_driver.get())?;
+    migrate::migrate_driver(&mut driver, schema_version);
+    serde_json::from_value(driver)
+}
+
+/// Parse a session document, decoding one driver at a time.
+fn parse_session(contents: &[u8], progress: &LoadProgress) -> Result<Session, LoadError> {
+    let raw: RawSession = serde_json::from_slice(contents)?;
+    let info = parse_header(raw.schema_version, raw.header)?;
+    progress
+        .total_drivers
+        .store(raw.drivers.len(), Ordering::Relaxed);
+
+    let mut drivers = Vec::with_capacity(raw.drivers.len());
+    for (index, raw_driver) in raw.drivers.iter().enumerate() {
+        progress.check_cancelled()?;
+        let driver = parse_driver(raw.schema_version, raw_driver)
+            .map_err(|e| locate_error(raw_driver, index, e))?;
+        drivers.push(driver);
+        progress.drivers_decoded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    Ok(Session {
+        schema_version: CURRENT_SCHEMA_VERSION,
+        year: info.year,
+        circuit: info.circuit,
+        circuit_short: info.circuit_short,
+        session_type: info.session_type,
+        date: info.date,
+        drivers,
+    })
+}
+
+/// Turn a driver decoding error into one naming the driver and lap at fault.
+fn locate_error(raw_driver: &RawValue, index: usize, error: serde_json::Error) -> LoadError {
+    let Ok(locator) = serde_json::from_str::<DriverLocator>(raw_driver.get()) else {
+        return LoadError::Malformed {
+            driver: format!("#{index}"),
+            lap: None,
+            source: error,
+        };
+    };
+    let driver = locator
+        .driver
+        .map_or_else(|| format!("#{index}"), |d| d.code);
+
+    for (lap_index, raw_lap) in locator.laps.iter().enumerate() {
+        if let Err(lap_error) = serde_json::from_str::<Lap>(raw_lap.get()) {
+            let lap = serde_json::from_str::<LapNumber>(raw_lap.get())
+                .map_or(lap_index as u32 + 1, |l| l.number);
+            return LoadError::Malformed {
+                driver,
+                lap: Some(lap),
+                source: lap_error,
+            };
+        }
+    }
+    LoadError::Malformed {
+        driver,
+        lap: None,
+        source: error,
+    }
 }
 
 #[cfg(test)]
@@ -40,4 +343,89 @@ mod tests {
         let result = load_session("/nonexistent/path.json");
         assert!(matches!(result, Err(LoadError::NotFound(_))));
     }
+
+    #[test]
+    fn test_cancelled_load() {
+        let path = std::env::temp_dir().join(format!("f1-data-cancel-{}.json", std::process::id()));
+        std::fs::write(&path, "{}").unwrap();
+
+        let progress = LoadProgress::default();
+        progress.cancel();
+        let result = load_session_with_progress(&path, &progress);
+        assert!(matches!(result, Err(LoadError::Cancelled)));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Session header as the exporter writes it: naive date, no timezone.
+    const HEADER: &str = r#""year": 2025, "circuit": "United States Grand Prix", "circuit_short": "Austin",
+        "session_type": "Qualifying", "date": "2025-10-18T21:00:00""#;
+
+    #[test]
+    fn test_tolerant_session() {
+        let json = format!(
+            r#"{{{HEADER}, "drivers": [{{
+                "driver": {{"code": "VER"}},
+                "laps": [{{"number": 7, "lap_time": 92.5,
+                    "samples": [{{"position": {{"x": 1.0, "y": 0.5, "z": 2.0}}, "time": 10.0, "speed": 290}}]}}]
+            }}]}}"#
+        );
+        let session = load_session_from_str(&json).unwrap();
+        assert_eq!(session.date.to_rfc3339(), "2025-10-18T21:00:00+00:00");
+        let lap = &session.drivers[0].laps[0];
+        assert!(lap.is_valid);
+        assert_eq!(lap.sector_times, [None; 3]);
+        assert_eq!(lap.samples[0].position.z, 2.0);
+        assert_eq!(lap.samples[0].gear, 0);
+    }
+
+    #[test]
+    fn test_circuit_utc_offset() {
+        let json = r#"{"year": 2025, "circuit": "United States Grand Prix", "session_type": "Race",
+            "date": "2025-10-19T14:00:00", "utc_offset": "-05:00:00"}"#;
+        let session = load_session_from_str(json).unwrap();
+        assert_eq!(session.date.to_rfc3339(), "2025-10-19T19:00:00+00:00");
+    }
+
+    #[test]
+    fn test_malformed_error_names_driver_and_lap() {
+        let json = format!(
+            r#"{{{HEADER}, "drivers": [{{
+                "driver": {{"code": "NOR"}},
+                "laps": [{{"number": 1, "samples": []}}, {{"number": 2, "samples": [{{"position": [1.0, 2.0], "time": 1.0}}]}}]
+            }}]}}"#
+        );
+        match load_session_from_str(&json) {
+            Err(LoadError::Malformed { driver, lap, .. }) => {
+                assert_eq!(driver, "NOR");
+                assert_eq!(lap, Some(2));
+            }
+            other => panic!("expected Malformed error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_unversioned_document_is_migrated() {
+        let json = format!(
+            r#"{{{HEADER}, "drivers": [{{
+                "driver": {{"code": "VER"}},
+                "laps": [{{"number": 1, "samples": [{{"position": [0.0, 0.0, 0.0], "time": 1.0, "brake": 1}}]}}]
+            }}]}}"#
+        );
+        let session = load_session_from_str(&json).unwrap();
+        assert_eq!(session.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(session.drivers[0].laps[0].samples[0].brake, 100);
+    }
+
+    #[test]
+    fn test_future_schema_version_rejected() {
+        let json = format!(r#"{{"schema_version": 99, {HEADER}}}"#);
+        assert!(matches!(
+            load_session_from_str(&json),
+            Err(LoadError::UnsupportedSchemaVersion {
+                found: 99,
+                supported: CURRENT_SCHEMA_VERSION
+            })
+        ));
+    }
 }
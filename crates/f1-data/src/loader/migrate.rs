@@ -0,0 +1,83 @@
+//! Schema migrations for session documents.
+//!
+//! Documents written before `schema_version` existed are version 0. Each
+//! migration upgrades a document by one version, operating on raw JSON so
+//! the current `Session` types never need to know about old layouts.
+//!
+//! Migrations are split into header-level (top-level fields other than
+//! `drivers`) and sample-level steps, so the lazy loader, which never
+//! materializes whole drivers, can apply them as well.
+
+use serde_json::{Map, Value};
+
+/// Schema version written by the current exporter.
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// Upgrade the top-level session fields from version `from` to current.
+pub(crate) fn migrate_header(_header: &mut Map<String, Value>, from: u32) {
+    // No header changes so far; v0 -> v1 only touched samples.
+    debug_assert!(from <= CURRENT_SCHEMA_VERSION);
+}
+
+/// Upgrade one driver object (`{"driver": .., "laps": [..]}`) from version `from` to current.
+pub(crate) fn migrate_driver(driver: &mut Value, from: u32) {
+    let Some(laps) = driver.get_mut("laps").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for lap in laps {
+        if let Some(samples) = lap.get_mut("samples") {
+            migrate_samples(samples, from);
+        }
+    }
+}
+
+/// Upgrade a lap's `samples` array from version `from` to current.
+pub(crate) fn migrate_samples(samples: &mut Value, from: u32) {
+    let Some(samples) = samples.as_array_mut() else {
+        return;
+    };
+    if from < 1 {
+        samples.iter_mut().for_each(v0_to_v1_sample);
+    }
+}
+
+/// v0 exporters wrote `brake` as FastF1's boolean cast to an integer (0/1);
+/// v1 uses 0/100 like the other pedal channels.
+fn v0_to_v1_sample(sample: &mut Value) {
+    if let Some(brake) = sample.get_mut("brake") {
+        if brake.as_u64() == Some(1) || brake.as_bool() == Some(true) {
+            *brake = Value::from(100);
+        } else if brake.as_bool() == Some(false) {
+            *brake = Value::from(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_v0_brake_scaled() {
+        let mut driver = json!({
+            "driver": {"code": "LEC"},
+            "laps": [{"number": 1, "samples": [{"brake": 1}, {"brake": 0}, {"brake": true}]}]
+        });
+        migrate_driver(&mut driver, 0);
+        let brakes: Vec<u64> = driver["laps"][0]["samples"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| s["brake"].as_u64().unwrap())
+            .collect();
+        assert_eq!(brakes, vec![100, 0, 100]);
+    }
+
+    #[test]
+    fn test_current_version_untouched() {
+        let mut samples = json!([{"brake": 1}]);
+        migrate_samples(&mut samples, CURRENT_SCHEMA_VERSION);
+        assert_eq!(samples[0]["brake"], 1);
+    }
+}
@@ -0,0 +1,611 @@
+//! Overtakes and battles.
+//!
+//! Each driver's race progress (laps completed plus the fraction of the
+//! current lap driven, from `LapTrace` distances) is sampled on a common
+//! session-time grid. Two cars on the same lap swap places where the
+//! difference in progress changes sign; the new order has to hold for
+//! `OvertakeConfig::min_hold` so side-by-side moments don't count twice.
+//! Swaps while either car is in the pit lane (`PitStops`, plus a margin)
+//! are pit-stop swaps, not overtakes.
+//!
+//! A pass is placed at the passing car's position. With turn definitions it
+//! is attributed to the turn whose box contains that position, or else to
+//! the next turn the car reaches within `turn_lookahead` (a pass completed on
+//! the straight into a corner counts for that corner).
+//!
+//! Battles come from the lap-by-lap `RaceHistory`: two cars running
+//! next to each other in the order within `battle_gap` at the line for at
+//! least `battle_laps` consecutive laps.
+
+use std::collections::HashMap;
+
+use glam::Vec3;
+use serde::Serialize;
+
+use crate::export::Exportable;
+use crate::history::RaceHistory;
+use crate::pit::PitStops;
+use crate::{CircuitTurns, LapTrace, Session};
+
+/// Overtake and battle thresholds.
+#[derive(Debug, Clone, Copy)]
+pub struct OvertakeConfig {
+    /// Spacing of the progress grid (s)
+    pub step: f64,
+    /// How long a new order must hold to count as a pass (s)
+    pub min_hold: f64,
+    /// Time before pit entry and after pit exit in which swaps are ignored
+    /// (s)
+    pub pit_margin: f64,
+    /// How far past a pass to look for the turn it was made into (m)
+    pub turn_lookahead: f32,
+    /// Largest gap at the line between cars in a battle (s)
+    pub battle_gap: f64,
+    /// Fewest consecutive laps within `battle_gap` for a battle
+    pub battle_laps: usize,
+}
+
+impl Default for OvertakeConfig {
+    fn default() -> Self {
+        Self {
+            step: 0.5,
+            min_hold: 3.0,
+            pit_margin: 5.0,
+            turn_lookahead: 300.0,
+            battle_gap: 1.0,
+            battle_laps: 3,
+        }
+    }
+}
+
+/// One car passing another on track.
+#[derive(Debug, Clone, Serialize)]
+pub struct Overtake {
+    /// The passing driver
+    pub driver_code: String,
+    /// The driver passed
+    pub passed_code: String,
+    /// Session time the cars swapped
+    pub session_time: f64,
+    /// Passing driver's lap
+    pub lap_number: u32,
+    /// Passing car's position
+    pub position: Vec3,
+    /// Turn the pass was made in or into
+    pub turn: Option<String>,
+}
+
+/// Every overtake in a session.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Overtakes {
+    /// In session-time order
+    pub overtakes: Vec<Overtake>,
+}
+
+impl Overtakes {
+    /// Passes made or suffered by a driver.
+    pub fn for_driver<'a>(&'a self, driver_code: &'a str) -> impl Iterator<Item = &'a Overtake> {
+        self.overtakes
+            .iter()
+            .filter(move |o| o.driver_code == driver_code || o.passed_code == driver_code)
+    }
+
+    /// Number of passes made in each turn (`None` for passes away from any
+    /// turn), most first.
+    pub fn by_turn(&self) -> Vec<(Option<&str>, usize)> {
+        let mut counts: Vec<(Option<&str>, usize)> = Vec::new();
+        for overtake in &self.overtakes {
+            let turn = overtake.turn.as_deref();
+            match counts.iter_mut().find(|(t, _)| *t == turn) {
+                Some(entry) => entry.1 += 1,
+                None => counts.push((turn, 1)),
+            }
+        }
+        counts.sort_by_key(|c| std::cmp::Reverse(c.1));
+        counts
+    }
+}
+
+impl Exportable for Overtakes {
+    fn title(&self) -> String {
+        "Overtakes".into()
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        vec![
+            "Session time (s)",
+            "Lap",
+            "Driver",
+            "Passed",
+            "Turn",
+            "X",
+            "Z",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.overtakes
+            .iter()
+            .map(|o| {
+                vec![
+                    format!("{:.1}", o.session_time),
+                    o.lap_number.to_string(),
+                    o.driver_code.clone(),
+                    o.passed_code.clone(),
+                    o.turn.clone().unwrap_or_default(),
+                    format!("{:.1}", o.position.x),
+                    format!("{:.1}", o.position.z),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// A driver's race progress on the common time grid.
+struct Timeline {
+    traces: Vec<LapTrace>,
+    /// Laps completed plus the fraction of the current lap, per grid step
+    progress: Vec<Option<f64>>,
+    /// Session time spans around pit visits
+    pit_windows: Vec<(f64, f64)>,
+}
+
+impl Timeline {
+    fn in_pit(&self, time: f64) -> bool {
+        self.pit_windows
+            .iter()
+            .any(|(from, to)| (*from..=*to).contains(&time))
+    }
+
+    fn trace_at(&self, time: f64) -> Option<&LapTrace> {
+        self.traces
+            .iter()
+            .find(|t| (t.start_time()..=t.start_time() + t.duration()).contains(&time))
+    }
+}
+
+/// Find every on-track pass.
+pub fn detect_overtakes(
+    session: &Session,
+    stops: &PitStops,
+    turns: Option<&CircuitTurns>,
+    config: &OvertakeConfig,
+) -> Overtakes {
+    let Some((start, end)) = session.time_range() else {
+        return Overtakes::default();
+    };
+    let steps = ((end - start) / config.step).floor() as usize + 1;
+    let time_at = |i: usize| start + i as f64 * config.step;
+
+    let timelines: Vec<Timeline> = session
+        .drivers
+        .iter()
+        .map(|driver| {
+            let traces: Vec<LapTrace> = driver.laps.iter().filter_map(LapTrace::new).collect();
+            let mut progress = vec![None; steps];
+            for trace in &traces {
+                let length = trace.length();
+                if length <= 0.0 {
+                    continue;
+                }
+                let first = ((trace.start_time() - start) / config.step).ceil().max(0.0) as usize;
+                let last = ((trace.start_time() + trace.duration() - start) / config.step).floor()
+                    as usize;
+                for (i, slot) in progress
+                    .iter_mut()
+                    .enumerate()
+                    .take(last.min(steps - 1) + 1)
+                    .skip(first)
+                {
+                    let lap_time = time_at(i) - trace.start_time();
+                    let fraction = (trace.distance_at(lap_time) / length) as f64;
+                    *slot = Some((trace.lap_number - 1) as f64 + fraction);
+                }
+            }
+            let pit_windows = stops
+                .for_driver(&driver.driver.code)
+                .map(|s| {
+                    (
+                        s.entry_time - config.pit_margin,
+                        s.exit_time + config.pit_margin,
+                    )
+                })
+                .collect();
+            Timeline {
+                traces,
+                progress,
+                pit_windows,
+            }
+        })
+        .collect();
+
+    let mut overtakes = Vec::new();
+    for a in 0..timelines.len() {
+        for b in a + 1..timelines.len() {
+            // Established order (a ahead = true) and a pending change with
+            // the grid step it started at
+            let mut order: Option<bool> = None;
+            let mut pending: Option<(bool, usize)> = None;
+            for i in 0..steps {
+                let (Some(pa), Some(pb)) = (timelines[a].progress[i], timelines[b].progress[i])
+                else {
+                    pending = None;
+                    continue;
+                };
+                let difference = pa - pb;
+                // Only cars on the same lap race each other for position
+                if difference.abs() >= 0.5 || difference == 0.0 {
+                    continue;
+                }
+                let a_ahead = difference > 0.0;
+                let Some(current) = order else {
+                    order = Some(a_ahead);
+                    continue;
+                };
+                if a_ahead == current {
+                    pending = None;
+                    continue;
+                }
+                let since = match pending {
+                    Some((ahead, since)) if ahead == a_ahead => since,
+                    _ => {
+                        pending = Some((a_ahead, i));
+                        i
+                    }
+                };
+                if time_at(i) - time_at(since) < config.min_hold {
+                    continue;
+                }
+                order = Some(a_ahead);
+                pending = None;
+
+                let time = time_at(since);
+                if timelines[a].in_pit(time) || timelines[b].in_pit(time) {
+                    continue;
+                }
+                let (passer, passed) = if a_ahead { (a, b) } else { (b, a) };
+                let Some(trace) = timelines[passer].trace_at(time) else {
+                    continue;
+                };
+                let lap_time = time - trace.start_time();
+                overtakes.push(Overtake {
+                    driver_code: session.drivers[passer].driver.code.clone(),
+                    passed_code: session.drivers[passed].driver.code.clone(),
+                    session_time: time,
+                    lap_number: trace.lap_number,
+                    position: trace.sample_at(lap_time).position,
+                    turn: turns
+                        .and_then(|turns| turn_at(trace, lap_time, turns, config.turn_lookahead)),
+                });
+            }
+        }
+    }
+    overtakes.sort_by(|a, b| a.session_time.total_cmp(&b.session_time));
+    Overtakes { overtakes }
+}
+
+/// The turn containing the car at `lap_time`, or the next one it reaches
+/// within `lookahead` meters.
+fn turn_at(
+    trace: &LapTrace,
+    lap_time: f64,
+    turns: &CircuitTurns,
+    lookahead: f32,
+) -> Option<String> {
+    const STEP: f32 = 10.0;
+    let distance = trace.distance_at(lap_time);
+    let steps = (lookahead / STEP) as usize;
+    (0..=steps)
+        .map(|k| distance + k as f32 * STEP)
+        .take_while(|&d| d <= trace.length())
+        .find_map(|d| {
+            let position = trace.sample_at(trace.time_at_distance(d)).position;
+            turns.turns.iter().find(|t| t.bounds.contains(position))
+        })
+        .map(|turn| turn.name.clone())
+}
+
+/// Two cars running together over several laps.
+#[derive(Debug, Clone, Serialize)]
+pub struct Battle {
+    /// Car ahead when the battle started
+    pub driver_code: String,
+    /// Car behind when the battle started
+    pub rival_code: String,
+    pub start_lap: u32,
+    pub end_lap: u32,
+    /// Closest gap at the line (s)
+    pub min_gap: f64,
+    /// Passes between the two during the battle
+    pub overtakes: usize,
+}
+
+impl Battle {
+    pub fn laps(&self) -> u32 {
+        self.end_lap + 1 - self.start_lap
+    }
+}
+
+/// Every battle in a race.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Battles {
+    /// In order of the lap they started
+    pub battles: Vec<Battle>,
+}
+
+impl Exportable for Battles {
+    fn title(&self) -> String {
+        "Battles".into()
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        vec![
+            "Driver",
+            "Rival",
+            "Start lap",
+            "End lap",
+            "Laps",
+            "Min gap (s)",
+            "Overtakes",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.battles
+            .iter()
+            .map(|b| {
+                vec![
+                    b.driver_code.clone(),
+                    b.rival_code.clone(),
+                    b.start_lap.to_string(),
+                    b.end_lap.to_string(),
+                    b.laps().to_string(),
+                    format!("{:.3}", b.min_gap),
+                    b.overtakes.to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// A lap two cars finished next to each other within the battle gap.
+struct CloseLap<'a> {
+    lap: u32,
+    gap: f64,
+    /// The car ahead
+    ahead: &'a str,
+}
+
+/// Find runs of laps where two cars are next to each other within
+/// `battle_gap` at the line.
+pub fn detect_battles(
+    history: &RaceHistory,
+    overtakes: &Overtakes,
+    config: &OvertakeConfig,
+) -> Battles {
+    // Laps close together for each pair of drivers, with the car ahead
+    let mut close: HashMap<(&str, &str), Vec<CloseLap>> = HashMap::new();
+    for lap in 1..=history.lap_count() {
+        let standings = history.standings(lap);
+        for pair in standings.windows(2) {
+            let ((ahead, a), (behind, b)) = (pair[0], pair[1]);
+            let gap = b.race_time - a.race_time;
+            if gap > config.battle_gap {
+                continue;
+            }
+            let key = if ahead < behind {
+                (ahead, behind)
+            } else {
+                (behind, ahead)
+            };
+            close
+                .entry(key)
+                .or_default()
+                .push(CloseLap { lap, gap, ahead });
+        }
+    }
+
+    let mut battles = Vec::new();
+    for ((x, y), laps) in close {
+        let mut runs: Vec<&[CloseLap]> = Vec::new();
+        let mut run_start = 0;
+        for i in 1..=laps.len() {
+            if i == laps.len() || laps[i].lap != laps[i - 1].lap + 1 {
+                runs.push(&laps[run_start..i]);
+                run_start = i;
+            }
+        }
+        for run in runs.into_iter().filter(|r| r.len() >= config.battle_laps) {
+            let (start_lap, end_lap) = (run[0].lap, run[run.len() - 1].lap);
+            let ahead = run[0].ahead;
+            let rival = if ahead == x { y } else { x };
+            let passes = overtakes
+                .overtakes
+                .iter()
+                .filter(|o| {
+                    let pair = (o.driver_code.as_str(), o.passed_code.as_str());
+                    (pair == (x, y) || pair == (y, x))
+                        && (start_lap..=end_lap + 1).contains(&o.lap_number)
+                })
+                .count();
+            battles.push(Battle {
+                driver_code: ahead.to_string(),
+                rival_code: rival.to_string(),
+                start_lap,
+                end_lap,
+                min_gap: run.iter().map(|l| l.gap).fold(f64::INFINITY, f64::min),
+                overtakes: passes,
+            });
+        }
+    }
+    battles.sort_by(|a, b| {
+        a.start_lap
+            .cmp(&b.start_lap)
+            .then_with(|| a.driver_code.cmp(&b.driver_code))
+    });
+    Battles { battles }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        BoundingBox, Driver, DriverSession, DrsStatus, Lap, SessionType, TelemetrySample, Turn,
+    };
+    use chrono::Utc;
+    use std::f32::consts::TAU;
+
+    const RADIUS: f32 = 300.0;
+
+    fn session(drivers: Vec<DriverSession>) -> Session {
+        Session {
+            schema_version: crate::loader::CURRENT_SCHEMA_VERSION,
+            year: 2025,
+            circuit: "Austin".into(),
+            circuit_short: "austin".into(),
+            session_type: SessionType::Race,
+            date: Utc::now(),
+            drivers,
+        }
+    }
+
+    fn driver(code: &str, laps: Vec<Lap>) -> DriverSession {
+        DriverSession {
+            driver: Driver {
+                code: code.into(),
+                name: String::new(),
+                number: 1,
+                team: String::new(),
+                team_color: String::new(),
+            },
+            laps,
+        }
+    }
+
+    /// Laps around a circle driven at `speed(t)` m/s, sampled every 0.25 s.
+    fn circle_laps(laps: u32, speed: impl Fn(f64) -> f32) -> Vec<Lap> {
+        let mut result: Vec<Lap> = Vec::new();
+        let (mut distance, mut time) = (0.0f32, 0.0f64);
+        let lap_length = TAU * RADIUS;
+        loop {
+            let number = (distance / lap_length) as u32 + 1;
+            if number > laps {
+                break;
+            }
+            let angle = distance / RADIUS;
+            let sample = TelemetrySample {
+                position: Vec3::new(RADIUS * angle.cos(), 0.0, RADIUS * angle.sin()),
+                time,
+                throttle: 100,
+                brake: 0,
+                gear: 7,
+                speed: (speed(time) * 3.6) as u16,
+                drs: DrsStatus::Off,
+            };
+            if result.last().is_none_or(|l| l.number != number) {
+                result.push(Lap {
+                    number,
+                    lap_time: None,
+                    sector_times: [None; 3],
+                    samples: Vec::new(),
+                    is_valid: true,
+                });
+            }
+            result.last_mut().unwrap().samples.push(sample);
+            distance += speed(time) * 0.25;
+            time += 0.25;
+        }
+        result
+    }
+
+    #[test]
+    fn test_overtake() {
+        // NOR pulls away for two laps then fades; VER catches and passes
+        // him around t = 145 s, 0.846 laps into lap 4
+        let nor_slows_at = 2.0 * (TAU * RADIUS / 52.0) as f64;
+        let session = session(vec![
+            driver(
+                "NOR",
+                circle_laps(5, |t| if t < nor_slows_at { 52.0 } else { 48.0 }),
+            ),
+            driver("VER", circle_laps(5, |_| 50.0)),
+        ]);
+        let angle = 0.846 * TAU;
+        let (x, z) = (RADIUS * angle.cos(), RADIUS * angle.sin());
+        let bounds = BoundingBox::new(x - 40.0, x + 40.0, z - 40.0, z + 40.0);
+        let turns = CircuitTurns {
+            circuit: "Austin".into(),
+            turns: vec![Turn {
+                name: "T12".into(),
+                bounds: bounds.clone(),
+                approach: bounds.clone(),
+                apex: bounds.clone(),
+                exit: bounds,
+            }],
+            speed_traps: Vec::new(),
+        };
+
+        let overtakes = detect_overtakes(
+            &session,
+            &PitStops::default(),
+            Some(&turns),
+            &OvertakeConfig::default(),
+        );
+        assert_eq!(overtakes.overtakes.len(), 1, "{overtakes:?}");
+        let pass = &overtakes.overtakes[0];
+        assert_eq!(
+            (pass.driver_code.as_str(), pass.passed_code.as_str()),
+            ("VER", "NOR")
+        );
+        assert_eq!(pass.lap_number, 4);
+        assert!((pass.session_time - 145.0).abs() < 1.0, "{pass:?}");
+        assert_eq!(pass.turn.as_deref(), Some("T12"));
+
+        // The same swap while NOR is in the pit lane doesn't count
+        let stops = PitStops {
+            stops: vec![crate::pit::PitStop {
+                driver_code: "NOR".into(),
+                lap_number: 4,
+                entry_time: 140.0,
+                exit_time: 160.0,
+                stationary_time: 2.0,
+                pit_loss: None,
+            }],
+        };
+        let overtakes = detect_overtakes(&session, &stops, None, &OvertakeConfig::default());
+        assert!(overtakes.overtakes.is_empty());
+    }
+
+    #[test]
+    fn test_battles() {
+        let timed = |code: &str, times: &[f64]| {
+            let laps = times
+                .iter()
+                .zip(1..)
+                .map(|(&t, number)| Lap {
+                    number,
+                    lap_time: Some(t),
+                    sector_times: [None; 3],
+                    samples: Vec::new(),
+                    is_valid: true,
+                })
+                .collect();
+            driver(code, laps)
+        };
+        // HAM closes to within a second of LEC from lap 2 to lap 5
+        let session = session(vec![
+            timed("LEC", &[90.0, 90.0, 90.0, 90.0, 90.0, 88.0]),
+            timed("HAM", &[91.5, 89.2, 90.0, 90.1, 89.9, 90.0]),
+        ]);
+        let history = RaceHistory::new(&session, &PitStops::default());
+        let battles = detect_battles(&history, &Overtakes::default(), &OvertakeConfig::default());
+        assert_eq!(battles.battles.len(), 1);
+        let battle = &battles.battles[0];
+        assert_eq!(
+            (battle.driver_code.as_str(), battle.rival_code.as_str()),
+            ("LEC", "HAM")
+        );
+        assert_eq!((battle.start_lap, battle.end_lap), (2, 5));
+        assert!((battle.min_gap - 0.7).abs() < 1e-6);
+    }
+}
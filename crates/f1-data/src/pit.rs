@@ -0,0 +1,610 @@
+//! Pit stop detection and pit-loss estimation.
+//!
+//! The export has no pit information, so stops are found from telemetry:
+//!
+//! 1. The racing line is taken from the session's fastest lap. How far push
+//!    laps stray from it gives the track's spread; anything further out is
+//!    off the racing line. `PitLane` keeps that threshold plus the pit-lane
+//!    corridor: grid cells where the off-line, pit-speed samples of pit laps
+//!    of at least `PitConfig::min_corridor_drivers` drivers fall. A single
+//!    car running wide or spinning on its in-lap doesn't make a corridor.
+//! 2. On laps classified as in-, out- or pit-through laps, a stretch of
+//!    samples off the line and in the corridor with a run at pit-limiter
+//!    speed is a pit visit, from entry to exit. A run is only broken by
+//!    more than `PitConfig::max_gap` seconds back on the racing line, since
+//!    the pit lane runs close to the track at either end.
+//! 3. Time spent below `stationary_speed` is the stop itself; the time lost
+//!    is the pit-lane time minus the time the driver's typical push lap
+//!    takes between the same two points of the track.
+
+use std::collections::{HashMap, HashSet};
+
+use glam::Vec2;
+use serde::Serialize;
+
+use crate::classify::LapClasses;
+use crate::export::{number, Exportable};
+use crate::{DriverSession, Lap, LapTrace, Session, TelemetrySample};
+
+/// Thresholds for pit detection.
+#[derive(Debug, Clone, Copy)]
+pub struct PitConfig {
+    /// Racing line point spacing (m)
+    pub line_step: f32,
+    /// Distance beyond the push laps' spread around the racing line at which
+    /// a sample counts as off the line (m)
+    pub margin: f32,
+    /// Highest pit-lane speed (km/h)
+    pub pit_speed: u16,
+    /// Shortest run at pit speed for a visit to count (s)
+    pub min_pit_time: f64,
+    /// Speed below which the car counts as stationary (km/h)
+    pub stationary_speed: u16,
+    /// Longest stretch back near the racing line within one visit (s)
+    pub max_gap: f64,
+    /// Side of the grid cells the pit-lane corridor is kept in (m)
+    pub corridor_cell: f32,
+    /// Drivers whose pit laps must cross a cell for it to be pit lane (or
+    /// all the drivers with pit laps, if fewer)
+    pub min_corridor_drivers: usize,
+}
+
+impl Default for PitConfig {
+    fn default() -> Self {
+        Self {
+            line_step: 10.0,
+            margin: 6.0,
+            pit_speed: 90,
+            min_pit_time: 4.0,
+            stationary_speed: 5,
+            max_gap: 5.0,
+            corridor_cell: 10.0,
+            min_corridor_drivers: 3,
+        }
+    }
+}
+
+/// One pass through the pit lane.
+#[derive(Debug, Clone, Serialize)]
+pub struct PitStop {
+    pub driver_code: String,
+    /// Lap on which the car entered the pit lane
+    pub lap_number: u32,
+    /// Session time of the first sample in the pit lane
+    pub entry_time: f64,
+    /// Session time of the last sample in the pit lane
+    pub exit_time: f64,
+    /// Time stopped (0 for a drive-through)
+    pub stationary_time: f64,
+    /// Time lost against a push lap over the same stretch of track
+    pub pit_loss: Option<f64>,
+}
+
+impl PitStop {
+    /// Time from pit entry to pit exit.
+    pub fn pit_lane_time(&self) -> f64 {
+        self.exit_time - self.entry_time
+    }
+}
+
+/// Every pit stop in a session.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PitStops {
+    /// Sorted by driver order in the session, then by time
+    pub stops: Vec<PitStop>,
+}
+
+impl PitStops {
+    /// A driver's stops, in order.
+    pub fn for_driver<'a>(&'a self, driver_code: &'a str) -> impl Iterator<Item = &'a PitStop> {
+        self.stops
+            .iter()
+            .filter(move |s| s.driver_code == driver_code)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stops.is_empty()
+    }
+}
+
+impl Exportable for PitStops {
+    fn title(&self) -> String {
+        "Pit stops".into()
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        vec![
+            "Driver",
+            "Lap",
+            "Entry (s)",
+            "Exit (s)",
+            "Pit lane (s)",
+            "Stationary (s)",
+            "Loss (s)",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.stops
+            .iter()
+            .map(|stop| {
+                vec![
+                    stop.driver_code.clone(),
+                    stop.lap_number.to_string(),
+                    format!("{:.3}", stop.entry_time),
+                    format!("{:.3}", stop.exit_time),
+                    format!("{:.3}", stop.pit_lane_time()),
+                    format!("{:.1}", stop.stationary_time),
+                    number(stop.pit_loss, 3),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// The racing line as evenly spaced points, with lookups of how far a
+/// position is from it and where along it.
+#[derive(Debug, Clone)]
+struct RacingLine {
+    points: Vec<Vec2>,
+    step: f32,
+}
+
+impl RacingLine {
+    fn new(lap: &Lap, step: f32) -> Option<Self> {
+        let trace = LapTrace::new(lap)?;
+        let count = (trace.length() / step).floor() as usize + 1;
+        let points: Vec<Vec2> = (0..count)
+            .map(|i| {
+                let position = trace
+                    .sample_at(trace.time_at_distance(i as f32 * step))
+                    .position;
+                Vec2::new(position.x, position.z)
+            })
+            .collect();
+        (points.len() >= 2).then_some(Self { points, step })
+    }
+
+    fn length(&self) -> f32 {
+        (self.points.len() - 1) as f32 * self.step
+    }
+
+    /// Distance along the line of the closest point, and the offset from it.
+    fn project(&self, sample: &TelemetrySample) -> (f32, f32) {
+        let point = Vec2::new(sample.position.x, sample.position.z);
+        let mut best = (0.0, f32::INFINITY);
+        for (i, segment) in self.points.windows(2).enumerate() {
+            let (start, direction) = (segment[0], segment[1] - segment[0]);
+            let t = ((point - start).dot(direction) / direction.length_squared().max(f32::EPSILON))
+                .clamp(0.0, 1.0);
+            let offset = point.distance(start + direction * t);
+            if offset < best.1 {
+                best = ((i as f32 + t) * self.step, offset);
+            }
+        }
+        best
+    }
+}
+
+/// The pit-lane corridor, learned from how far samples stray from the
+/// racing line.
+#[derive(Debug, Clone)]
+pub struct PitLane {
+    line: RacingLine,
+    /// Samples further than this from the racing line are off it (m)
+    pub threshold: f32,
+    /// Side of the corridor cells (m)
+    cell: f32,
+    /// Grid cells (X, Z) the pit lane passes through
+    corridor: HashSet<(i32, i32)>,
+}
+
+impl PitLane {
+    /// Learn the corridor. `None` if the session has no fastest lap.
+    pub fn learn(session: &Session, classes: &LapClasses, config: &PitConfig) -> Option<Self> {
+        let (_, fastest) = session.fastest_laps().into_iter().next()?;
+        let line = RacingLine::new(fastest, config.line_step)?;
+
+        // Spread of push laps around the racing line: one lap per driver,
+        // every fourth sample, is plenty
+        let mut offsets: Vec<f32> = session
+            .drivers
+            .iter()
+            .filter_map(|d| classes.representative_laps(d).into_iter().next())
+            .flat_map(|lap| lap.samples.iter().step_by(4))
+            .map(|s| line.project(s).1)
+            .collect();
+        offsets.sort_by(f32::total_cmp);
+        let spread = offsets
+            .get((offsets.len() as f32 * 0.99) as usize)
+            .or(offsets.last())
+            .copied()
+            .unwrap_or(0.0);
+        let threshold = spread + config.margin;
+
+        // Cells with off-line pit-speed samples, counted once per driver
+        let mut lane = Self {
+            line,
+            threshold,
+            cell: config.corridor_cell,
+            corridor: HashSet::new(),
+        };
+        let mut drivers_per_cell: HashMap<(i32, i32), usize> = HashMap::new();
+        let mut pitting_drivers = 0;
+        for driver in &session.drivers {
+            let cells: HashSet<(i32, i32)> = pit_laps(driver, classes)
+                .into_iter()
+                .flat_map(|lap| &lap.samples)
+                .filter(|s| s.speed <= config.pit_speed && lane.is_off_line(s))
+                .map(|s| lane.cell_of(s))
+                .collect();
+            pitting_drivers += usize::from(!cells.is_empty());
+            for cell in cells {
+                *drivers_per_cell.entry(cell).or_default() += 1;
+            }
+        }
+        let required = config.min_corridor_drivers.min(pitting_drivers).max(1);
+        lane.corridor = drivers_per_cell
+            .into_iter()
+            .filter(|(_, drivers)| *drivers >= required)
+            .map(|(cell, _)| cell)
+            .collect();
+        Some(lane)
+    }
+
+    /// Whether a sample is off the racing line.
+    pub fn is_off_line(&self, sample: &TelemetrySample) -> bool {
+        self.line.project(sample).1 > self.threshold
+    }
+
+    /// Whether a sample is in the pit lane: off the racing line and in or
+    /// next to a corridor cell.
+    pub fn in_pit_lane(&self, sample: &TelemetrySample) -> bool {
+        let (x, z) = self.cell_of(sample);
+        self.is_off_line(sample)
+            && (-1..=1).any(|dx| (-1..=1).any(|dz| self.corridor.contains(&(x + dx, z + dz))))
+    }
+
+    fn cell_of(&self, sample: &TelemetrySample) -> (i32, i32) {
+        (
+            (sample.position.x / self.cell).floor() as i32,
+            (sample.position.z / self.cell).floor() as i32,
+        )
+    }
+}
+
+/// A driver's in-, out- and pit-through laps.
+fn pit_laps<'a>(driver: &'a DriverSession, classes: &LapClasses) -> Vec<&'a Lap> {
+    driver
+        .laps
+        .iter()
+        .filter(|lap| {
+            classes
+                .get(&driver.driver.code, lap.number)
+                .is_some_and(|c| c.is_pit())
+        })
+        .collect()
+}
+
+/// Find every pit stop in a session.
+pub fn detect_pit_stops(session: &Session, classes: &LapClasses, config: &PitConfig) -> PitStops {
+    let Some(lane) = PitLane::learn(session, classes, config) else {
+        return PitStops::default();
+    };
+    detect_with_lane(session, classes, &lane, config)
+}
+
+/// Find every pit stop using an already learned pit lane.
+pub fn detect_with_lane(
+    session: &Session,
+    classes: &LapClasses,
+    lane: &PitLane,
+    config: &PitConfig,
+) -> PitStops {
+    let mut stops = Vec::new();
+    for driver in &session.drivers {
+        let reference = typical_lap(driver, classes).and_then(LapTrace::new);
+        for visit in visits(driver, classes, lane, config) {
+            stops.push(pit_stop(driver, &visit, lane, reference.as_ref(), config));
+        }
+    }
+    PitStops { stops }
+}
+
+/// Samples of one pass through the pit lane.
+struct Visit<'a> {
+    lap_number: u32,
+    samples: Vec<&'a TelemetrySample>,
+}
+
+/// Stretches of a driver's pit laps in the pit lane that include a run at
+/// pit speed.
+fn visits<'a>(
+    driver: &'a DriverSession,
+    classes: &LapClasses,
+    lane: &PitLane,
+    config: &PitConfig,
+) -> Vec<Visit<'a>> {
+    let mut visits = Vec::new();
+    let mut current: Option<Visit> = None;
+    let mut previous_lap = None;
+
+    for lap in pit_laps(driver, classes) {
+        // The pit lane spans consecutive laps (in-lap, out-lap) at most
+        if previous_lap.is_some_and(|n: u32| n + 1 != lap.number) {
+            visits.extend(current.take());
+        }
+        previous_lap = Some(lap.number);
+
+        for sample in &lap.samples {
+            if lane.in_pit_lane(sample) {
+                current
+                    .get_or_insert_with(|| Visit {
+                        lap_number: lap.number,
+                        samples: Vec::new(),
+                    })
+                    .samples
+                    .push(sample);
+            } else if current
+                .as_ref()
+                .and_then(|v| v.samples.last())
+                .is_some_and(|last| sample.time - last.time > config.max_gap)
+            {
+                visits.extend(current.take());
+            }
+        }
+    }
+    visits.extend(current);
+    visits.retain(|visit| has_pit_speed_run(&visit.samples, config));
+    visits
+}
+
+fn has_pit_speed_run(samples: &[&TelemetrySample], config: &PitConfig) -> bool {
+    let mut run_start: Option<f64> = None;
+    for sample in samples {
+        if sample.speed > config.pit_speed {
+            run_start = None;
+            continue;
+        }
+        let start = *run_start.get_or_insert(sample.time);
+        if sample.time - start >= config.min_pit_time {
+            return true;
+        }
+    }
+    false
+}
+
+fn pit_stop(
+    driver: &DriverSession,
+    visit: &Visit,
+    lane: &PitLane,
+    reference: Option<&LapTrace>,
+    config: &PitConfig,
+) -> PitStop {
+    let (entry, exit) = (visit.samples[0], visit.samples[visit.samples.len() - 1]);
+    let stationary_time = visit
+        .samples
+        .windows(2)
+        .filter(|pair| pair[0].speed <= config.stationary_speed)
+        .map(|pair| pair[1].time - pair[0].time)
+        // `sum` of no terms is -0.0, which prints as "-0.0" for drive-throughs
+        .fold(0.0, |total, time| total + time);
+
+    // Time a push lap takes between the points where the car entered and
+    // left the pit lane, wrapping over the start/finish line
+    let pit_loss = reference.map(|trace| {
+        let length = lane.line.length();
+        let time_at = |sample: &TelemetrySample| {
+            let fraction = lane.line.project(sample).0 / length;
+            trace.time_at_distance(fraction * trace.length())
+        };
+        let (from, to) = (time_at(entry), time_at(exit));
+        let on_track = if to >= from {
+            to - from
+        } else {
+            trace.duration() - from + to
+        };
+        (exit.time - entry.time) - on_track
+    });
+
+    PitStop {
+        driver_code: driver.driver.code.clone(),
+        lap_number: visit.lap_number,
+        entry_time: entry.time,
+        exit_time: exit.time,
+        stationary_time,
+        pit_loss,
+    }
+}
+
+/// The driver's push lap with the median lap time.
+fn typical_lap<'a>(driver: &'a DriverSession, classes: &LapClasses) -> Option<&'a Lap> {
+    let mut laps: Vec<&Lap> = classes
+        .representative_laps(driver)
+        .into_iter()
+        .filter(|lap| lap.lap_time.is_some())
+        .collect();
+    laps.sort_by(|a, b| {
+        a.lap_time
+            .partial_cmp(&b.lap_time)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    laps.get(laps.len() / 2).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::{classify_session, ClassifyConfig, LapClass};
+    use crate::{Driver, DrsStatus, SessionType};
+    use chrono::Utc;
+    use glam::Vec3;
+    use std::f32::consts::TAU;
+
+    const RADIUS: f32 = 300.0;
+    const PIT_RADIUS: f32 = 270.0;
+
+    /// Drive around a circle from `from` to `to` (radians) at `speed` (m/s),
+    /// or stand still for `stop` seconds, sampling every 0.25 s.
+    struct Track {
+        samples: Vec<TelemetrySample>,
+        time: f64,
+    }
+
+    impl Track {
+        fn drive(&mut self, from: f32, to: f32, radius: f32, speed: f32) -> &mut Self {
+            let step = speed * 0.25 / radius;
+            let mut angle = from;
+            while angle < to {
+                self.sample(angle, radius, speed);
+                angle += step;
+            }
+            self
+        }
+
+        fn stop(&mut self, angle: f32, seconds: f64) -> &mut Self {
+            let end = self.time + seconds;
+            while self.time < end {
+                self.sample(angle, PIT_RADIUS, 0.0);
+            }
+            self
+        }
+
+        fn sample(&mut self, angle: f32, radius: f32, speed: f32) {
+            self.samples.push(TelemetrySample {
+                position: Vec3::new(radius * angle.cos(), 0.0, radius * angle.sin()),
+                time: self.time,
+                throttle: 100,
+                brake: 0,
+                gear: 5,
+                speed: (speed * 3.6) as u16,
+                drs: DrsStatus::Off,
+            });
+            self.time += 0.25;
+        }
+
+        fn lap(&mut self, number: u32) -> Lap {
+            let samples = std::mem::take(&mut self.samples);
+            Lap {
+                number,
+                lap_time: Some(samples.last().unwrap().time - samples[0].time + 0.25),
+                sector_times: [None; 3],
+                samples,
+                is_valid: true,
+            }
+        }
+    }
+
+    /// Five laps at 50 m/s, pitting at the end of lap 3 and standing for
+    /// `stop` seconds (0 for a drive-through). The pit lane runs 30 m inside
+    /// the track from 0.8 rad before the line to 0.8 rad after it, at
+    /// 22 m/s. With `run_wide`, the car also runs 30 m wide at 20 m/s for a
+    /// while late on its in-lap.
+    fn pit_race(stop: f64, run_wide: bool) -> Vec<Lap> {
+        let mut track = Track {
+            samples: Vec::new(),
+            time: 0.0,
+        };
+        let mut laps = Vec::new();
+        for number in 1..=2 {
+            laps.push(track.drive(0.0, TAU, RADIUS, 50.0).lap(number));
+        }
+        if run_wide {
+            track
+                .drive(0.0, 4.4, RADIUS, 50.0)
+                .drive(4.4, 4.8, RADIUS + 30.0, 20.0)
+                .drive(4.8, TAU - 0.8, RADIUS, 50.0);
+        } else {
+            track.drive(0.0, TAU - 0.8, RADIUS, 50.0);
+        }
+        track.drive(TAU - 0.8, TAU, PIT_RADIUS, 22.0);
+        laps.push(track.lap(3));
+        track
+            .drive(0.0, 0.2, PIT_RADIUS, 22.0)
+            .stop(0.2, stop)
+            .drive(0.2, 0.8, PIT_RADIUS, 22.0)
+            .drive(0.8, TAU, RADIUS, 50.0);
+        laps.push(track.lap(4));
+        laps.push(track.drive(0.0, TAU, RADIUS, 50.0).lap(5));
+        laps
+    }
+
+    fn session(drivers: Vec<(&str, Vec<Lap>)>) -> Session {
+        Session {
+            schema_version: crate::loader::CURRENT_SCHEMA_VERSION,
+            year: 2025,
+            circuit: "Austin".into(),
+            circuit_short: "austin".into(),
+            session_type: SessionType::Race,
+            date: Utc::now(),
+            drivers: drivers
+                .into_iter()
+                .map(|(code, laps)| DriverSession {
+                    driver: Driver {
+                        code: code.into(),
+                        name: String::new(),
+                        number: 1,
+                        team: String::new(),
+                        team_color: String::new(),
+                    },
+                    laps,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_pit_stop() {
+        let session = session(vec![("VER", pit_race(3.0, false))]);
+        let classes = classify_session(&session, &ClassifyConfig::default());
+        assert_eq!(classes.get("VER", 3), Some(LapClass::InLap));
+        assert_eq!(classes.get("VER", 4), Some(LapClass::OutLap));
+
+        let stops = detect_pit_stops(&session, &classes, &PitConfig::default());
+        assert_eq!(stops.stops.len(), 1);
+        let stop = &stops.stops[0];
+        assert_eq!(stop.lap_number, 3);
+        assert!((stop.stationary_time - 3.0).abs() < 0.3, "{stop:?}");
+
+        // 432 m at 22 m/s plus 3 s stopped, against 480 m at 50 m/s
+        let expected_loss = 1.6 * PIT_RADIUS as f64 / 22.0 + 3.0 - 1.6 * RADIUS as f64 / 50.0;
+        let loss = stop.pit_loss.unwrap();
+        assert!(
+            (loss - expected_loss).abs() < 0.6,
+            "{loss} vs {expected_loss}"
+        );
+
+        // A drive-through is a stop with no stationary time
+        let drive_through = self::session(vec![("VER", pit_race(0.0, false))]);
+        let classes = classify_session(&drive_through, &ClassifyConfig::default());
+        let stops = detect_pit_stops(&drive_through, &classes, &PitConfig::default());
+        assert_eq!(stops.stops.len(), 1);
+        let stationary = stops.stops[0].stationary_time;
+        assert_eq!(format!("{stationary:.1}"), "0.0");
+    }
+
+    #[test]
+    fn test_running_wide_is_not_a_pit_stop() {
+        let session = session(vec![
+            ("VER", pit_race(3.0, false)),
+            ("NOR", pit_race(3.0, true)),
+            ("LEC", pit_race(3.0, false)),
+        ]);
+        let classes = classify_session(&session, &ClassifyConfig::default());
+        assert_eq!(classes.get("NOR", 3), Some(LapClass::InLap));
+
+        // NOR's slow moment off the line is outside the corridor
+        let stops = detect_pit_stops(&session, &classes, &PitConfig::default());
+        let nor: Vec<&PitStop> = stops
+            .stops
+            .iter()
+            .filter(|s| s.driver_code == "NOR")
+            .collect();
+        assert_eq!(nor.len(), 1, "{nor:?}");
+        assert!((nor[0].stationary_time - 3.0).abs() < 0.3);
+        assert_eq!(stops.stops.len(), 3);
+        let ver = stops.for_driver("VER").next().unwrap();
+        let duration = |stop: &PitStop| stop.exit_time - stop.entry_time;
+        assert!((duration(nor[0]) - duration(ver)).abs() < 0.5, "{nor:?}");
+    }
+}
@@ -0,0 +1,366 @@
+//! Coordinate registration between sessions.
+//!
+//! Position data from different years (and sometimes different sessions) at
+//! the same circuit comes in slightly shifted and rotated X/Z frames, so a
+//! turn file authored on one year misses on the next. `register_sessions`
+//! fits a rigid transform (rotation plus translation in the track plane) that
+//! maps one session's racing line onto a reference session's, using
+//! iterative closest point (ICP) on the fastest laps' centerlines. The
+//! `RigidTransform` can be saved and applied to samples (`apply_to_session`)
+//! or, inverted, to turn definitions (`apply_to_turns`).
+//!
+//! ICP only finds the nearest fit, so the frames should differ by a
+//! rotation of well under 20°; translation is handled by first matching the
+//! centerlines' centroids.
+
+use glam::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::smooth::resample_by_distance;
+use crate::{BoundingBox, CircuitTurns, Lap, Session};
+
+/// Rotation about the vertical axis followed by a translation in the X/Z
+/// plane. Elevation (Y) is left unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RigidTransform {
+    /// Counter-clockwise rotation from +X towards +Z (radians)
+    pub rotation: f32,
+    /// Offset added after rotating, as (X, Z) in meters
+    pub translation: Vec2,
+}
+
+impl Default for RigidTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl RigidTransform {
+    pub const IDENTITY: Self = Self {
+        rotation: 0.0,
+        translation: Vec2::ZERO,
+    };
+
+    /// Transform a point in the X/Z plane.
+    pub fn apply_2d(&self, point: Vec2) -> Vec2 {
+        Vec2::from_angle(self.rotation).rotate(point) + self.translation
+    }
+
+    /// Transform a track position, keeping its elevation.
+    pub fn apply(&self, position: Vec3) -> Vec3 {
+        let moved = self.apply_2d(Vec2::new(position.x, position.z));
+        Vec3::new(moved.x, position.y, moved.y)
+    }
+
+    /// The transform that undoes this one.
+    pub fn inverse(&self) -> Self {
+        let rotation = Vec2::from_angle(-self.rotation);
+        Self {
+            rotation: -self.rotation,
+            translation: -rotation.rotate(self.translation),
+        }
+    }
+
+    /// Axis-aligned box around the transformed corners of `bounds`.
+    ///
+    /// Boxes stay axis-aligned, so a rotation grows them slightly (each side
+    /// by about the other side's length times `sin(rotation)`).
+    pub fn apply_to_box(&self, bounds: &BoundingBox) -> BoundingBox {
+        let corners = [
+            Vec2::new(bounds.min_x, bounds.min_z),
+            Vec2::new(bounds.min_x, bounds.max_z),
+            Vec2::new(bounds.max_x, bounds.min_z),
+            Vec2::new(bounds.max_x, bounds.max_z),
+        ]
+        .map(|corner| self.apply_2d(corner));
+        let min = corners.into_iter().reduce(Vec2::min).unwrap_or_default();
+        let max = corners.into_iter().reduce(Vec2::max).unwrap_or_default();
+        BoundingBox::new(min.x, max.x, min.y, max.y)
+    }
+
+    /// Move every sample of a lap.
+    pub fn apply_to_lap(&self, lap: &mut Lap) {
+        for sample in &mut lap.samples {
+            sample.position = self.apply(sample.position);
+        }
+    }
+
+    /// Move every sample of a session.
+    pub fn apply_to_session(&self, session: &mut Session) {
+        for lap in session.drivers.iter_mut().flat_map(|d| d.laps.iter_mut()) {
+            self.apply_to_lap(lap);
+        }
+    }
+
+    /// Move every turn's boxes (see `apply_to_box`) and speed trap gates.
+    pub fn apply_to_turns(&self, turns: &mut CircuitTurns) {
+        for trap in &mut turns.speed_traps {
+            trap.start = self.apply_2d(trap.start);
+            trap.end = self.apply_2d(trap.end);
+        }
+        for turn in &mut turns.turns {
+            for bounds in [
+                &mut turn.bounds,
+                &mut turn.approach,
+                &mut turn.apex,
+                &mut turn.exit,
+            ] {
+                *bounds = self.apply_to_box(bounds);
+            }
+        }
+    }
+}
+
+/// ICP settings.
+#[derive(Debug, Clone, Copy)]
+pub struct RegistrationConfig {
+    /// Centerline point spacing (m)
+    pub step: f32,
+    pub max_iterations: usize,
+    /// Fraction of closest point pairs used in each fit; the rest (e.g.
+    /// where the layout or the line differs) are ignored
+    pub trim: f32,
+    /// Stop once the RMS error improves by less than this (m)
+    pub tolerance: f32,
+}
+
+impl Default for RegistrationConfig {
+    fn default() -> Self {
+        Self {
+            step: 10.0,
+            max_iterations: 50,
+            trim: 0.9,
+            tolerance: 1e-3,
+        }
+    }
+}
+
+/// Result of registering a session or lap against a reference.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Registration {
+    /// Maps the registered positions into the reference frame
+    pub transform: RigidTransform,
+    /// RMS distance between matched centerline points after the fit (m)
+    pub rms_error: f32,
+    pub iterations: usize,
+}
+
+/// Register a session against a reference session using each session's
+/// fastest lap. `None` if either has no timed lap with telemetry.
+pub fn register_sessions(
+    session: &Session,
+    reference: &Session,
+    config: &RegistrationConfig,
+) -> Option<Registration> {
+    let (_, lap) = session.fastest_laps().into_iter().next()?;
+    let (_, reference_lap) = reference.fastest_laps().into_iter().next()?;
+    register_laps(lap, reference_lap, config)
+}
+
+/// Register one lap's line against a reference lap's line.
+///
+/// `None` if either lap has too little telemetry for a centerline.
+pub fn register_laps(
+    lap: &Lap,
+    reference: &Lap,
+    config: &RegistrationConfig,
+) -> Option<Registration> {
+    let source = centerline(lap, config.step);
+    let target = centerline(reference, config.step);
+    if source.len() < 3 || target.len() < 2 {
+        return None;
+    }
+    Some(icp(&source, &target, config))
+}
+
+/// Lap positions in the X/Z plane, evenly spaced by distance.
+fn centerline(lap: &Lap, step: f32) -> Vec<Vec2> {
+    resample_by_distance(lap, step)
+        .samples
+        .iter()
+        .map(|s| Vec2::new(s.position.x, s.position.z))
+        .collect()
+}
+
+fn icp(source: &[Vec2], target: &[Vec2], config: &RegistrationConfig) -> Registration {
+    let mut transform = RigidTransform {
+        rotation: 0.0,
+        translation: centroid(target) - centroid(source),
+    };
+    let keep = ((source.len() as f32 * config.trim).ceil() as usize).clamp(3, source.len());
+    let mut rms_error = f32::INFINITY;
+    let mut iterations = 0;
+
+    while iterations < config.max_iterations {
+        iterations += 1;
+        // Closest point on the reference line to each moved point, nearest first
+        let mut pairs: Vec<(f32, Vec2, Vec2)> = source
+            .iter()
+            .map(|&point| {
+                let moved = transform.apply_2d(point);
+                let nearest = closest_point(target, moved);
+                (nearest.distance_squared(moved), point, nearest)
+            })
+            .collect();
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        pairs.truncate(keep);
+
+        let error = (pairs.iter().map(|p| p.0).sum::<f32>() / keep as f32).sqrt();
+        let improved = rms_error - error;
+        rms_error = error;
+        if improved < config.tolerance {
+            break;
+        }
+        transform = fit(&pairs);
+    }
+
+    Registration {
+        transform,
+        rms_error,
+        iterations,
+    }
+}
+
+/// Closest point to `point` on the polyline through `line`.
+fn closest_point(line: &[Vec2], point: Vec2) -> Vec2 {
+    line.windows(2)
+        .map(|segment| {
+            let (start, direction) = (segment[0], segment[1] - segment[0]);
+            let t = (point - start).dot(direction) / direction.length_squared().max(f32::EPSILON);
+            start + direction * t.clamp(0.0, 1.0)
+        })
+        .min_by(|a, b| {
+            a.distance_squared(point)
+                .total_cmp(&b.distance_squared(point))
+        })
+        .unwrap_or(point)
+}
+
+/// Least-squares rigid transform moving each pair's point onto its match.
+fn fit(pairs: &[(f32, Vec2, Vec2)]) -> RigidTransform {
+    let from = centroid_of(pairs.iter().map(|p| p.1));
+    let to = centroid_of(pairs.iter().map(|p| p.2));
+    let (mut dot, mut cross) = (0.0, 0.0);
+    for &(_, p, q) in pairs {
+        let (p, q) = (p - from, q - to);
+        dot += p.dot(q);
+        cross += p.perp_dot(q);
+    }
+    let rotation = f32::atan2(cross, dot);
+    RigidTransform {
+        rotation,
+        translation: to - Vec2::from_angle(rotation).rotate(from),
+    }
+}
+
+fn centroid(points: &[Vec2]) -> Vec2 {
+    centroid_of(points.iter().copied())
+}
+
+fn centroid_of(points: impl Iterator<Item = Vec2>) -> Vec2 {
+    let (sum, count) = points.fold((Vec2::ZERO, 0), |(sum, n), p| (sum + p, n + 1));
+    sum / count.max(1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrsStatus, SpeedTrap, TelemetrySample, Turn};
+
+    /// A closed, irregular loop (no symmetry for ICP to snap to).
+    fn lap(transform: RigidTransform) -> Lap {
+        let samples = (0..=400)
+            .map(|i| {
+                let t = i as f32 / 400.0 * std::f32::consts::TAU;
+                let radius = 600.0 + 120.0 * (3.0 * t).cos() + 80.0 * t.sin();
+                let position = Vec3::new(radius * t.cos(), 5.0, 0.6 * radius * t.sin());
+                TelemetrySample {
+                    position: transform.apply(position),
+                    time: i as f64 * 0.2,
+                    throttle: 100,
+                    brake: 0,
+                    gear: 7,
+                    speed: 250,
+                    drs: DrsStatus::Off,
+                }
+            })
+            .collect();
+        Lap {
+            number: 1,
+            lap_time: Some(80.0),
+            sector_times: [None; 3],
+            samples,
+            is_valid: true,
+        }
+    }
+
+    #[test]
+    fn test_register_shifted_lap() {
+        // The new year's frame is rotated 4° and shifted ~40 m
+        let shift = RigidTransform {
+            rotation: 4f32.to_radians(),
+            translation: Vec2::new(35.0, -20.0),
+        };
+        let reference = lap(RigidTransform::IDENTITY);
+        let shifted = lap(shift);
+
+        let registration =
+            register_laps(&shifted, &reference, &RegistrationConfig::default()).unwrap();
+        let expected = shift.inverse();
+        assert!((registration.transform.rotation - expected.rotation).abs() < 0.2f32.to_radians());
+        assert!(
+            registration
+                .transform
+                .translation
+                .distance(expected.translation)
+                < 2.0
+        );
+        assert!(registration.rms_error < 2.0);
+
+        // Samples land back on the reference line
+        let mut aligned = shifted.clone();
+        registration.transform.apply_to_lap(&mut aligned);
+        let error = aligned.samples[100]
+            .position
+            .distance(reference.samples[100].position);
+        assert!(error < 2.0, "{error}");
+        assert!((aligned.samples[100].position.y - 5.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_transform_turns() {
+        let quarter_turn = RigidTransform {
+            rotation: std::f32::consts::FRAC_PI_2,
+            translation: Vec2::new(10.0, 0.0),
+        };
+        let bounds = BoundingBox::new(0.0, 20.0, 0.0, 10.0);
+        let mut turns = CircuitTurns {
+            circuit: "Austin".into(),
+            turns: vec![Turn {
+                name: "T1".into(),
+                bounds: bounds.clone(),
+                approach: bounds.clone(),
+                apex: bounds.clone(),
+                exit: bounds,
+            }],
+            speed_traps: vec![SpeedTrap {
+                name: "ST".into(),
+                start: Vec2::new(0.0, 5.0),
+                end: Vec2::new(20.0, 5.0),
+            }],
+        };
+        quarter_turn.apply_to_turns(&mut turns);
+
+        // (x, z) -> (-z, x), then +10 in X
+        let apex = &turns.turns[0].apex;
+        assert!((apex.min_x - 0.0).abs() < 1e-4 && (apex.max_x - 10.0).abs() < 1e-4);
+        assert!((apex.min_z - 0.0).abs() < 1e-4 && (apex.max_z - 20.0).abs() < 1e-4);
+        let trap = &turns.speed_traps[0];
+        assert!(trap.start.distance(Vec2::new(5.0, 0.0)) < 1e-4);
+        assert!(trap.end.distance(Vec2::new(5.0, 20.0)) < 1e-4);
+
+        let point = Vec3::new(3.0, 1.0, 4.0);
+        let round_trip = quarter_turn.inverse().apply(quarter_turn.apply(point));
+        assert!(round_trip.distance(point) < 1e-4);
+    }
+}
@@ -0,0 +1,538 @@
+//! Headless track rendering to SVG and PNG.
+//!
+//! Draws the circuit from a reference lap, overlays `CircuitTurns` boxes
+//! and speed trap gates with their labels and can color the line by any `Channel`. Nothing here
+//! needs Bevy or a GPU, so turn files can be reviewed as images and
+//! rendering can be checked in tests. PNG output needs the `png` feature,
+//! which rasterizes the SVG with resvg.
+//!
+//! Track X maps to image X and track Z to image Y, with +Z up as in the app.
+
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+
+use glam::Vec3;
+use thiserror::Error;
+
+use crate::{Channel, CircuitTurns, Lap, LapChannels};
+
+#[derive(Error, Debug)]
+pub enum RenderError {
+    #[error("Failed to write image: {0}")]
+    Io(#[from] io::Error),
+    #[error("Unknown image format for {0} (expected .svg or .png)")]
+    UnknownFormat(String),
+    #[error("PNG output needs the `png` feature of f1-data")]
+    PngUnsupported,
+    #[error("Failed to rasterize SVG: {0}")]
+    Png(String),
+}
+
+/// How the line is colored.
+#[derive(Debug, Clone)]
+pub enum LineColor {
+    /// One color for the whole lap
+    Solid(String),
+    /// Blue (low) to red (high) by a channel; signed channels are centered on zero
+    Channel(Channel),
+    /// Sample ranges in their own colors (e.g. mini-sectors by fastest driver)
+    Ranges(Vec<ColoredRange>),
+}
+
+impl Default for LineColor {
+    fn default() -> Self {
+        Self::Solid("#e8e8ee".to_string())
+    }
+}
+
+/// Samples `start..=end` of the lap, drawn in one color.
+#[derive(Debug, Clone)]
+pub struct ColoredRange {
+    pub start: usize,
+    pub end: usize,
+    pub color: String,
+}
+
+/// Image size and what to draw.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Image width in pixels; the height follows the track's shape
+    pub width: u32,
+    /// Margin around the track (pixels)
+    pub padding: f32,
+    /// Width of the line (pixels); the track surface is drawn twice as wide
+    pub line_width: f32,
+    pub line_color: LineColor,
+    /// Draw approach/apex/exit boxes as well as each turn's overall bounds
+    pub segments: bool,
+    /// Background color, transparent if `None`
+    pub background: Option<String>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            width: 1200,
+            padding: 40.0,
+            line_width: 4.0,
+            line_color: LineColor::default(),
+            segments: true,
+            background: Some("#15151e".to_string()),
+        }
+    }
+}
+
+/// Approach, apex and exit box colors.
+const SEGMENT_COLORS: [&str; 3] = ["#ffd166", "#ef476f", "#06d6a0"];
+const TURN_COLOR: &str = "#b36bff";
+const TRAP_COLOR: &str = "#4cc9f0";
+/// Low-to-high ramp for channel coloring (diverging, so zero sits in the middle).
+const RAMP: [[u8; 3]; 5] = [
+    [59, 76, 192],
+    [141, 176, 254],
+    [221, 221, 221],
+    [244, 154, 123],
+    [180, 4, 38],
+];
+/// Height of the channel legend below the track (pixels).
+const LEGEND_HEIGHT: f32 = 48.0;
+
+/// Maps track X/Z into the image.
+struct Projection {
+    min_x: f32,
+    max_z: f32,
+    scale: f32,
+    offset_x: f32,
+    padding: f32,
+}
+
+impl Projection {
+    /// Fit the lap, the turn boxes and the trap gates into a square of `width` pixels;
+    /// returns the projection and the height actually used.
+    fn fit(lap: &Lap, turns: Option<&CircuitTurns>, width: f32, padding: f32) -> (Self, f32) {
+        let (mut min_x, mut max_x) = (f32::INFINITY, f32::NEG_INFINITY);
+        let (mut min_z, mut max_z) = (f32::INFINITY, f32::NEG_INFINITY);
+        let mut include = |x: f32, z: f32| {
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+            min_z = min_z.min(z);
+            max_z = max_z.max(z);
+        };
+        for sample in &lap.samples {
+            include(sample.position.x, sample.position.z);
+        }
+        for turn in turns.map(|t| t.turns.as_slice()).unwrap_or_default() {
+            include(turn.bounds.min_x, turn.bounds.min_z);
+            include(turn.bounds.max_x, turn.bounds.max_z);
+        }
+        for trap in turns.map(|t| t.speed_traps.as_slice()).unwrap_or_default() {
+            include(trap.start.x, trap.start.y);
+            include(trap.end.x, trap.end.y);
+        }
+        if !min_x.is_finite() {
+            (min_x, max_x, min_z, max_z) = (0.0, 1.0, 0.0, 1.0);
+        }
+
+        let size = (width - 2.0 * padding).max(1.0);
+        let scale = size / (max_x - min_x).max(max_z - min_z).max(f32::EPSILON);
+        let projection = Self {
+            min_x,
+            max_z,
+            scale,
+            offset_x: (size - (max_x - min_x) * scale) / 2.0,
+            padding,
+        };
+        (projection, (max_z - min_z) * scale + 2.0 * padding)
+    }
+
+    fn point(&self, x: f32, z: f32) -> (f32, f32) {
+        (
+            (x - self.min_x) * self.scale + self.padding + self.offset_x,
+            (self.max_z - z) * self.scale + self.padding,
+        )
+    }
+
+    fn project(&self, position: Vec3) -> (f32, f32) {
+        self.point(position.x, position.z)
+    }
+}
+
+/// Render the track as an SVG document.
+pub fn render_svg(lap: &Lap, turns: Option<&CircuitTurns>, options: &RenderOptions) -> String {
+    let width = options.width as f32;
+    let (projection, track_height) = Projection::fit(lap, turns, width, options.padding);
+    let legend_height = match options.line_color {
+        LineColor::Channel(_) => LEGEND_HEIGHT,
+        _ => 0.0,
+    };
+    let height = track_height + legend_height;
+    let font_size = (width / 70.0).clamp(10.0, 18.0);
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.0}" height="{height:.0}" viewBox="0 0 {width:.0} {height:.0}" font-family="sans-serif" font-size="{font_size:.0}">"#
+    );
+    if let Some(background) = &options.background {
+        let _ = write!(
+            svg,
+            r#"<rect width="100%" height="100%" fill="{background}"/>"#
+        );
+    }
+
+    if options.segments {
+        for turn in turns.map(|t| t.turns.as_slice()).unwrap_or_default() {
+            for (bounds, color) in [&turn.approach, &turn.apex, &turn.exit]
+                .into_iter()
+                .zip(SEGMENT_COLORS)
+            {
+                svg.push_str(&rect(&projection, bounds, color, true));
+            }
+        }
+    }
+
+    let outline = points(lap.samples.iter().map(|s| projection.project(s.position)));
+    let _ = write!(
+        svg,
+        r##"<polyline points="{outline}" fill="none" stroke="#3a3a44" stroke-width="{:.1}" stroke-linejoin="round" stroke-linecap="round"/>"##,
+        options.line_width * 2.0
+    );
+    let stroke = |color: &str| {
+        format!(
+            r#"fill="none" stroke="{color}" stroke-width="{:.1}" stroke-linejoin="round" stroke-linecap="round""#,
+            options.line_width
+        )
+    };
+    match &options.line_color {
+        LineColor::Solid(color) => {
+            let _ = write!(svg, r#"<polyline points="{outline}" {}/>"#, stroke(color));
+        }
+        LineColor::Channel(channel) => {
+            let channels = LapChannels::new(lap);
+            let values = channels.get(*channel);
+            let (low, high) = channel_range(values);
+            for (pair, value) in lap.samples.windows(2).zip(values.windows(2)) {
+                let t = ((value[0] + value[1]) / 2.0 - low) / (high - low);
+                let line = points(pair.iter().map(|s| projection.project(s.position)));
+                let _ = write!(svg, r#"<polyline points="{line}" {}/>"#, stroke(&ramp(t)));
+            }
+            draw_legend(
+                &mut svg,
+                *channel,
+                low,
+                high,
+                track_height,
+                width,
+                options.padding,
+            );
+        }
+        LineColor::Ranges(ranges) => {
+            for range in ranges {
+                let Some(samples) = lap.samples.get(range.start..=range.end) else {
+                    continue;
+                };
+                let line = points(samples.iter().map(|s| projection.project(s.position)));
+                let _ = write!(
+                    svg,
+                    r#"<polyline points="{line}" {}/>"#,
+                    stroke(&range.color)
+                );
+            }
+        }
+    }
+
+    if let Some(start) = lap.samples.first() {
+        let (x, y) = projection.project(start.position);
+        let _ = write!(
+            svg,
+            r##"<circle cx="{x:.1}" cy="{y:.1}" r="{:.1}" fill="#ffffff"/>"##,
+            options.line_width * 1.5
+        );
+    }
+
+    for turn in turns.map(|t| t.turns.as_slice()).unwrap_or_default() {
+        svg.push_str(&rect(&projection, &turn.bounds, TURN_COLOR, false));
+        let (x, y) = projection.point(turn.bounds.min_x, turn.bounds.max_z);
+        let _ = write!(
+            svg,
+            r##"<text x="{:.1}" y="{:.1}" fill="#ffffff" stroke="#15151e" stroke-width="3" paint-order="stroke">{}</text>"##,
+            x + 3.0,
+            y + font_size + 2.0,
+            escape(&turn.name)
+        );
+    }
+
+    for trap in turns.map(|t| t.speed_traps.as_slice()).unwrap_or_default() {
+        let (x1, y1) = projection.point(trap.start.x, trap.start.y);
+        let (x2, y2) = projection.point(trap.end.x, trap.end.y);
+        let _ = write!(
+            svg,
+            r##"<line x1="{x1:.1}" y1="{y1:.1}" x2="{x2:.1}" y2="{y2:.1}" stroke="{TRAP_COLOR}" stroke-width="{:.1}"/><text x="{:.1}" y="{:.1}" fill="{TRAP_COLOR}" stroke="#15151e" stroke-width="3" paint-order="stroke">{}</text>"##,
+            options.line_width,
+            x2 + 3.0,
+            y2,
+            escape(&trap.name)
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Render the track as a PNG image.
+#[cfg(feature = "png")]
+pub fn render_png(
+    lap: &Lap,
+    turns: Option<&CircuitTurns>,
+    options: &RenderOptions,
+) -> Result<Vec<u8>, RenderError> {
+    use resvg::{tiny_skia, usvg};
+
+    let mut usvg_options = usvg::Options::default();
+    let fontdb = usvg_options.fontdb_mut();
+    fontdb.load_system_fonts();
+    // Labels use the generic sans-serif family; point it at whatever is
+    // installed if the default (Arial) isn't
+    let sans_serif = usvg::fontdb::Query {
+        families: &[usvg::fontdb::Family::SansSerif],
+        ..Default::default()
+    };
+    if fontdb.query(&sans_serif).is_none() {
+        let families: Vec<String> = fontdb
+            .faces()
+            .filter_map(|face| Some(face.families.first()?.0.clone()))
+            .collect();
+        let family = families
+            .iter()
+            .find(|f| f.contains("Sans") && !f.contains("Mono"))
+            .or(families.first());
+        if let Some(family) = family.cloned() {
+            fontdb.set_sans_serif_family(family);
+        }
+    }
+
+    let svg = render_svg(lap, turns, options);
+    let tree =
+        usvg::Tree::from_str(&svg, &usvg_options).map_err(|e| RenderError::Png(e.to_string()))?;
+    let size = tree.size().to_int_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| RenderError::Png(format!("invalid image size {size:?}")))?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap
+        .encode_png()
+        .map_err(|e| RenderError::Png(e.to_string()))
+}
+
+/// Render to a file, choosing SVG or PNG from its extension.
+pub fn render_to_file(
+    path: impl AsRef<Path>,
+    lap: &Lap,
+    turns: Option<&CircuitTurns>,
+    options: &RenderOptions,
+) -> Result<(), RenderError> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("svg") => std::fs::write(path, render_svg(lap, turns, options))?,
+        #[cfg(feature = "png")]
+        Some("png") => std::fs::write(path, render_png(lap, turns, options)?)?,
+        #[cfg(not(feature = "png"))]
+        Some("png") => return Err(RenderError::PngUnsupported),
+        _ => return Err(RenderError::UnknownFormat(path.display().to_string())),
+    }
+    Ok(())
+}
+
+fn rect(projection: &Projection, bounds: &crate::BoundingBox, color: &str, filled: bool) -> String {
+    let (x, y) = projection.point(bounds.min_x, bounds.max_z);
+    let width = (bounds.max_x - bounds.min_x) * projection.scale;
+    let height = (bounds.max_z - bounds.min_z) * projection.scale;
+    let paint = if filled {
+        format!(r#"fill="{color}" fill-opacity="0.18" stroke="none""#)
+    } else {
+        format!(r#"fill="none" stroke="{color}" stroke-width="1.5" stroke-dasharray="6 4""#)
+    };
+    format!(r#"<rect x="{x:.1}" y="{y:.1}" width="{width:.1}" height="{height:.1}" {paint}/>"#)
+}
+
+/// Gradient bar with the channel's name and range under the track.
+fn draw_legend(
+    svg: &mut String,
+    channel: Channel,
+    low: f32,
+    high: f32,
+    top: f32,
+    width: f32,
+    padding: f32,
+) {
+    let bar_width = (width - 2.0 * padding).min(320.0);
+    let y = top + 8.0;
+    svg.push_str(r#"<defs><linearGradient id="ramp">"#);
+    for i in 0..RAMP.len() {
+        let t = i as f32 / (RAMP.len() - 1) as f32;
+        let _ = write!(svg, r#"<stop offset="{t:.2}" stop-color="{}"/>"#, ramp(t));
+    }
+    let unit = match channel.unit() {
+        "" => String::new(),
+        unit => format!(" ({unit})"),
+    };
+    let decimals = match channel {
+        Channel::Curvature => 4,
+        _ if channel.is_derived() && high - low < 10.0 => 2,
+        _ => 0,
+    };
+    let _ = write!(
+        svg,
+        r##"</linearGradient></defs><rect x="{padding:.1}" y="{y:.1}" width="{bar_width:.1}" height="12" fill="url(#ramp)"/><text x="{padding:.1}" y="{:.1}" fill="#c8c8d0">{low:.decimals$}</text><text x="{:.1}" y="{:.1}" fill="#c8c8d0" text-anchor="end">{high:.decimals$}</text><text x="{:.1}" y="{:.1}" fill="#c8c8d0">{}{unit}</text>"##,
+        y + 30.0,
+        padding + bar_width,
+        y + 30.0,
+        padding + bar_width + 12.0,
+        y + 11.0,
+        channel.name(),
+    );
+}
+
+/// Color range for a channel: min to max, or symmetric around zero for
+/// values of both signs.
+fn channel_range(values: &[f32]) -> (f32, f32) {
+    let low = values.iter().copied().fold(f32::INFINITY, f32::min);
+    let high = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if !low.is_finite() {
+        return (0.0, 1.0);
+    }
+    let (low, high) = if low < 0.0 && high > 0.0 {
+        let extent = low.abs().max(high);
+        (-extent, extent)
+    } else {
+        (low, high)
+    };
+    if (high - low).abs() < f32::EPSILON {
+        (low - 1.0, high + 1.0)
+    } else {
+        (low, high)
+    }
+}
+
+/// Color at `t` (0..=1) along the ramp.
+fn ramp(t: f32) -> String {
+    let position = t.clamp(0.0, 1.0) * (RAMP.len() - 1) as f32;
+    let i = (position.floor() as usize).min(RAMP.len() - 2);
+    let f = position - i as f32;
+    let [r, g, b] = [0, 1, 2].map(|c| {
+        let (a, b) = (RAMP[i][c] as f32, RAMP[i + 1][c] as f32);
+        (a + (b - a) * f).round() as u8
+    });
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn points(points: impl Iterator<Item = (f32, f32)>) -> String {
+    let mut out = String::new();
+    for (x, y) in points {
+        let _ = write!(out, "{x:.1},{y:.1} ");
+    }
+    out
+}
+
+/// Escape text for SVG/HTML content and attribute values.
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoundingBox, DrsStatus, SpeedTrap, TelemetrySample, Turn};
+    use glam::Vec2;
+
+    /// A lap around a 100 m circle, speeding up as it goes.
+    fn circle_lap() -> Lap {
+        let samples = (0..=40)
+            .map(|i| {
+                let angle = i as f32 / 40.0 * std::f32::consts::TAU;
+                TelemetrySample {
+                    position: Vec3::new(100.0 * angle.cos(), 0.0, 100.0 * angle.sin()),
+                    time: i as f64 * 0.5,
+                    throttle: 100,
+                    brake: 0,
+                    gear: 6,
+                    speed: 100 + i as u16,
+                    drs: DrsStatus::Off,
+                }
+            })
+            .collect();
+        Lap {
+            number: 1,
+            lap_time: Some(20.0),
+            sector_times: [None; 3],
+            samples,
+            is_valid: true,
+        }
+    }
+
+    fn turns() -> CircuitTurns {
+        CircuitTurns {
+            circuit: "Test".into(),
+            turns: vec![Turn {
+                name: "T1 <Hairpin>".into(),
+                bounds: BoundingBox::new(50.0, 150.0, -20.0, 60.0),
+                approach: BoundingBox::new(90.0, 150.0, -20.0, 10.0),
+                apex: BoundingBox::new(80.0, 120.0, 10.0, 40.0),
+                exit: BoundingBox::new(50.0, 90.0, 40.0, 60.0),
+            }],
+            speed_traps: vec![SpeedTrap {
+                name: "ST".into(),
+                start: Vec2::new(-110.0, 0.0),
+                end: Vec2::new(-90.0, 0.0),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let lap = circle_lap();
+        let options = RenderOptions {
+            width: 400,
+            line_color: LineColor::Channel(Channel::Speed),
+            ..RenderOptions::default()
+        };
+        let svg = render_svg(&lap, Some(&turns()), &options);
+
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert!(svg.contains("T1 &lt;Hairpin&gt;"));
+        assert_eq!(svg.matches("<line ").count(), 1);
+        assert!(svg.contains(">ST</text>"));
+        // Turn bounds plus three segment boxes, background and legend bar
+        assert_eq!(svg.matches("<rect").count(), 6);
+        // Outline plus one colored piece per pair of samples
+        assert_eq!(svg.matches("<polyline").count(), 41);
+        // Colored by the mean speed of each piece
+        let piece = |mean: f32| format!("stroke=\"{}\"", ramp((mean - 100.0) / 40.0));
+        assert!(svg.contains(&piece(100.5)) && svg.contains(&piece(139.5)));
+        assert!(svg.contains("Speed (km/h)"));
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_render_png() {
+        let options = RenderOptions {
+            width: 200,
+            ..RenderOptions::default()
+        };
+        let png = render_png(&circle_lap(), Some(&turns()), &options).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+}
@@ -0,0 +1,626 @@
+//! Self-contained HTML session reports.
+//!
+//! `session_report` renders a session as one offline HTML file: the
+//! fastest-lap ranking, a track map with turns and mini-sectors, speed and
+//! delta traces, per-turn comparisons and every driver's lap table. Styles
+//! and charts (SVG) are inline, so the file opens on a phone without a
+//! network connection.
+//!
+//! Times follow the timing screens: purple is the best in the session (or
+//! the comparison), green a driver's personal best.
+
+mod svg;
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+
+use crate::analysis::{MiniSectorComparison, SegmentTime, TurnComparison};
+use crate::classify::{self, ClassifyConfig, LapClass, LapClasses};
+use crate::format;
+use crate::render::{self, escape, ColoredRange, LineColor, RenderOptions};
+use crate::{
+    Channel, CircuitTurns, DeltaTrace, Driver, DriverSession, Lap, LapChannels, LapTrace, Session,
+    TurnSegment,
+};
+use svg::Series;
+
+/// What goes into a report.
+#[derive(Debug, Clone)]
+pub struct ReportConfig {
+    /// Driver codes to include (all drivers if empty)
+    pub drivers: Vec<String>,
+    /// Number of equal-distance mini-sectors on the track map
+    pub mini_sectors: usize,
+    /// How many of the fastest drivers get speed and delta traces
+    pub trace_drivers: usize,
+    /// Spacing of trace points (m)
+    pub trace_step: f32,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self {
+            drivers: Vec::new(),
+            mini_sectors: 25,
+            trace_drivers: 4,
+            trace_step: 10.0,
+        }
+    }
+}
+
+const STYLE: &str = "\
+body{margin:0;background:#15151e;color:#e8e8ee;font:14px/1.4 system-ui,sans-serif}\
+main{max-width:720px;margin:0 auto;padding:12px}\
+h1{font-size:22px;margin:8px 0 0}h2{font-size:17px;margin:28px 0 8px;border-bottom:1px solid #33333f}\
+h3{font-size:15px;margin:18px 0 6px}.meta,.note{color:#9a9aa8}\
+.scroll{overflow-x:auto}table{border-collapse:collapse;width:100%;font-variant-numeric:tabular-nums}\
+th,td{padding:4px 6px;text-align:right;white-space:nowrap}th{color:#9a9aa8;font-weight:500}\
+th:first-child,td:first-child{text-align:left}tr:nth-child(even) td{background:#1d1d28}\
+.purple{color:#c77dff;font-weight:700}.green{color:#4ade80}.dim td{color:#6a6a78}\
+.swatch{display:inline-block;width:10px;height:10px;border-radius:2px;margin-right:6px}\
+.legend span{margin-right:12px;white-space:nowrap}details{margin:6px 0}summary{cursor:pointer;padding:4px 0}\
+svg{display:block;margin:8px 0;width:100%;height:auto}svg .tick{fill:#c8c8d0;font-size:11px}svg .grid{stroke:#2a2a36}";
+
+/// Render a session (and optionally its turn definitions) as an HTML page.
+pub fn session_report(
+    session: &Session,
+    turns: Option<&CircuitTurns>,
+    config: &ReportConfig,
+) -> String {
+    let drivers: Vec<&DriverSession> = session
+        .drivers
+        .iter()
+        .filter(|d| {
+            config.drivers.is_empty()
+                || config
+                    .drivers
+                    .iter()
+                    .any(|code| code.eq_ignore_ascii_case(&d.driver.code))
+        })
+        .collect();
+    let styles = DriverStyles::new(&drivers);
+
+    let mut fastest: Vec<(&Driver, &Lap)> = drivers
+        .iter()
+        .filter_map(|d| Some((&d.driver, d.fastest_lap()?)))
+        .collect();
+    fastest.sort_by(|a, b| {
+        let time_a = a.1.lap_time.unwrap_or(f64::MAX);
+        let time_b = b.1.lap_time.unwrap_or(f64::MAX);
+        time_a
+            .partial_cmp(&time_b)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let fastest_by_code: Vec<(&str, &Lap)> = fastest
+        .iter()
+        .map(|(driver, lap)| (driver.code.as_str(), *lap))
+        .collect();
+
+    let title = session.label();
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\"><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <title>{}</title><style>{STYLE}</style></head><body><main>\
+         <h1>{}</h1><p class=\"meta\">{} · {}</p>",
+        escape(&title),
+        escape(&title),
+        session.date.format("%Y-%m-%d %H:%M UTC"),
+        pluralize(drivers.len(), "driver"),
+    );
+
+    let bests = TimingBests::new(&drivers);
+    ranking_section(&mut html, &fastest, &bests, &styles);
+    if let Some((_, reference)) = fastest_by_code.first() {
+        track_section(
+            &mut html,
+            reference,
+            &fastest_by_code,
+            turns,
+            config,
+            &styles,
+        );
+        trace_section(&mut html, &fastest_by_code, config, &styles);
+    }
+    if let Some(turns) = turns {
+        turn_section(&mut html, turns, &fastest_by_code);
+    }
+    let classes = classify::classify_session(session, &ClassifyConfig::default());
+    lap_section(&mut html, &drivers, &bests, &classes, &styles);
+
+    html.push_str("</main></body></html>\n");
+    html
+}
+
+/// Render a report and write it to a file.
+pub fn write_session_report(
+    path: impl AsRef<Path>,
+    session: &Session,
+    turns: Option<&CircuitTurns>,
+    config: &ReportConfig,
+) -> io::Result<()> {
+    std::fs::write(path, session_report(session, turns, config))
+}
+
+fn ranking_section(
+    html: &mut String,
+    fastest: &[(&Driver, &Lap)],
+    bests: &TimingBests,
+    styles: &DriverStyles,
+) {
+    html.push_str(
+        "<h2>Fastest laps</h2><div class=\"scroll\"><table><tr><th>Driver</th><th>Lap</th>\
+         <th>Time</th><th>Gap</th><th>S1</th><th>S2</th><th>S3</th></tr>",
+    );
+    let leader = fastest.first().and_then(|(_, lap)| lap.lap_time);
+    for (i, (driver, lap)) in fastest.iter().enumerate() {
+        let gap = match (lap.lap_time, leader) {
+            (Some(time), Some(leader)) if i > 0 => format!("+{:.3}", time - leader),
+            _ => String::new(),
+        };
+        let _ = write!(
+            html,
+            "<tr><td>{}. {}</td><td>{}</td>{}<td>{gap}</td>",
+            i + 1,
+            styles.label(&driver.code),
+            lap.number,
+            bests.lap_cell(&driver.code, lap.lap_time, lap_time),
+        );
+        for (sector, time) in lap.sector_times.iter().enumerate() {
+            html.push_str(&bests.sector_cell(&driver.code, sector, *time));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table></div>");
+}
+
+fn track_section(
+    html: &mut String,
+    reference: &Lap,
+    laps: &[(&str, &Lap)],
+    turns: Option<&CircuitTurns>,
+    config: &ReportConfig,
+    styles: &DriverStyles,
+) {
+    let count = config.mini_sectors.max(1);
+    let minis = MiniSectorComparison::compare(laps, count);
+
+    // Cut the reference lap where each mini-sector starts
+    let channels = LapChannels::new(reference);
+    let distance = channels.get(Channel::Distance);
+    let length = distance.last().copied().unwrap_or(0.0).max(f32::EPSILON);
+    let mini_sector_of = |i: usize| ((distance[i] / length * count as f32) as usize).min(count - 1);
+    let mut segments: Vec<ColoredRange> = Vec::new();
+    for i in 0..reference.samples.len() {
+        let mini = mini_sector_of(i);
+        match segments.last_mut() {
+            Some(segment) if mini_sector_of(segment.start) == mini => segment.end = i,
+            _ => {
+                if let Some(previous) = segments.last_mut() {
+                    // Join up with the previous stretch
+                    previous.end = i;
+                }
+                let color = minis.fastest_driver(mini).map_or_else(
+                    || "#ffffff".to_string(),
+                    |code| styles.color(code).to_string(),
+                );
+                segments.push(ColoredRange {
+                    start: i,
+                    end: i,
+                    color,
+                });
+            }
+        }
+    }
+
+    let _ = write!(
+        html,
+        "<h2>Track</h2><p class=\"note\">{} mini-sectors coloured by the fastest driver \
+         (fastest laps compared); dashed boxes are turns.</p>",
+        count
+    );
+    let options = RenderOptions {
+        width: 640,
+        padding: 24.0,
+        line_width: 5.0,
+        line_color: LineColor::Ranges(segments),
+        segments: false,
+        background: None,
+    };
+    html.push_str(&render::render_svg(reference, turns, &options));
+
+    html.push_str("<p class=\"legend\">");
+    for (index, code) in minis.driver_codes.iter().enumerate() {
+        let won = minis.fastest.iter().filter(|&&f| f == index).count();
+        if won > 0 {
+            let _ = write!(html, "<span>{} {won}</span>", styles.label(code));
+        }
+    }
+    html.push_str("</p>");
+}
+
+fn trace_section(
+    html: &mut String,
+    laps: &[(&str, &Lap)],
+    config: &ReportConfig,
+    styles: &DriverStyles,
+) {
+    let traces: Vec<(&str, LapTrace)> = laps
+        .iter()
+        .take(config.trace_drivers)
+        .filter_map(|(code, lap)| Some((*code, LapTrace::new(lap)?)))
+        .collect();
+    let Some((reference_code, reference)) = traces.first() else {
+        return;
+    };
+
+    let deltas: Vec<DeltaTrace> = traces
+        .iter()
+        .map(|(code, trace)| {
+            DeltaTrace::new(code, trace, reference_code, reference, config.trace_step)
+        })
+        .collect();
+    let series = |value: fn(&crate::trace::DeltaPoint) -> f32| -> Vec<Series> {
+        deltas
+            .iter()
+            .map(|delta| Series {
+                name: delta.driver_code.clone(),
+                color: styles.color(&delta.driver_code).to_string(),
+                dashed: styles.dashed(&delta.driver_code),
+                points: delta
+                    .points
+                    .iter()
+                    .map(|p| (p.distance, value(p)))
+                    .collect(),
+            })
+            .collect()
+    };
+
+    html.push_str("<h2>Speed traces</h2><p class=\"legend\">");
+    for delta in &deltas {
+        let _ = write!(html, "<span>{}</span>", styles.label(&delta.driver_code));
+    }
+    html.push_str("</p>");
+    html.push_str(&svg::line_chart(
+        &series(|p| p.speed as f32),
+        "distance (m)",
+        "speed (km/h)",
+    ));
+    html.push_str(&svg::line_chart(
+        &series(|p| p.delta as f32),
+        "distance (m)",
+        &format!("gap to {reference_code} (s)"),
+    ));
+}
+
+fn turn_section(html: &mut String, turns: &CircuitTurns, laps: &[(&str, &Lap)]) {
+    html.push_str("<h2>Turns</h2>");
+    for turn in &turns.turns {
+        let comparison = TurnComparison::compare_drivers(laps, turn);
+        let _ = write!(html, "<h3>{}</h3>", escape(&turn.name));
+        if comparison.analyses.is_empty() {
+            html.push_str("<p class=\"note\">No lap passes through this turn.</p>");
+            continue;
+        }
+        if !comparison.uniform_fastest {
+            html.push_str(
+                "<p class=\"note\">The fastest driver through the turn isn't fastest in \
+                 every segment.</p>",
+            );
+        }
+
+        html.push_str(
+            "<div class=\"scroll\"><table><tr><th>Driver</th><th>Turn</th><th>Gap</th>\
+             <th>Approach</th><th>Apex</th><th>Exit</th><th>Entry</th><th>Min</th>\
+             <th>Exit km/h</th></tr>",
+        );
+        let leader = comparison.analyses[0].total_time.as_ref().map(|t| t.time);
+        for (i, analysis) in comparison.analyses.iter().enumerate() {
+            let total = analysis.total_time.as_ref();
+            let gap = match (total, leader) {
+                (Some(t), Some(leader)) if i > 0 => format!("+{:.3}", t.time - leader),
+                _ => String::new(),
+            };
+            let _ = write!(
+                html,
+                "<tr><td>{}</td>{}<td>{gap}</td>",
+                escape(analysis.driver_code.as_deref().unwrap_or_default()),
+                cell(total.map(|t| t.time), i == 0, seconds)
+            );
+            for segment in [TurnSegment::Approach, TurnSegment::Apex, TurnSegment::Exit] {
+                let is_best = comparison
+                    .segment_best(segment)
+                    .is_some_and(|best| std::ptr::eq(best, analysis));
+                let time = analysis.segment(segment).map(|s| s.time);
+                html.push_str(&cell(time, is_best, seconds));
+            }
+            let speed = |get: fn(&SegmentTime) -> u16| {
+                total.map_or_else(String::new, |t| get(t).to_string())
+            };
+            let _ = write!(
+                html,
+                "<td>{}</td><td>{}</td><td>{}</td></tr>",
+                speed(|t| t.entry_speed),
+                speed(|t| t.min_speed),
+                speed(|t| t.exit_speed)
+            );
+        }
+        html.push_str("</table></div>");
+    }
+}
+
+fn lap_section(
+    html: &mut String,
+    drivers: &[&DriverSession],
+    bests: &TimingBests,
+    classes: &LapClasses,
+    styles: &DriverStyles,
+) {
+    html.push_str("<h2>Laps</h2>");
+    for driver in drivers {
+        let code = &driver.driver.code;
+        let _ = write!(
+            html,
+            "<details><summary>{} {} · {}</summary><div class=\"scroll\"><table>\
+             <tr><th>Lap</th><th>Time</th><th>S1</th><th>S2</th><th>S3</th><th></th></tr>",
+            styles.label(code),
+            escape(&driver.driver.name),
+            escape(&driver.driver.team),
+        );
+        for lap in &driver.laps {
+            // Push laps are left unlabelled
+            let class = classes
+                .get(code, lap.number)
+                .filter(|c| *c != LapClass::Push)
+                .map_or("", |c| c.as_str());
+            // Deleted laps don't count for bests
+            if lap.is_valid {
+                let _ = write!(
+                    html,
+                    "<tr><td>{}</td>{}",
+                    lap.number,
+                    bests.lap_cell(code, lap.lap_time, lap_time)
+                );
+                for (sector, time) in lap.sector_times.iter().enumerate() {
+                    html.push_str(&bests.sector_cell(code, sector, *time));
+                }
+                let _ = write!(html, "<td>{class}</td></tr>");
+            } else {
+                let _ = write!(
+                    html,
+                    "<tr class=\"dim\"><td>{}</td>{}",
+                    lap.number,
+                    cell(lap.lap_time, false, lap_time)
+                );
+                for time in lap.sector_times {
+                    html.push_str(&cell(time, false, seconds));
+                }
+                html.push_str("<td>deleted</td></tr>");
+            }
+        }
+        html.push_str("</table></div></details>");
+    }
+}
+
+/// Session and personal bests over valid laps.
+struct TimingBests {
+    lap: Option<f64>,
+    sectors: [Option<f64>; 3],
+    personal: HashMap<String, (Option<f64>, [Option<f64>; 3])>,
+}
+
+impl TimingBests {
+    fn new(drivers: &[&DriverSession]) -> Self {
+        let mut bests = Self {
+            lap: None,
+            sectors: [None; 3],
+            personal: HashMap::new(),
+        };
+        for driver in drivers {
+            let mut lap_best = None;
+            let mut sector_bests = [None; 3];
+            for lap in driver.laps.iter().filter(|l| l.is_valid) {
+                lap_best = min(lap_best, lap.lap_time);
+                for (best, time) in sector_bests.iter_mut().zip(lap.sector_times) {
+                    *best = min(*best, time);
+                }
+            }
+            bests.lap = min(bests.lap, lap_best);
+            for (best, personal) in bests.sectors.iter_mut().zip(sector_bests) {
+                *best = min(*best, personal);
+            }
+            bests
+                .personal
+                .insert(driver.driver.code.clone(), (lap_best, sector_bests));
+        }
+        bests
+    }
+
+    fn lap_cell(&self, code: &str, time: Option<f64>, format: fn(Option<f64>) -> String) -> String {
+        let personal = self.personal.get(code).and_then(|p| p.0);
+        timing_cell(time, self.lap, personal, format)
+    }
+
+    fn sector_cell(&self, code: &str, sector: usize, time: Option<f64>) -> String {
+        let personal = self.personal.get(code).and_then(|p| p.1[sector]);
+        timing_cell(time, self.sectors[sector], personal, seconds)
+    }
+}
+
+fn min(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// A table cell, purple for the overall best and green for a personal best.
+fn timing_cell(
+    time: Option<f64>,
+    best: Option<f64>,
+    personal: Option<f64>,
+    format: fn(Option<f64>) -> String,
+) -> String {
+    let matches = |other: Option<f64>| time.zip(other).is_some_and(|(a, b)| (a - b).abs() < 1e-6);
+    let class = if matches(best) {
+        " class=\"purple\""
+    } else if matches(personal) {
+        " class=\"green\""
+    } else {
+        ""
+    };
+    format!("<td{class}>{}</td>", format(time))
+}
+
+fn cell(time: Option<f64>, is_best: bool, format: fn(Option<f64>) -> String) -> String {
+    let class = if is_best { " class=\"purple\"" } else { "" };
+    format!("<td{class}>{}</td>", format(time))
+}
+
+/// Team color and line style per driver; the second driver of a team gets a
+/// dashed line so teammates can be told apart.
+struct DriverStyles {
+    styles: HashMap<String, (String, bool)>,
+}
+
+impl DriverStyles {
+    fn new(drivers: &[&DriverSession]) -> Self {
+        let mut styles = HashMap::new();
+        let mut used = Vec::new();
+        for driver in drivers {
+            let color = css_color(&driver.driver.team_color);
+            let dashed = used.contains(&color);
+            used.push(color.clone());
+            styles.insert(driver.driver.code.clone(), (color, dashed));
+        }
+        Self { styles }
+    }
+
+    fn color(&self, code: &str) -> &str {
+        self.styles.get(code).map_or("#ffffff", |(color, _)| color)
+    }
+
+    fn dashed(&self, code: &str) -> bool {
+        self.styles.get(code).is_some_and(|(_, dashed)| *dashed)
+    }
+
+    /// Driver code with a team color swatch.
+    fn label(&self, code: &str) -> String {
+        format!(
+            "<span class=\"swatch\" style=\"background:{}\"></span>{}",
+            self.color(code),
+            escape(code)
+        )
+    }
+}
+
+/// A `#rgb`/`#rrggbb` team color, or white if it isn't one.
+fn css_color(hex: &str) -> String {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    if matches!(digits.len(), 3 | 6) && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        format!("#{digits}")
+    } else {
+        "#ffffff".to_string()
+    }
+}
+
+/// `m:ss.sss`, or empty.
+fn lap_time(time: Option<f64>) -> String {
+    time.map_or_else(String::new, format::lap_time)
+}
+
+/// `ss.sss`, or empty.
+fn seconds(time: Option<f64>) -> String {
+    time.map_or_else(String::new, |t| format!("{t:.3}"))
+}
+
+fn pluralize(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoundingBox, DrsStatus, SessionType, TelemetrySample, Turn};
+    use chrono::{TimeZone, Utc};
+    use glam::Vec3;
+
+    /// A lap around a 100 m circle taking `duration` seconds.
+    fn driver(code: &str, name: &str, duration: f64) -> DriverSession {
+        let samples = (0..=40)
+            .map(|i| {
+                let angle = i as f32 / 40.0 * std::f32::consts::TAU;
+                TelemetrySample {
+                    position: Vec3::new(100.0 * angle.cos(), 0.0, 100.0 * angle.sin()),
+                    time: duration * i as f64 / 40.0,
+                    throttle: 100,
+                    brake: 0,
+                    gear: 6,
+                    speed: 200,
+                    drs: DrsStatus::Off,
+                }
+            })
+            .collect();
+        DriverSession {
+            driver: Driver {
+                code: code.into(),
+                name: name.into(),
+                number: 0,
+                team: "Team".into(),
+                team_color: "#3671C6".into(),
+            },
+            laps: vec![Lap {
+                number: 1,
+                lap_time: Some(duration),
+                sector_times: [Some(duration / 3.0); 3],
+                samples,
+                is_valid: true,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_session_report() {
+        let session = Session {
+            schema_version: crate::loader::CURRENT_SCHEMA_VERSION,
+            year: 2025,
+            circuit: "Austin".into(),
+            circuit_short: "austin".into(),
+            session_type: SessionType::Qualifying,
+            date: Utc.with_ymd_and_hms(2025, 10, 18, 21, 0, 0).unwrap(),
+            drivers: vec![
+                driver("NOR", "Lando <Norris>", 31.0),
+                driver("VER", "Max Verstappen", 30.0),
+            ],
+        };
+        let turns = CircuitTurns {
+            circuit: "Austin".into(),
+            turns: vec![Turn {
+                name: "T1".into(),
+                bounds: BoundingBox::new(0.0, 110.0, 0.0, 110.0),
+                approach: BoundingBox::new(50.0, 110.0, 0.0, 50.0),
+                apex: BoundingBox::new(50.0, 110.0, 50.0, 110.0),
+                exit: BoundingBox::new(0.0, 50.0, 50.0, 110.0),
+            }],
+            speed_traps: Vec::new(),
+        };
+
+        let html = session_report(&session, Some(&turns), &ReportConfig::default());
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>2025 Austin Q</title>"));
+        assert_eq!(html.matches("<svg").count(), 3);
+        assert!(html.contains("<h3>T1</h3>"));
+        assert!(html.contains("<td class=\"purple\">0:30.000</td>"));
+        assert!(html.contains("Lando &lt;Norris&gt;"));
+        // Teammates share a color, so the second is dashed
+        assert!(html.contains("stroke-dasharray=\"6 3\""));
+    }
+}
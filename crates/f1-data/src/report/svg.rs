@@ -0,0 +1,129 @@
+//! Inline SVG line charts for reports.
+
+use std::fmt::Write;
+
+use crate::render::escape;
+
+/// Width of every chart in SVG units.
+const WIDTH: f32 = 640.0;
+const PADDING: f32 = 24.0;
+
+/// One line in a chart.
+pub(super) struct Series {
+    pub name: String,
+    pub color: String,
+    pub dashed: bool,
+    pub points: Vec<(f32, f32)>,
+}
+
+/// Line chart of several series sharing both axes.
+pub(super) fn line_chart(series: &[Series], x_label: &str, y_label: &str) -> String {
+    const HEIGHT: f32 = 260.0;
+    const LEFT: f32 = 48.0;
+    const BOTTOM: f32 = 32.0;
+
+    let all = || series.iter().flat_map(|s| s.points.iter());
+    let (mut min_x, mut max_x) = (f32::INFINITY, f32::NEG_INFINITY);
+    let (mut min_y, mut max_y) = (f32::INFINITY, f32::NEG_INFINITY);
+    for &(x, y) in all() {
+        min_x = min_x.min(x);
+        max_x = max_x.max(x);
+        min_y = min_y.min(y);
+        max_y = max_y.max(y);
+    }
+    let mut svg = open(HEIGHT);
+    if !min_x.is_finite() {
+        svg.push_str("</svg>");
+        return svg;
+    }
+    if (max_y - min_y).abs() < f32::EPSILON {
+        (min_y, max_y) = (min_y - 1.0, max_y + 1.0);
+    }
+
+    let plot_width = WIDTH - LEFT - PADDING;
+    let plot_height = HEIGHT - BOTTOM - PADDING;
+    let sx = |x: f32| LEFT + (x - min_x) / (max_x - min_x).max(f32::EPSILON) * plot_width;
+    let sy = |y: f32| PADDING + (max_y - y) / (max_y - min_y) * plot_height;
+
+    for (tick, label) in ticks(min_y, max_y) {
+        let y = sy(tick);
+        let _ = write!(
+            svg,
+            r#"<line x1="{LEFT}" y1="{y:.1}" x2="{:.1}" y2="{y:.1}" class="grid"/><text x="{:.1}" y="{:.1}" class="tick" text-anchor="end">{label}</text>"#,
+            WIDTH - PADDING,
+            LEFT - 4.0,
+            y + 4.0
+        );
+    }
+    for (tick, label) in ticks(min_x, max_x) {
+        let x = sx(tick);
+        let _ = write!(
+            svg,
+            r#"<text x="{x:.1}" y="{:.1}" class="tick" text-anchor="middle">{label}</text>"#,
+            HEIGHT - BOTTOM + 14.0
+        );
+    }
+    let _ = write!(
+        svg,
+        r#"<text x="{:.1}" y="{:.1}" class="tick" text-anchor="end">{}</text><text x="4" y="14" class="tick">{}</text>"#,
+        WIDTH - PADDING,
+        HEIGHT - 4.0,
+        escape(x_label),
+        escape(y_label)
+    );
+
+    for s in series {
+        let line = points(s.points.iter().map(|&(x, y)| (sx(x), sy(y))));
+        let dash = if s.dashed {
+            r#" stroke-dasharray="6 3""#
+        } else {
+            ""
+        };
+        let _ = write!(
+            svg,
+            r#"<polyline points="{line}" fill="none" stroke="{}" stroke-width="1.5"{dash}><title>{}</title></polyline>"#,
+            s.color,
+            escape(&s.name)
+        );
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+fn open(height: f32) -> String {
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {WIDTH} {height:.0}" width="100%">"#
+    )
+}
+
+fn points(points: impl Iterator<Item = (f32, f32)>) -> String {
+    let mut out = String::new();
+    for (x, y) in points {
+        let _ = write!(out, "{x:.1},{y:.1} ");
+    }
+    out
+}
+
+/// Round tick values (1, 2 or 5 × 10ⁿ apart) covering `min..=max`, with labels.
+fn ticks(min: f32, max: f32) -> Vec<(f32, String)> {
+    let raw = (max - min) / 5.0;
+    if raw <= 0.0 || !raw.is_finite() {
+        return vec![(min, format!("{min}"))];
+    }
+    let magnitude = 10f32.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|step| step >= &raw)
+        .unwrap_or(10.0 * magnitude);
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last)
+        .map(|i| {
+            let tick = i as f32 * step;
+            (tick, format!("{tick:.decimals$}"))
+        })
+        .collect()
+}
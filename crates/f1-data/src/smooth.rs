@@ -0,0 +1,399 @@
+//! Resampling and smoothing of lap telemetry.
+//!
+//! Raw position and speed channels are noisy enough that per-lap minimum
+//! speeds jitter by several km/h. The functions here produce cleaned copies
+//! of a `Lap` (same number, times and validity) that can be passed to any
+//! analysis in place of the original, so smoothing is always opt-in.
+//!
+//! The filters assume roughly even sample spacing; resample first
+//! (`resample_by_time` or `resample_by_distance`) if the source isn't.
+
+use crate::{Lap, LapTrace, TelemetrySample};
+
+/// A smoothing filter applied to a lap's position and speed channels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    /// Centered moving average over `window` samples
+    MovingAverage { window: usize },
+    /// Savitzky–Golay: local least-squares polynomial of `order` over
+    /// `window` samples. Keeps peaks (e.g. minimum speeds) sharper than a
+    /// moving average of the same width.
+    SavitzkyGolay { window: usize, order: usize },
+    /// Constant-velocity Kalman filter with a backward (RTS) smoothing pass
+    Kalman(KalmanConfig),
+}
+
+/// Noise parameters for `Filter::Kalman`, as standard deviations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KalmanConfig {
+    /// Position measurement noise (meters)
+    pub position_noise: f64,
+    /// Unmodelled acceleration of the car (m/s²)
+    pub position_process: f64,
+    /// Speed measurement noise (km/h)
+    pub speed_noise: f64,
+    /// Unmodelled rate of change of acceleration (km/h per s²)
+    pub speed_process: f64,
+}
+
+impl Default for KalmanConfig {
+    fn default() -> Self {
+        Self {
+            position_noise: 2.0,
+            position_process: 30.0,
+            speed_noise: 3.0,
+            speed_process: 150.0,
+        }
+    }
+}
+
+/// Resample a lap at a fixed time `interval` (seconds) from its first sample.
+///
+/// Laps with fewer than two samples are returned unchanged.
+pub fn resample_by_time(lap: &Lap, interval: f64) -> Lap {
+    let Some(trace) = LapTrace::new(lap).filter(|_| interval > 0.0) else {
+        return lap.clone();
+    };
+    let count = (trace.duration() / interval).floor() as usize + 1;
+    let samples = (0..count)
+        .map(|i| trace.sample_at(i as f64 * interval))
+        .collect();
+    with_samples(lap, samples)
+}
+
+/// Resample a lap every `step` meters of distance driven.
+///
+/// Laps with fewer than two samples are returned unchanged.
+pub fn resample_by_distance(lap: &Lap, step: f32) -> Lap {
+    let Some(trace) = LapTrace::new(lap).filter(|_| step > 0.0) else {
+        return lap.clone();
+    };
+    let count = (trace.length() / step).floor() as usize + 1;
+    let samples = (0..count)
+        .map(|i| trace.sample_at(trace.time_at_distance(i as f32 * step)))
+        .collect();
+    with_samples(lap, samples)
+}
+
+/// Smooth a lap's position (X/Y/Z) and speed channels with `filter`.
+///
+/// Time, pedals, gear and DRS are left untouched.
+pub fn smooth_lap(lap: &Lap, filter: &Filter) -> Lap {
+    let samples = &lap.samples;
+    let times: Vec<f64> = samples.iter().map(|s| s.time).collect();
+    let channel = |get: fn(&TelemetrySample) -> f64, noise: fn(&KalmanConfig) -> (f64, f64)| {
+        let values: Vec<f64> = samples.iter().map(get).collect();
+        match filter {
+            Filter::MovingAverage { window } => moving_average(&values, *window),
+            Filter::SavitzkyGolay { window, order } => savitzky_golay(&values, *window, *order),
+            Filter::Kalman(config) => {
+                let (measurement, process) = noise(config);
+                kalman(&times, &values, measurement, process)
+            }
+        }
+    };
+    let position_noise = |c: &KalmanConfig| (c.position_noise, c.position_process);
+
+    let x = channel(|s| s.position.x as f64, position_noise);
+    let y = channel(|s| s.position.y as f64, position_noise);
+    let z = channel(|s| s.position.z as f64, position_noise);
+    let speed = channel(|s| s.speed as f64, |c| (c.speed_noise, c.speed_process));
+
+    let samples = samples
+        .iter()
+        .enumerate()
+        .map(|(i, s)| TelemetrySample {
+            position: glam::Vec3::new(x[i] as f32, y[i] as f32, z[i] as f32),
+            speed: speed[i].round().clamp(0.0, u16::MAX as f64) as u16,
+            ..s.clone()
+        })
+        .collect();
+    with_samples(lap, samples)
+}
+
+/// Centered moving average. Near the ends the window shrinks to the samples
+/// available.
+pub fn moving_average(values: &[f64], window: usize) -> Vec<f64> {
+    let half = window / 2;
+    (0..values.len())
+        .map(|i| {
+            let range = &values[i.saturating_sub(half)..(i + half + 1).min(values.len())];
+            range.iter().sum::<f64>() / range.len() as f64
+        })
+        .collect()
+}
+
+/// Savitzky–Golay smoothing with an odd `window` and polynomial `order`.
+///
+/// Samples within half a window of either end are evaluated on the nearest
+/// full window instead of being padded. Returns the input unchanged if there
+/// are too few samples to fit the polynomial.
+pub fn savitzky_golay(values: &[f64], window: usize, order: usize) -> Vec<f64> {
+    let window = (window.min(values.len()).max(1) - 1) | 1;
+    if window <= order || values.len() < window {
+        return values.to_vec();
+    }
+    let half = window / 2;
+    let centered = savitzky_golay_weights(-(half as i64), window, order);
+
+    (0..values.len())
+        .map(|i| {
+            let start = i.saturating_sub(half).min(values.len() - window);
+            let weights = if start + half == i {
+                centered.clone()
+            } else {
+                savitzky_golay_weights(start as i64 - i as i64, window, order)
+            };
+            weights
+                .iter()
+                .zip(&values[start..start + window])
+                .map(|(w, v)| w * v)
+                .sum()
+        })
+        .collect()
+}
+
+/// Weights that evaluate a least-squares polynomial of `order`, fitted to
+/// `window` samples at offsets `first, first + 1, ..`, at offset 0.
+fn savitzky_golay_weights(first: i64, window: usize, order: usize) -> Vec<f64> {
+    let n = order + 1;
+    let rows: Vec<Vec<f64>> = (0..window)
+        .map(|k| {
+            let x = (first + k as i64) as f64;
+            (0..n).map(|p| x.powi(p as i32)).collect()
+        })
+        .collect();
+
+    // Solve (JᵀJ) z = e₀; the weights are J z.
+    let mut normal = vec![vec![0.0; n + 1]; n];
+    for (r, row) in normal.iter_mut().enumerate() {
+        for (c, cell) in row.iter_mut().take(n).enumerate() {
+            *cell = rows.iter().map(|j| j[r] * j[c]).sum();
+        }
+        row[n] = if r == 0 { 1.0 } else { 0.0 };
+    }
+    let z = solve(normal);
+    rows.iter()
+        .map(|j| j.iter().zip(&z).map(|(a, b)| a * b).sum())
+        .collect()
+}
+
+/// Gaussian elimination with partial pivoting on an augmented `n × (n + 1)` matrix.
+fn solve(mut m: Vec<Vec<f64>>) -> Vec<f64> {
+    let n = m.len();
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))
+            .unwrap_or(col);
+        m.swap(col, pivot);
+        let (above, below) = m.split_at_mut(col + 1);
+        let pivot_row = &above[col];
+        for row in below {
+            let factor = row[col] / pivot_row[col];
+            for (cell, p) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *cell -= factor * p;
+            }
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| m[row][k] * x[k]).sum();
+        x[row] = (m[row][n] - sum) / m[row][row];
+    }
+    x
+}
+
+type Vec2 = [f64; 2];
+type Mat2 = [[f64; 2]; 2];
+
+/// Constant-velocity Kalman filter followed by a Rauch–Tung–Striebel
+/// backward pass, so the result has no lag.
+///
+/// `measurement_noise` is the standard deviation of the values and
+/// `process_noise` that of their second derivative; sample spacing is taken
+/// from `times`.
+pub fn kalman(
+    times: &[f64],
+    values: &[f64],
+    measurement_noise: f64,
+    process_noise: f64,
+) -> Vec<f64> {
+    let n = values.len().min(times.len());
+    if n < 2 {
+        return values.to_vec();
+    }
+    let r = measurement_noise * measurement_noise;
+    let q = process_noise * process_noise;
+
+    // Filtered and predicted (a priori) states and covariances
+    let mut filtered: Vec<(Vec2, Mat2)> = Vec::with_capacity(n);
+    let mut predicted: Vec<(Vec2, Mat2)> = Vec::with_capacity(n);
+    let mut x: Vec2 = [values[0], 0.0];
+    let mut p: Mat2 = [[r, 0.0], [0.0, 1e6]];
+
+    for i in 0..n {
+        if i > 0 {
+            let dt = (times[i] - times[i - 1]).max(0.0);
+            let f = transition(dt);
+            x = [x[0] + dt * x[1], x[1]];
+            let (dt2, dt3, dt4) = (dt * dt, dt * dt * dt, dt * dt * dt * dt);
+            let fp = mul(f, p);
+            p = add(
+                mul(fp, transpose(f)),
+                [[q * dt4 / 4.0, q * dt3 / 2.0], [q * dt3 / 2.0, q * dt2]],
+            );
+        }
+        predicted.push((x, p));
+
+        // Update with the measured value (H = [1, 0])
+        let s = p[0][0] + r;
+        let k = [p[0][0] / s, p[1][0] / s];
+        let innovation = values[i] - x[0];
+        x = [x[0] + k[0] * innovation, x[1] + k[1] * innovation];
+        p = [
+            [(1.0 - k[0]) * p[0][0], (1.0 - k[0]) * p[0][1]],
+            [p[1][0] - k[1] * p[0][0], p[1][1] - k[1] * p[0][1]],
+        ];
+        filtered.push((x, p));
+    }
+
+    let mut smoothed = vec![filtered[n - 1].0; n];
+    for i in (0..n - 1).rev() {
+        let (x, p) = filtered[i];
+        let (x_pred, p_pred) = predicted[i + 1];
+        let Some(p_pred_inv) = inverse(p_pred) else {
+            smoothed[i] = x;
+            continue;
+        };
+        let gain = mul(
+            mul(p, transpose(transition(times[i + 1] - times[i]))),
+            p_pred_inv,
+        );
+        let diff = [
+            smoothed[i + 1][0] - x_pred[0],
+            smoothed[i + 1][1] - x_pred[1],
+        ];
+        smoothed[i] = [
+            x[0] + gain[0][0] * diff[0] + gain[0][1] * diff[1],
+            x[1] + gain[1][0] * diff[0] + gain[1][1] * diff[1],
+        ];
+    }
+    smoothed.into_iter().map(|s| s[0]).collect()
+}
+
+fn transition(dt: f64) -> Mat2 {
+    [[1.0, dt.max(0.0)], [0.0, 1.0]]
+}
+
+fn mul(a: Mat2, b: Mat2) -> Mat2 {
+    let cell = |r: usize, c: usize| a[r][0] * b[0][c] + a[r][1] * b[1][c];
+    [[cell(0, 0), cell(0, 1)], [cell(1, 0), cell(1, 1)]]
+}
+
+fn add(a: Mat2, b: Mat2) -> Mat2 {
+    [
+        [a[0][0] + b[0][0], a[0][1] + b[0][1]],
+        [a[1][0] + b[1][0], a[1][1] + b[1][1]],
+    ]
+}
+
+fn transpose(a: Mat2) -> Mat2 {
+    [[a[0][0], a[1][0]], [a[0][1], a[1][1]]]
+}
+
+fn inverse(a: Mat2) -> Option<Mat2> {
+    let det = a[0][0] * a[1][1] - a[0][1] * a[1][0];
+    (det.abs() > f64::EPSILON).then(|| {
+        [
+            [a[1][1] / det, -a[0][1] / det],
+            [-a[1][0] / det, a[0][0] / det],
+        ]
+    })
+}
+
+/// Copy of `lap` with different samples.
+fn with_samples(lap: &Lap, samples: Vec<TelemetrySample>) -> Lap {
+    Lap {
+        number: lap.number,
+        lap_time: lap.lap_time,
+        sector_times: lap.sector_times,
+        samples,
+        is_valid: lap.is_valid,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DrsStatus;
+    use glam::Vec3;
+
+    /// Deterministic ±amplitude noise.
+    fn noise(i: usize, amplitude: f64) -> f64 {
+        if i.is_multiple_of(2) {
+            amplitude
+        } else {
+            -amplitude
+        }
+    }
+
+    fn rms_error(values: &[f64], truth: impl Fn(usize) -> f64) -> f64 {
+        let sum: f64 = values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v - truth(i)).powi(2))
+            .sum();
+        (sum / values.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn test_savitzky_golay_preserves_polynomials() {
+        let quadratic: Vec<f64> = (0..20)
+            .map(|i| 0.5 * (i * i) as f64 - 3.0 * i as f64)
+            .collect();
+        let smoothed = savitzky_golay(&quadratic, 7, 2);
+        for (a, b) in quadratic.iter().zip(&smoothed) {
+            assert!((a - b).abs() < 1e-6, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn test_filters_reduce_noise() {
+        let truth = |i: usize| 200.0 - i as f64;
+        let times: Vec<f64> = (0..60).map(|i| i as f64 * 0.25).collect();
+        let noisy: Vec<f64> = (0..60).map(|i| truth(i) + noise(i, 4.0)).collect();
+        let raw = rms_error(&noisy, truth);
+
+        assert!(rms_error(&moving_average(&noisy, 5), truth) < raw / 2.0);
+        assert!(rms_error(&savitzky_golay(&noisy, 9, 2), truth) < raw / 2.0);
+        assert!(rms_error(&kalman(&times, &noisy, 4.0, 1.0), truth) < raw / 2.0);
+    }
+
+    #[test]
+    fn test_resample_by_distance() {
+        let samples = (0..=10)
+            .map(|i| TelemetrySample {
+                position: Vec3::new(i as f32 * 10.0, 0.0, 0.0),
+                time: i as f64 * 0.2,
+                throttle: 100,
+                brake: 0,
+                gear: 7,
+                speed: 180,
+                drs: DrsStatus::Off,
+            })
+            .collect();
+        let lap = Lap {
+            number: 3,
+            lap_time: Some(2.0),
+            sector_times: [None; 3],
+            samples,
+            is_valid: true,
+        };
+
+        let resampled = resample_by_distance(&lap, 25.0);
+        let xs: Vec<f32> = resampled.samples.iter().map(|s| s.position.x).collect();
+        assert_eq!(xs, vec![0.0, 25.0, 50.0, 75.0, 100.0]);
+        assert_eq!(resampled.number, 3);
+        assert_eq!(resample_by_time(&lap, 0.5).samples.len(), 5);
+    }
+}
@@ -0,0 +1,327 @@
+//! Stints and race pace.
+//!
+//! A stint runs from the start (or a pit exit) to the next tyre stop. Pace
+//! is measured on the stint's push laps only, so in- and out-laps and
+//! safety-car laps don't skew it:
+//!
+//! - the median lap time and its standard deviation (consistency);
+//! - the fuel-corrected lap time: the lap time minus what the fuel still in
+//!   the car costs, relative to the last lap of the race;
+//! - degradation, the slope of a least-squares line through the
+//!   fuel-corrected lap times over lap number. Fuel burn makes cars faster
+//!   every lap, which would otherwise hide tyre wear.
+
+use serde::Serialize;
+
+use crate::classify::LapClasses;
+use crate::export::{number, Exportable};
+use crate::pit::PitStops;
+use crate::{DriverSession, Session};
+
+/// Fuel model and stint-splitting settings.
+#[derive(Debug, Clone, Copy)]
+pub struct StintConfig {
+    /// Fuel burned per lap (kg)
+    pub fuel_per_lap: f64,
+    /// Lap time cost of carrying fuel (s/kg)
+    pub time_per_kg: f64,
+    /// Shortest stop that counts as a tyre change (s); drive-through
+    /// penalties don't start a new stint
+    pub min_stationary_time: f64,
+}
+
+impl Default for StintConfig {
+    fn default() -> Self {
+        Self {
+            fuel_per_lap: 1.6,
+            time_per_kg: 0.03,
+            min_stationary_time: 1.0,
+        }
+    }
+}
+
+/// A push lap in a stint.
+#[derive(Debug, Clone, Serialize)]
+pub struct StintLap {
+    pub lap_number: u32,
+    pub lap_time: f64,
+    /// Lap time as if the car carried the fuel of the race's last lap
+    pub fuel_corrected: f64,
+}
+
+/// One driver's laps between two tyre stops.
+#[derive(Debug, Clone, Serialize)]
+pub struct Stint {
+    pub driver_code: String,
+    /// 1 for the first stint
+    pub number: usize,
+    pub start_lap: u32,
+    pub end_lap: u32,
+    /// The stint's push laps
+    pub laps: Vec<StintLap>,
+    pub median_lap_time: Option<f64>,
+    pub fuel_corrected_median: Option<f64>,
+    /// Standard deviation of the push lap times (s)
+    pub std_dev: Option<f64>,
+    /// Change in fuel-corrected lap time per lap (s/lap)
+    pub degradation: Option<f64>,
+}
+
+impl Stint {
+    /// Laps from the start to the end of the stint, including pit laps.
+    pub fn length(&self) -> u32 {
+        self.end_lap + 1 - self.start_lap
+    }
+}
+
+/// Every driver's stints in a session.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RacePace {
+    /// Sorted by driver order in the session, then by stint
+    pub stints: Vec<Stint>,
+}
+
+impl RacePace {
+    /// A driver's stints, in order.
+    pub fn for_driver<'a>(&'a self, driver_code: &'a str) -> impl Iterator<Item = &'a Stint> {
+        self.stints
+            .iter()
+            .filter(move |s| s.driver_code == driver_code)
+    }
+}
+
+impl Exportable for RacePace {
+    fn title(&self) -> String {
+        "Race pace by stint".into()
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        vec![
+            "Driver",
+            "Stint",
+            "From lap",
+            "To lap",
+            "Push laps",
+            "Median (s)",
+            "Fuel-corrected (s)",
+            "Std dev (s)",
+            "Degradation (s/lap)",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.stints
+            .iter()
+            .map(|stint| {
+                vec![
+                    stint.driver_code.clone(),
+                    stint.number.to_string(),
+                    stint.start_lap.to_string(),
+                    stint.end_lap.to_string(),
+                    stint.laps.len().to_string(),
+                    number(stint.median_lap_time, 3),
+                    number(stint.fuel_corrected_median, 3),
+                    number(stint.std_dev, 3),
+                    number(stint.degradation, 3),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Split every driver's laps into stints at their pit stops and measure the
+/// pace of each.
+pub fn race_pace(
+    session: &Session,
+    classes: &LapClasses,
+    stops: &PitStops,
+    config: &StintConfig,
+) -> RacePace {
+    let race_laps = session
+        .drivers
+        .iter()
+        .flat_map(|d| d.laps.iter().map(|l| l.number))
+        .max()
+        .unwrap_or(0);
+    let stints = session
+        .drivers
+        .iter()
+        .flat_map(|driver| driver_stints(driver, classes, stops, race_laps, config))
+        .collect();
+    RacePace { stints }
+}
+
+fn driver_stints(
+    driver: &DriverSession,
+    classes: &LapClasses,
+    stops: &PitStops,
+    race_laps: u32,
+    config: &StintConfig,
+) -> Vec<Stint> {
+    let (Some(first), Some(last)) = (driver.laps.first(), driver.laps.last()) else {
+        return Vec::new();
+    };
+    let code = &driver.driver.code;
+    // A stint ends on the lap the car entered the pit lane
+    let mut ends: Vec<u32> = stops
+        .for_driver(code)
+        .filter(|stop| stop.stationary_time >= config.min_stationary_time)
+        .map(|stop| stop.lap_number)
+        .filter(|&lap| lap < last.number)
+        .collect();
+    ends.push(last.number);
+    ends.dedup();
+
+    let push_laps = classes.representative_laps(driver);
+    let mut start_lap = first.number;
+    let mut stints = Vec::with_capacity(ends.len());
+    for end_lap in ends {
+        let laps: Vec<StintLap> = push_laps
+            .iter()
+            .filter(|lap| (start_lap..=end_lap).contains(&lap.number))
+            .filter_map(|lap| {
+                let lap_time = lap.lap_time?;
+                let fuel = race_laps.saturating_sub(lap.number) as f64 * config.fuel_per_lap;
+                Some(StintLap {
+                    lap_number: lap.number,
+                    lap_time,
+                    fuel_corrected: lap_time - fuel * config.time_per_kg,
+                })
+            })
+            .collect();
+        stints.push(Stint {
+            driver_code: code.clone(),
+            number: stints.len() + 1,
+            start_lap,
+            end_lap,
+            median_lap_time: median(laps.iter().map(|l| l.lap_time)),
+            fuel_corrected_median: median(laps.iter().map(|l| l.fuel_corrected)),
+            std_dev: std_dev(laps.iter().map(|l| l.lap_time)),
+            degradation: slope(laps.iter().map(|l| (l.lap_number as f64, l.fuel_corrected))),
+            laps,
+        });
+        start_lap = end_lap + 1;
+    }
+    stints
+}
+
+fn median(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let mut values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+/// Sample standard deviation; `None` for fewer than two values.
+fn std_dev(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let values: Vec<f64> = values.collect();
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance =
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    Some(variance.sqrt())
+}
+
+/// Least-squares slope of `y` over `x`; `None` for fewer than two distinct
+/// `x` values.
+fn slope(points: impl Iterator<Item = (f64, f64)>) -> Option<f64> {
+    let points: Vec<(f64, f64)> = points.collect();
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for &(x, y) in &points {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance += (x - mean_x).powi(2);
+    }
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::{classify_session, ClassifyConfig};
+    use crate::pit::PitStop;
+    use crate::{Driver, Lap, SessionType};
+    use chrono::Utc;
+
+    #[test]
+    fn test_race_pace() {
+        // 20-lap race, stop on lap 8. Tyres lose 0.1 s/lap; fuel is worth
+        // 0.048 s/lap with the default model
+        let laps = (1..=20)
+            .map(|number| {
+                let tyre_age = if number <= 8 { number } else { number - 8 } as f64;
+                let fuel = (20 - number) as f64 * 0.048;
+                Lap {
+                    number,
+                    lap_time: Some(95.0 + 0.1 * tyre_age + fuel),
+                    sector_times: [None; 3],
+                    samples: Vec::new(),
+                    is_valid: true,
+                }
+            })
+            .collect();
+        let session = Session {
+            schema_version: crate::loader::CURRENT_SCHEMA_VERSION,
+            year: 2025,
+            circuit: "Austin".into(),
+            circuit_short: "austin".into(),
+            session_type: SessionType::Race,
+            date: Utc::now(),
+            drivers: vec![DriverSession {
+                driver: Driver {
+                    code: "VER".into(),
+                    name: String::new(),
+                    number: 1,
+                    team: String::new(),
+                    team_color: String::new(),
+                },
+                laps,
+            }],
+        };
+        let classes = classify_session(&session, &ClassifyConfig::default());
+        let stop = |stationary_time| PitStop {
+            driver_code: "VER".into(),
+            lap_number: 8,
+            entry_time: 0.0,
+            exit_time: 20.0,
+            stationary_time,
+            pit_loss: None,
+        };
+
+        let stops = PitStops {
+            stops: vec![stop(2.5)],
+        };
+        let pace = race_pace(&session, &classes, &stops, &StintConfig::default());
+        let stints: Vec<&Stint> = pace.for_driver("VER").collect();
+        assert_eq!(stints.len(), 2);
+        assert_eq!((stints[0].start_lap, stints[0].end_lap), (1, 8));
+        assert_eq!((stints[1].start_lap, stints[1].end_lap), (9, 20));
+        for stint in &stints {
+            let degradation = stint.degradation.unwrap();
+            assert!((degradation - 0.1).abs() < 1e-9, "{degradation}");
+            assert!(stint.std_dev.unwrap() > 0.0);
+        }
+        // Last lap carries no fuel to correct for
+        let last = stints[1].laps.last().unwrap();
+        assert!((last.fuel_corrected - last.lap_time).abs() < 1e-9);
+
+        // A drive-through doesn't change tyres
+        let stops = PitStops {
+            stops: vec![stop(0.0)],
+        };
+        let pace = race_pace(&session, &classes, &stops, &StintConfig::default());
+        assert_eq!(pace.stints.len(), 1);
+        assert_eq!(pace.stints[0].length(), 20);
+    }
+}
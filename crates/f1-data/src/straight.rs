@@ -0,0 +1,101 @@
+//! Straights: the full-throttle stretches of a reference lap.
+//!
+//! The reference is the session's fastest lap. Walking it in 5 m steps, a
+//! run with the throttle at or above `StraightConfig::full_throttle` that is
+//! at least `min_straight` long is a straight, kept as distances along that
+//! lap so other laps can be measured on it scaled to their own length. DRS,
+//! speed traps and clipping all use the same straights.
+
+use glam::Vec3;
+use serde::Serialize;
+
+use crate::{LapTrace, Session};
+
+/// Straight detection thresholds.
+#[derive(Debug, Clone, Copy)]
+pub struct StraightConfig {
+    /// Shortest full-throttle stretch counted as a straight (m)
+    pub min_straight: f32,
+    /// Lowest throttle counted as full throttle (%)
+    pub full_throttle: u8,
+}
+
+impl Default for StraightConfig {
+    fn default() -> Self {
+        Self {
+            min_straight: 300.0,
+            full_throttle: 98,
+        }
+    }
+}
+
+/// A full-throttle stretch of the reference lap.
+#[derive(Debug, Clone, Serialize)]
+pub struct Straight {
+    /// 1-based, in lap order
+    pub number: usize,
+    /// Distance along the reference lap (m)
+    pub start_distance: f32,
+    pub end_distance: f32,
+    /// Reference lap positions along the straight
+    #[serde(skip)]
+    pub path: Vec<Vec3>,
+    /// Someone opened DRS on it (set by `drs::analyze_drs`)
+    pub drs_zone: bool,
+}
+
+impl Straight {
+    pub fn length(&self) -> f32 {
+        self.end_distance - self.start_distance
+    }
+
+    /// Reference position halfway along the straight.
+    pub fn midpoint(&self) -> Option<Vec3> {
+        self.path.get(self.path.len() / 2).copied()
+    }
+}
+
+/// The session's fastest lap, which straights are found on.
+pub fn reference_lap(session: &Session) -> Option<LapTrace> {
+    session
+        .fastest_laps()
+        .first()
+        .and_then(|(_, lap)| LapTrace::new(lap))
+}
+
+/// Full-throttle stretches of the reference lap at least
+/// `config.min_straight` long.
+pub fn find_straights(reference: &LapTrace, config: &StraightConfig) -> Vec<Straight> {
+    const STEP: f32 = 5.0;
+    let mut straights = Vec::new();
+    let mut run: Option<(f32, Vec<Vec3>)> = None;
+    let mut distance = 0.0;
+    loop {
+        let at_end = distance >= reference.length();
+        let distance_here = distance.min(reference.length());
+        let sample = reference.sample_at(reference.time_at_distance(distance_here));
+        let full = !at_end && sample.throttle >= config.full_throttle;
+        match (&mut run, full) {
+            (Some((_, path)), true) => path.push(sample.position),
+            (None, true) => run = Some((distance_here, vec![sample.position])),
+            (Some(_), false) => {
+                let (start, path) = run.take().unwrap();
+                if distance_here - start >= config.min_straight {
+                    straights.push(Straight {
+                        number: straights.len() + 1,
+                        start_distance: start,
+                        end_distance: distance_here,
+                        path,
+                        drs_zone: false,
+                    });
+                }
+            }
+            (None, false) => {}
+        }
+        if at_end {
+            break;
+        }
+        distance += STEP;
+    }
+    straights
+}
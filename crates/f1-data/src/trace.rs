@@ -0,0 +1,307 @@
+//! Lap traces for lap-relative playback and comparison.
+//!
+//! Telemetry samples are stamped with session time, so two laps set minutes
+//! apart cannot be compared directly. A `LapTrace` re-bases a lap onto
+//! "time since lap start" and tracks the distance driven, which lets any two
+//! laps be aligned to a common start and compared at equal track distance.
+
+use serde::Serialize;
+
+use crate::{DriverSession, Lap, TelemetrySample};
+
+/// A lap prepared for lap-relative lookups.
+#[derive(Debug, Clone)]
+pub struct LapTrace {
+    /// Lap number this trace was built from
+    pub lap_number: u32,
+    samples: Vec<TelemetrySample>,
+    /// Cumulative distance (meters) at each sample, in the X/Z plane
+    distances: Vec<f32>,
+    start_time: f64,
+}
+
+impl LapTrace {
+    /// Build a trace from a lap. Returns `None` if the lap has fewer than two samples.
+    pub fn new(lap: &Lap) -> Option<Self> {
+        if lap.samples.len() < 2 {
+            return None;
+        }
+
+        let mut distances = Vec::with_capacity(lap.samples.len());
+        let mut total = 0.0;
+        distances.push(total);
+        for pair in lap.samples.windows(2) {
+            total += planar_distance(&pair[0], &pair[1]);
+            distances.push(total);
+        }
+
+        Some(Self {
+            lap_number: lap.number,
+            start_time: lap.samples[0].time,
+            samples: lap.samples.clone(),
+            distances,
+        })
+    }
+
+    /// Duration covered by the samples, in seconds.
+    pub fn duration(&self) -> f64 {
+        self.samples.last().map_or(0.0, |s| s.time) - self.start_time
+    }
+
+    /// Total distance covered by the samples, in meters.
+    pub fn length(&self) -> f32 {
+        self.distances.last().copied().unwrap_or(0.0)
+    }
+
+    /// Session time of the first sample.
+    pub fn start_time(&self) -> f64 {
+        self.start_time
+    }
+
+    /// Index of the last sample at or before `lap_time`.
+    pub fn sample_index_at(&self, lap_time: f64) -> usize {
+        let time = self.start_time + lap_time;
+        self.samples
+            .partition_point(|s| s.time <= time)
+            .saturating_sub(1)
+    }
+
+    /// Interpolated sample at `lap_time` seconds after the lap start.
+    ///
+    /// Times outside the lap are clamped to the first/last sample. The
+    /// returned sample keeps its session time.
+    pub fn sample_at(&self, lap_time: f64) -> TelemetrySample {
+        let (i, t) = self.locate(lap_time);
+        interpolate(&self.samples[i], &self.samples[i + 1], t)
+    }
+
+    /// Distance driven at `lap_time` seconds after the lap start.
+    pub fn distance_at(&self, lap_time: f64) -> f32 {
+        let (i, t) = self.locate(lap_time);
+        self.distances[i] + (self.distances[i + 1] - self.distances[i]) * t as f32
+    }
+
+    /// Lap-relative time at which `distance` meters had been driven.
+    pub fn time_at_distance(&self, distance: f32) -> f64 {
+        let distance = distance.clamp(0.0, self.length());
+        let i = self
+            .distances
+            .partition_point(|&d| d <= distance)
+            .clamp(1, self.distances.len() - 1)
+            - 1;
+        let (d0, d1) = (self.distances[i], self.distances[i + 1]);
+        let (t0, t1) = (self.samples[i].time, self.samples[i + 1].time);
+        let t = if d1 > d0 {
+            ((distance - d0) / (d1 - d0)) as f64
+        } else {
+            0.0
+        };
+        t0 + (t1 - t0) * t - self.start_time
+    }
+
+    /// Time gap to `reference` at the distance this lap has covered at `lap_time`.
+    ///
+    /// Positive values mean this lap is behind the reference.
+    pub fn gap_to(&self, reference: &LapTrace, lap_time: f64) -> f64 {
+        let lap_time = lap_time.clamp(0.0, self.duration());
+        lap_time - reference.time_at_distance(self.distance_at(lap_time))
+    }
+
+    /// Find the sample pair surrounding `lap_time` and the blend factor between them.
+    fn locate(&self, lap_time: f64) -> (usize, f64) {
+        let time = self.start_time + lap_time;
+        let i = self.sample_index_at(lap_time).min(self.samples.len() - 2);
+        let (t0, t1) = (self.samples[i].time, self.samples[i + 1].time);
+        let t = if t1 > t0 {
+            ((time - t0) / (t1 - t0)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        (i, t)
+    }
+}
+
+/// One point of a `DeltaTrace`.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DeltaPoint {
+    /// Distance into the lap (meters)
+    pub distance: f32,
+    /// Time lost to the reference at this distance (seconds, positive = behind)
+    pub delta: f64,
+    /// Speed of the lap (km/h)
+    pub speed: u16,
+    /// Speed of the reference lap (km/h)
+    pub reference_speed: u16,
+}
+
+/// Time delta of a lap to a reference lap along the distance driven.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeltaTrace {
+    pub driver_code: String,
+    pub lap_number: u32,
+    pub reference_driver_code: String,
+    pub reference_lap_number: u32,
+    pub points: Vec<DeltaPoint>,
+}
+
+impl DeltaTrace {
+    /// Sample the delta every `step` meters, up to the shorter of the two laps.
+    pub fn new(
+        driver_code: &str,
+        lap: &LapTrace,
+        reference_driver_code: &str,
+        reference: &LapTrace,
+        step: f32,
+    ) -> Self {
+        let length = lap.length().min(reference.length());
+        let count = if step > 0.0 {
+            (length / step).floor() as usize + 1
+        } else {
+            0
+        };
+        let points = (0..count)
+            .map(|i| {
+                let distance = i as f32 * step;
+                let time = lap.time_at_distance(distance);
+                let reference_time = reference.time_at_distance(distance);
+                DeltaPoint {
+                    distance,
+                    delta: time - reference_time,
+                    speed: lap.sample_at(time).speed,
+                    reference_speed: reference.sample_at(reference_time).speed,
+                }
+            })
+            .collect();
+
+        Self {
+            driver_code: driver_code.to_string(),
+            lap_number: lap.lap_number,
+            reference_driver_code: reference_driver_code.to_string(),
+            reference_lap_number: reference.lap_number,
+            points,
+        }
+    }
+
+    /// Delta at the end of the trace.
+    pub fn final_delta(&self) -> Option<f64> {
+        self.points.last().map(|p| p.delta)
+    }
+}
+
+/// Interpolated sample for a driver at a session time.
+///
+/// Returns the lap the time falls in and the index of the last sample at or
+/// before it, or `None` if the driver has no telemetry at that time.
+pub fn session_sample_at(
+    driver: &DriverSession,
+    time: f64,
+) -> Option<(&Lap, usize, TelemetrySample)> {
+    let lap = driver.laps.iter().find(|lap| {
+        matches!((lap.samples.first(), lap.samples.last()), (Some(first), Some(last)) if first.time <= time && time <= last.time)
+    })?;
+    if lap.samples.len() < 2 {
+        return Some((lap, 0, lap.samples[0].clone()));
+    }
+
+    let index = (lap
+        .samples
+        .partition_point(|s| s.time <= time)
+        .saturating_sub(1))
+    .min(lap.samples.len() - 2);
+    let (a, b) = (&lap.samples[index], &lap.samples[index + 1]);
+    let t = if b.time > a.time {
+        ((time - a.time) / (b.time - a.time)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    Some((lap, index, interpolate(a, b, t)))
+}
+
+/// Blend two samples. Continuous channels are interpolated, discrete
+/// channels (gear, DRS, brake) are taken from the nearer sample.
+pub(crate) fn interpolate(a: &TelemetrySample, b: &TelemetrySample, t: f64) -> TelemetrySample {
+    let nearest = if t < 0.5 { a } else { b };
+    let lerp = |x: f64, y: f64| x + (y - x) * t;
+    TelemetrySample {
+        position: a.position.lerp(b.position, t as f32),
+        time: lerp(a.time, b.time),
+        throttle: lerp(a.throttle as f64, b.throttle as f64).round() as u8,
+        brake: nearest.brake,
+        gear: nearest.gear,
+        speed: lerp(a.speed as f64, b.speed as f64).round() as u16,
+        drs: nearest.drs,
+    }
+}
+
+pub(crate) fn planar_distance(a: &TelemetrySample, b: &TelemetrySample) -> f32 {
+    let (dx, dz) = (b.position.x - a.position.x, b.position.z - a.position.z);
+    (dx * dx + dz * dz).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DrsStatus;
+    use glam::Vec3;
+
+    fn straight_lap(start_time: f64, speed_mps: f32) -> Lap {
+        let samples = (0..=10)
+            .map(|i| TelemetrySample {
+                position: Vec3::new(i as f32 * speed_mps, 0.0, 0.0),
+                time: start_time + i as f64,
+                throttle: 100,
+                brake: 0,
+                gear: 7,
+                speed: (speed_mps * 3.6) as u16,
+                drs: DrsStatus::Off,
+            })
+            .collect();
+        Lap {
+            number: 1,
+            lap_time: Some(10.0),
+            sector_times: [None; 3],
+            samples,
+            is_valid: true,
+        }
+    }
+
+    #[test]
+    fn test_sample_at_is_lap_relative() {
+        let trace = LapTrace::new(&straight_lap(3600.0, 50.0)).unwrap();
+        assert_eq!(trace.duration(), 10.0);
+        let sample = trace.sample_at(2.5);
+        assert!((sample.position.x - 125.0).abs() < 1e-3);
+        assert!((trace.distance_at(2.5) - 125.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_session_sample_at_finds_lap() {
+        let mut second = straight_lap(110.0, 50.0);
+        second.number = 2;
+        let driver = DriverSession {
+            driver: crate::Driver {
+                code: "VER".into(),
+                name: "Max Verstappen".into(),
+                number: 1,
+                team: "Red Bull Racing".into(),
+                team_color: "#3671C6".into(),
+            },
+            laps: vec![straight_lap(100.0, 50.0), second],
+        };
+
+        let (lap, index, sample) = session_sample_at(&driver, 113.5).unwrap();
+        assert_eq!(lap.number, 2);
+        assert_eq!(index, 3);
+        assert!((sample.position.x - 175.0).abs() < 1e-3);
+        assert!(session_sample_at(&driver, 50.0).is_none());
+    }
+
+    #[test]
+    fn test_gap_at_equal_distance() {
+        let reference = LapTrace::new(&straight_lap(100.0, 50.0)).unwrap();
+        let slower = LapTrace::new(&straight_lap(900.0, 40.0)).unwrap();
+        // After 5s the slower car has covered 200m, which the reference reached in 4s.
+        assert!((slower.gap_to(&reference, 5.0) - 1.0).abs() < 1e-6);
+        assert!((reference.time_at_distance(200.0) - 4.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,413 @@
+//! Speed traps and straight-line speed.
+//!
+//! A `SpeedTrap` is a gate line across the track kept with the circuit's
+//! turn definitions. A lap's trap speed is its speed where the line between
+//! two samples crosses the gate, interpolated between them; the gap to the
+//! next lap's first sample is included so a gate on the finish line is
+//! caught at the end of the lap.
+//!
+//! Straights are the full-throttle stretches of the fastest lap (see
+//! `straight.rs`). On each straight a lap has a top speed and a distance
+//! to top speed: how far down the straight it first got within
+//! `TrapConfig::top_speed_fraction` of its top speed, which separates cars
+//! that accelerate hard from those that just run less drag.
+
+use serde::Serialize;
+
+use crate::classify::{LapClass, LapClasses};
+use crate::export::Exportable;
+use crate::straight::{self, Straight, StraightConfig};
+use crate::{LapTrace, Session, SpeedTrap};
+
+/// Straight detection and top speed thresholds.
+#[derive(Debug, Clone, Copy)]
+pub struct TrapConfig {
+    pub straights: StraightConfig,
+    /// Share of the top speed that counts as having reached it
+    pub top_speed_fraction: f64,
+}
+
+impl Default for TrapConfig {
+    fn default() -> Self {
+        Self {
+            straights: StraightConfig::default(),
+            top_speed_fraction: 0.99,
+        }
+    }
+}
+
+/// One lap through one speed trap.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrapSpeed {
+    pub driver_code: String,
+    pub lap_number: u32,
+    /// `None` for laps that weren't classified
+    pub class: Option<LapClass>,
+    pub trap: String,
+    /// km/h
+    pub speed: f64,
+}
+
+/// One lap down one straight.
+#[derive(Debug, Clone, Serialize)]
+pub struct StraightSpeed {
+    pub driver_code: String,
+    pub lap_number: u32,
+    pub class: Option<LapClass>,
+    pub straight: usize,
+    /// km/h
+    pub top_speed: f64,
+    /// Distance from the start of the straight to reaching top speed (m)
+    pub distance_to_top: f32,
+}
+
+/// A driver's speeds at one trap or straight, for ranking.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeedRank {
+    pub driver_code: String,
+    /// km/h
+    pub best: f64,
+    /// km/h
+    pub mean: f64,
+    pub laps: usize,
+    /// Mean distance to top speed on a straight (m)
+    pub distance_to_top: Option<f32>,
+}
+
+/// Trap speeds and straight-line speed of every lap with telemetry.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SpeedTraps {
+    /// Trap names, in the order of the turn file
+    pub traps: Vec<String>,
+    pub straights: Vec<Straight>,
+    pub trap_speeds: Vec<TrapSpeed>,
+    pub straight_speeds: Vec<StraightSpeed>,
+}
+
+impl SpeedTraps {
+    /// Drivers by their best speed through a trap on laps of `classes`
+    /// (every lap if empty), fastest first.
+    pub fn trap_ranking(&self, trap: &str, classes: &[LapClass]) -> Vec<SpeedRank> {
+        let speeds = self
+            .trap_speeds
+            .iter()
+            .filter(|s| s.trap == trap && is_ranked(s.class, classes));
+        rank(speeds.map(|s| (s.driver_code.as_str(), s.speed, None)))
+    }
+
+    /// Drivers by their best top speed on a straight on laps of `classes`
+    /// (every lap if empty), fastest first.
+    pub fn straight_ranking(&self, straight: usize, classes: &[LapClass]) -> Vec<SpeedRank> {
+        let speeds = self
+            .straight_speeds
+            .iter()
+            .filter(|s| s.straight == straight && is_ranked(s.class, classes));
+        rank(speeds.map(|s| (s.driver_code.as_str(), s.top_speed, Some(s.distance_to_top))))
+    }
+}
+
+impl Exportable for SpeedTraps {
+    fn title(&self) -> String {
+        "Speed traps and top speeds".into()
+    }
+
+    fn headers(&self) -> Vec<&'static str> {
+        vec![
+            "Driver",
+            "Lap",
+            "Class",
+            "Trap",
+            "Speed (km/h)",
+            "Distance to top speed (m)",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let traps = self.trap_speeds.iter().map(|s| {
+            vec![
+                s.driver_code.clone(),
+                s.lap_number.to_string(),
+                class_name(s.class).into(),
+                s.trap.clone(),
+                format!("{:.1}", s.speed),
+                String::new(),
+            ]
+        });
+        let straights = self.straight_speeds.iter().map(|s| {
+            vec![
+                s.driver_code.clone(),
+                s.lap_number.to_string(),
+                class_name(s.class).into(),
+                format!("Straight {}", s.straight),
+                format!("{:.0}", s.top_speed),
+                format!("{:.0}", s.distance_to_top),
+            ]
+        });
+        traps.chain(straights).collect()
+    }
+}
+
+fn class_name(class: Option<LapClass>) -> &'static str {
+    class.map_or("", |c| c.as_str())
+}
+
+fn is_ranked(class: Option<LapClass>, classes: &[LapClass]) -> bool {
+    classes.is_empty() || class.is_some_and(|c| classes.contains(&c))
+}
+
+/// Group speeds by driver, best first.
+fn rank<'a>(speeds: impl Iterator<Item = (&'a str, f64, Option<f32>)>) -> Vec<SpeedRank> {
+    let mut ranks: Vec<(SpeedRank, f32, usize)> = Vec::new();
+    for (code, speed, distance) in speeds {
+        let index = match ranks.iter().position(|r| r.0.driver_code == code) {
+            Some(index) => index,
+            None => {
+                ranks.push((
+                    SpeedRank {
+                        driver_code: code.to_string(),
+                        best: speed,
+                        mean: 0.0,
+                        laps: 0,
+                        distance_to_top: None,
+                    },
+                    0.0,
+                    0,
+                ));
+                ranks.len() - 1
+            }
+        };
+        let (rank, distance_sum, distances) = &mut ranks[index];
+        rank.best = rank.best.max(speed);
+        rank.mean += speed;
+        rank.laps += 1;
+        if let Some(distance) = distance {
+            *distance_sum += distance;
+            *distances += 1;
+        }
+    }
+    let mut ranks: Vec<SpeedRank> = ranks
+        .into_iter()
+        .map(|(mut rank, distance_sum, distances)| {
+            rank.mean /= rank.laps as f64;
+            rank.distance_to_top = (distances > 0).then(|| distance_sum / distances as f32);
+            rank
+        })
+        .collect();
+    ranks.sort_by(|a, b| b.best.total_cmp(&a.best));
+    ranks
+}
+
+/// Measure every lap with telemetry at each trap and on each straight,
+/// keeping its class so rankings can pick e.g. push laps only.
+pub fn measure_speed_traps(
+    session: &Session,
+    classes: &LapClasses,
+    traps: &[SpeedTrap],
+    config: &TrapConfig,
+) -> SpeedTraps {
+    let straights = straight::reference_lap(session).map(|reference| {
+        let straights = straight::find_straights(&reference, &config.straights);
+        (reference.length(), straights)
+    });
+
+    let mut trap_speeds = Vec::new();
+    let mut straight_speeds = Vec::new();
+    for driver in &session.drivers {
+        let code = &driver.driver.code;
+        for (index, lap) in driver.laps.iter().enumerate() {
+            if lap.samples.is_empty() {
+                continue;
+            }
+            let class = classes.get(code, lap.number);
+
+            // The lap's samples plus the first of the next lap
+            let next = driver
+                .laps
+                .get(index + 1)
+                .filter(|next| next.number == lap.number + 1)
+                .and_then(|next| next.samples.first());
+            let samples: Vec<_> = lap.samples.iter().chain(next).collect();
+            for trap in traps {
+                let speed = samples.windows(2).find_map(|pair| {
+                    let t = trap.crossing(pair[0].position, pair[1].position)?;
+                    let (a, b) = (pair[0].speed as f64, pair[1].speed as f64);
+                    Some(a + (b - a) * t as f64)
+                });
+                if let Some(speed) = speed {
+                    trap_speeds.push(TrapSpeed {
+                        driver_code: code.clone(),
+                        lap_number: lap.number,
+                        class,
+                        trap: trap.name.clone(),
+                        speed,
+                    });
+                }
+            }
+
+            let (Some((reference_length, straights)), Some(trace)) =
+                (&straights, LapTrace::new(lap))
+            else {
+                continue;
+            };
+            let scale = trace.length() / reference_length;
+            for straight in straights {
+                let start_distance = straight.start_distance * scale;
+                let from = trace.start_time() + trace.time_at_distance(start_distance);
+                let to = trace.start_time() + trace.time_at_distance(straight.end_distance * scale);
+                let on_straight: Vec<_> = lap
+                    .samples
+                    .iter()
+                    .filter(|s| (from..=to).contains(&s.time))
+                    .collect();
+                let Some(top_speed) = on_straight.iter().map(|s| s.speed).max() else {
+                    continue;
+                };
+                let top_speed = top_speed as f64;
+                let reached = on_straight
+                    .iter()
+                    .find(|s| s.speed as f64 >= top_speed * config.top_speed_fraction)
+                    .map_or(from, |s| s.time);
+                let distance = trace.distance_at(reached - trace.start_time());
+                straight_speeds.push(StraightSpeed {
+                    driver_code: code.clone(),
+                    lap_number: lap.number,
+                    class,
+                    straight: straight.number,
+                    top_speed,
+                    distance_to_top: (distance - start_distance).max(0.0),
+                });
+            }
+        }
+    }
+
+    SpeedTraps {
+        traps: traps.iter().map(|t| t.name.clone()).collect(),
+        straights: straights.map(|(_, s)| s).unwrap_or_default(),
+        trap_speeds,
+        straight_speeds,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::{self, ClassifyConfig};
+    use crate::{Driver, DriverSession, DrsStatus, Lap, SessionType, TelemetrySample};
+    use chrono::Utc;
+    use glam::{Vec2, Vec3};
+    use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+    const RADIUS: f32 = 300.0;
+
+    /// A lap of a circle: full throttle over the first half, accelerating
+    /// from 60 m/s by 0.05 m/s per meter up to `top_speed`, then 50 m/s.
+    fn lap(number: u32, start_time: f64, top_speed: f32) -> Lap {
+        let (mut angle, mut time) = (0.0f32, start_time);
+        let mut samples = Vec::new();
+        while angle < TAU {
+            let straight = angle < PI;
+            let speed = if straight {
+                (60.0 + 0.05 * angle * RADIUS).min(top_speed)
+            } else {
+                50.0
+            };
+            samples.push(TelemetrySample {
+                position: Vec3::new(RADIUS * angle.cos(), 0.0, RADIUS * angle.sin()),
+                time,
+                throttle: if straight { 100 } else { 40 },
+                brake: 0,
+                gear: 7,
+                speed: (speed * 3.6).round() as u16,
+                drs: DrsStatus::Off,
+            });
+            angle += speed * 0.1 / RADIUS;
+            time += 0.1;
+        }
+        Lap {
+            number,
+            lap_time: Some(time - start_time),
+            sector_times: [None; 3],
+            samples,
+            is_valid: true,
+        }
+    }
+
+    fn driver(code: &str, top_speed: f32) -> DriverSession {
+        DriverSession {
+            driver: Driver {
+                code: code.into(),
+                name: String::new(),
+                number: 1,
+                team: String::new(),
+                team_color: String::new(),
+            },
+            laps: (1..=3)
+                .map(|n| lap(n, n as f64 * 100.0, top_speed))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_speed_traps() {
+        let mut session = Session {
+            schema_version: crate::loader::CURRENT_SCHEMA_VERSION,
+            year: 2025,
+            circuit: "Austin".into(),
+            circuit_short: "austin".into(),
+            session_type: SessionType::Qualifying,
+            date: Utc::now(),
+            drivers: vec![driver("ALO", 85.0), driver("STR", 90.0)],
+        };
+        // A cool-down lap for ALO, at 40 m/s down the straight
+        let alo = &mut session.drivers[0];
+        alo.laps.push(lap(4, 400.0, 40.0));
+        // Across the track a quarter of the way round, 471 m down the
+        // straight where the speed is 60 + 0.05 * 471 m/s
+        let trap = SpeedTrap {
+            name: "ST".into(),
+            start: Vec2::new(-20.0, RADIUS - 20.0),
+            end: Vec2::new(20.0, RADIUS + 20.0),
+        };
+        let classes = classify::classify_session(&session, &ClassifyConfig::default());
+        let traps = measure_speed_traps(&session, &classes, &[trap], &TrapConfig::default());
+
+        let expected = (60.0 + 0.05 * FRAC_PI_2 * RADIUS) as f64 * 3.6;
+        let ranking = traps.trap_ranking("ST", &[LapClass::Push]);
+        assert_eq!(ranking.len(), 2);
+        assert!((ranking[0].best - expected).abs() < 2.0, "{ranking:?}");
+        assert!((ranking[1].best - expected).abs() < 2.0, "{ranking:?}");
+
+        // STR tops out at 90 m/s 600 m down the straight, ALO at 85 m/s
+        // after 500 m
+        assert_eq!(traps.straights.len(), 1);
+        let ranking = traps.straight_ranking(1, &[LapClass::Push]);
+        assert_eq!(ranking[0].driver_code, "STR");
+        assert_eq!(ranking[0].laps, 3);
+        assert!((ranking[0].best - 324.0).abs() < 0.5);
+        let distance = ranking[0].distance_to_top.unwrap();
+        assert!((distance - 583.0).abs() < 15.0, "{distance}");
+        assert!(ranking[1].distance_to_top.unwrap() < distance);
+        assert_eq!(ranking[1].laps, 3);
+
+        // The cool-down lap is measured but only ranked with every lap
+        assert_eq!(classes.get("ALO", 4), Some(LapClass::CoolDown));
+        assert_eq!(traps.trap_speeds.len(), 7);
+        let slow = traps
+            .trap_speeds
+            .iter()
+            .find(|s| s.lap_number == 4)
+            .unwrap();
+        assert_eq!(slow.class, Some(LapClass::CoolDown));
+        assert!((slow.speed - 144.0).abs() < 1.0, "{slow:?}");
+        let alo = |classes: &[LapClass]| {
+            let ranking = traps.trap_ranking("ST", classes);
+            ranking
+                .into_iter()
+                .find(|r| r.driver_code == "ALO")
+                .unwrap()
+        };
+        let (push, every_lap) = (alo(&[LapClass::Push]), alo(&[]));
+        assert_eq!((push.laps, every_lap.laps), (3, 4));
+        assert!(every_lap.mean < push.mean - 20.0);
+    }
+}
@@ -1,9 +1,10 @@
 //! Turn definition and segmentation.
 //!
 //! A turn is defined by a bounding area on the track. Each turn is divided
-//! into three segments for analysis: approach, apex, and exit.
+//! into three segments for analysis: approach, apex, and exit. Speed traps
+//! are gate lines across the track, kept in the same per-circuit file.
 
-use glam::Vec3;
+use glam::{Vec2, Vec3};
 use serde::{Deserialize, Serialize};
 
 use crate::TelemetrySample;
@@ -83,17 +84,22 @@ impl Turn {
             .collect()
     }
 
+    /// Bounding box of a segment of the turn.
+    pub fn segment_bounds(&self, segment: TurnSegment) -> &BoundingBox {
+        match segment {
+            TurnSegment::Approach => &self.approach,
+            TurnSegment::Apex => &self.apex,
+            TurnSegment::Exit => &self.exit,
+        }
+    }
+
     /// Get samples for a specific segment of the turn.
     pub fn filter_segment<'a>(
         &self,
         samples: &'a [TelemetrySample],
         segment: TurnSegment,
     ) -> Vec<&'a TelemetrySample> {
-        let bounds = match segment {
-            TurnSegment::Approach => &self.approach,
-            TurnSegment::Apex => &self.apex,
-            TurnSegment::Exit => &self.exit,
-        };
+        let bounds = self.segment_bounds(segment);
         samples
             .iter()
             .filter(|s| bounds.contains(s.position))
@@ -114,15 +120,52 @@ impl Turn {
     }
 }
 
+/// A gate line across the track at which speed is measured, like the
+/// official speed trap or the finish line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeedTrap {
+    /// Trap identifier (e.g., "ST", "FL")
+    pub name: String,
+    /// One end of the gate, as (X, Z)
+    pub start: Vec2,
+    /// The other end of the gate, as (X, Z)
+    pub end: Vec2,
+}
+
+impl SpeedTrap {
+    /// Where the path from `a` to `b` crosses the gate, as a fraction of the
+    /// way from `a` (ignoring Y).
+    pub fn crossing(&self, a: Vec3, b: Vec3) -> Option<f32> {
+        let (a, b) = (Vec2::new(a.x, a.z), Vec2::new(b.x, b.z));
+        let path = b - a;
+        let gate = self.end - self.start;
+        let denominator = path.perp_dot(gate);
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+        let offset = self.start - a;
+        let along_path = offset.perp_dot(gate) / denominator;
+        let along_gate = offset.perp_dot(path) / denominator;
+        ((0.0..1.0).contains(&along_path) && (0.0..=1.0).contains(&along_gate))
+            .then_some(along_path)
+    }
+}
+
 /// Collection of turns for a circuit.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CircuitTurns {
     pub circuit: String,
     pub turns: Vec<Turn>,
+    #[serde(default)]
+    pub speed_traps: Vec<SpeedTrap>,
 }
 
 impl CircuitTurns {
     pub fn get_turn(&self, name: &str) -> Option<&Turn> {
         self.turns.iter().find(|t| t.name == name)
     }
+
+    pub fn get_speed_trap(&self, name: &str) -> Option<&SpeedTrap> {
+        self.speed_traps.iter().find(|t| t.name == name)
+    }
 }
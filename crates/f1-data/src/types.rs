@@ -8,18 +8,24 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelemetrySample {
     /// Position in world coordinates (meters)
+    #[serde(deserialize_with = "crate::de::position")]
     pub position: Vec3,
     /// Session time when this sample was recorded
     pub time: f64,
     /// Throttle application (0-100%)
+    #[serde(default)]
     pub throttle: u8,
     /// Brake application (0 or 100 in F1 data)
+    #[serde(default)]
     pub brake: u8,
     /// Current gear (0 = neutral, 1-8 = forward gears)
+    #[serde(default)]
     pub gear: u8,
     /// Speed in km/h
+    #[serde(default)]
     pub speed: u16,
     /// DRS status
+    #[serde(default)]
     pub drs: DrsStatus,
 }
 
@@ -40,10 +46,13 @@ pub struct Lap {
     /// Lap time in seconds (None if incomplete)
     pub lap_time: Option<f64>,
     /// Sector times [S1, S2, S3] in seconds
+    #[serde(default)]
     pub sector_times: [Option<f64>; 3],
     /// All telemetry samples for this lap
+    #[serde(default)]
     pub samples: Vec<TelemetrySample>,
     /// Whether this lap is valid (no track limits, etc.)
+    #[serde(default = "crate::de::default_true")]
     pub is_valid: bool,
 }
 
@@ -53,12 +62,16 @@ pub struct Driver {
     /// Three-letter driver abbreviation (e.g., "VER", "HAM")
     pub code: String,
     /// Full driver name
+    #[serde(default)]
     pub name: String,
     /// Car number
+    #[serde(default)]
     pub number: u32,
     /// Team name
+    #[serde(default)]
     pub team: String,
     /// Team color as hex (e.g., "#FF0000")
+    #[serde(default)]
     pub team_color: String,
 }
 
@@ -66,9 +79,27 @@ pub struct Driver {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DriverSession {
     pub driver: Driver,
+    #[serde(default)]
     pub laps: Vec<Lap>,
 }
 
+impl DriverSession {
+    /// Get a lap by its number.
+    pub fn get_lap(&self, number: u32) -> Option<&Lap> {
+        self.laps.iter().find(|l| l.number == number)
+    }
+
+    /// Fastest valid, timed lap that has telemetry.
+    pub fn fastest_lap(&self) -> Option<&Lap> {
+        self.laps
+            .iter()
+            .filter(|l| l.is_valid && !l.samples.is_empty())
+            .filter_map(|l| l.lap_time.map(|t| (l, t)))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(lap, _)| lap)
+    }
+}
+
 /// Session type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SessionType {
@@ -96,26 +127,68 @@ impl SessionType {
 /// Complete session data for a race weekend event.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
+    /// Schema version the data conforms to (0 for documents that predate
+    /// versioning; the loader migrates everything to the current version)
+    #[serde(default)]
+    pub schema_version: u32,
     /// Year of the season
     pub year: u32,
     /// Circuit name
     pub circuit: String,
     /// Circuit short name / location
+    #[serde(default)]
     pub circuit_short: String,
     /// Type of session
     pub session_type: SessionType,
-    /// Session date/time
+    /// Session date/time (naive dates in the JSON are taken as UTC)
+    #[serde(deserialize_with = "crate::de::datetime_utc")]
     pub date: DateTime<Utc>,
     /// All driver sessions
+    #[serde(default)]
     pub drivers: Vec<DriverSession>,
 }
 
 impl Session {
+    /// Display name, e.g. "2025 United States Grand Prix Q".
+    pub fn label(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.year,
+            self.circuit,
+            self.session_type.as_str()
+        )
+    }
+
     /// Get a driver's session data by their code.
     pub fn get_driver(&self, code: &str) -> Option<&DriverSession> {
         self.drivers.iter().find(|d| d.driver.code == code)
     }
 
+    /// Each driver's fastest lap, sorted fastest first.
+    pub fn fastest_laps(&self) -> Vec<(&Driver, &Lap)> {
+        let mut laps: Vec<(&Driver, &Lap)> = self
+            .drivers
+            .iter()
+            .filter_map(|d| d.fastest_lap().map(|lap| (&d.driver, lap)))
+            .collect();
+        laps.sort_by(|a, b| {
+            let time_a = a.1.lap_time.unwrap_or(f64::MAX);
+            let time_b = b.1.lap_time.unwrap_or(f64::MAX);
+            time_a.partial_cmp(&time_b).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        laps
+    }
+
+    /// Earliest and latest sample times across all drivers.
+    pub fn time_range(&self) -> Option<(f64, f64)> {
+        self.drivers
+            .iter()
+            .flat_map(|d| &d.laps)
+            .flat_map(|l| l.samples.first().zip(l.samples.last()))
+            .map(|(first, last)| (first.time, last.time))
+            .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)))
+    }
+
     /// Get all driver codes in this session.
     pub fn driver_codes(&self) -> Vec<&str> {
         self.drivers.iter().map(|d| d.driver.code.as_str()).collect()
@@ -0,0 +1,494 @@
+//! Session validation and data-quality reporting.
+//!
+//! Exported telemetry is not always clean: positions teleport, timestamps
+//! repeat or run backwards, single samples spike in speed, and some laps have
+//! no samples at all. `validate_session` reports every problem per driver and
+//! lap; `clean_session` then either repairs what can be repaired or drops the
+//! affected laps so analysis only sees trustworthy data.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::trace::planar_distance;
+use crate::{Lap, Session, TelemetrySample};
+
+/// Thresholds used to flag suspicious samples.
+#[derive(Debug, Clone, Copy)]
+pub struct ValidationConfig {
+    /// Longest allowed gap between consecutive samples (seconds)
+    pub max_gap: f64,
+    /// Highest speed implied by consecutive positions before it counts as a
+    /// teleport (km/h)
+    pub max_implied_speed: f32,
+    /// A sample whose speed differs from both neighbours by more than this,
+    /// in the same direction, is a spike (km/h)
+    pub max_speed_spike: u16,
+    /// Highest valid gear
+    pub max_gear: u8,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            max_gap: 1.0,
+            max_implied_speed: 400.0,
+            max_speed_spike: 50,
+            max_gear: 8,
+        }
+    }
+}
+
+/// A single data-quality problem.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IssueKind {
+    /// The lap has no telemetry samples
+    EmptyLap,
+    /// Sample time is earlier than the previous sample's
+    NonMonotonicTime { backwards: f64 },
+    /// Sample has the same time as the previous sample
+    DuplicateTimestamp,
+    /// Time since the previous sample exceeds `max_gap`
+    TimeGap { seconds: f64 },
+    /// Distance from the previous sample implies an impossible speed
+    PositionJump { implied_speed: f32 },
+    /// Speed differs sharply from both neighbours
+    SpeedSpike { speed: u16, neighbours: (u16, u16) },
+    /// Throttle above 100%
+    ThrottleOutOfRange { throttle: u8 },
+    /// Gear above `max_gear`
+    GearOutOfRange { gear: u8 },
+}
+
+impl IssueKind {
+    /// Whether `clean_session` can fix this issue in `Repair` mode.
+    pub fn is_repairable(&self) -> bool {
+        !matches!(self, Self::EmptyLap | Self::TimeGap { .. })
+    }
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::EmptyLap => write!(f, "lap has no samples"),
+            Self::NonMonotonicTime { backwards } => {
+                write!(f, "time runs backwards by {backwards:.3} s")
+            }
+            Self::DuplicateTimestamp => write!(f, "duplicate timestamp"),
+            Self::TimeGap { seconds } => write!(f, "{seconds:.2} s gap between samples"),
+            Self::PositionJump { implied_speed } => {
+                write!(f, "position jump implying {implied_speed:.0} km/h")
+            }
+            Self::SpeedSpike {
+                speed,
+                neighbours: (before, after),
+            } => write!(
+                f,
+                "speed spike to {speed} km/h (neighbours {before}/{after})"
+            ),
+            Self::ThrottleOutOfRange { throttle } => write!(f, "throttle {throttle}% out of range"),
+            Self::GearOutOfRange { gear } => write!(f, "gear {gear} out of range"),
+        }
+    }
+}
+
+/// An issue and where in the lap it occurred.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Issue {
+    /// Index into the lap's samples, `None` for lap-level issues
+    pub sample_index: Option<usize>,
+    pub kind: IssueKind,
+}
+
+/// Issues found in one lap.
+#[derive(Debug, Clone, Serialize)]
+pub struct LapReport {
+    pub lap_number: u32,
+    pub sample_count: usize,
+    pub issues: Vec<Issue>,
+}
+
+impl LapReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Whether every issue in the lap can be repaired.
+    pub fn is_repairable(&self) -> bool {
+        self.issues.iter().all(|i| i.kind.is_repairable())
+    }
+}
+
+/// Laps with issues for one driver.
+#[derive(Debug, Clone, Serialize)]
+pub struct DriverReport {
+    pub driver_code: String,
+    /// Only laps that have at least one issue
+    pub laps: Vec<LapReport>,
+}
+
+/// Data-quality report for a whole session.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    /// Number of laps checked
+    pub laps_checked: usize,
+    /// Only drivers that have at least one lap with issues
+    pub drivers: Vec<DriverReport>,
+}
+
+impl ValidationReport {
+    pub fn is_clean(&self) -> bool {
+        self.drivers.is_empty()
+    }
+
+    /// Total number of issues across all drivers and laps.
+    pub fn issue_count(&self) -> usize {
+        self.laps().map(|(_, lap)| lap.issues.len()).sum()
+    }
+
+    /// All laps with issues, paired with their driver code.
+    pub fn laps(&self) -> impl Iterator<Item = (&str, &LapReport)> {
+        self.drivers
+            .iter()
+            .flat_map(|d| d.laps.iter().map(move |l| (d.driver_code.as_str(), l)))
+    }
+
+    /// The report for a specific lap, if it has issues.
+    pub fn lap(&self, driver_code: &str, lap_number: u32) -> Option<&LapReport> {
+        self.laps()
+            .find(|(code, lap)| *code == driver_code && lap.lap_number == lap_number)
+            .map(|(_, lap)| lap)
+    }
+}
+
+/// What `clean_session` does with laps that have issues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CleanupMode {
+    /// Fix repairable issues; drop only laps with no samples
+    #[default]
+    Repair,
+    /// Drop every lap with any issue
+    Exclude,
+}
+
+/// Outcome of `clean_session`.
+#[derive(Debug, Clone)]
+pub struct Cleanup {
+    /// Report on the session before cleaning
+    pub report: ValidationReport,
+    /// Laps modified in place
+    pub repaired: usize,
+    /// Laps removed from the session
+    pub excluded: usize,
+}
+
+/// Check every lap of every driver.
+pub fn validate_session(session: &Session, config: &ValidationConfig) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    for driver in &session.drivers {
+        report.laps_checked += driver.laps.len();
+        let laps: Vec<LapReport> = driver
+            .laps
+            .iter()
+            .map(|lap| validate_lap(lap, config))
+            .filter(|r| !r.is_clean())
+            .collect();
+        if !laps.is_empty() {
+            report.drivers.push(DriverReport {
+                driver_code: driver.driver.code.clone(),
+                laps,
+            });
+        }
+    }
+    report
+}
+
+/// Check a single lap.
+pub fn validate_lap(lap: &Lap, config: &ValidationConfig) -> LapReport {
+    let samples = &lap.samples;
+    let mut issues = Vec::new();
+    let mut push = |index: usize, kind: IssueKind| {
+        issues.push(Issue {
+            sample_index: Some(index),
+            kind,
+        })
+    };
+
+    for (i, sample) in samples.iter().enumerate() {
+        if sample.throttle > 100 {
+            push(
+                i,
+                IssueKind::ThrottleOutOfRange {
+                    throttle: sample.throttle,
+                },
+            );
+        }
+        if sample.gear > config.max_gear {
+            push(i, IssueKind::GearOutOfRange { gear: sample.gear });
+        }
+
+        let Some(prev) = i.checked_sub(1).map(|p| &samples[p]) else {
+            continue;
+        };
+        let dt = sample.time - prev.time;
+        if dt < 0.0 {
+            push(i, IssueKind::NonMonotonicTime { backwards: -dt });
+        } else if dt == 0.0 {
+            push(i, IssueKind::DuplicateTimestamp);
+        } else {
+            if dt > config.max_gap {
+                push(i, IssueKind::TimeGap { seconds: dt });
+            }
+            let implied_speed = implied_speed(prev, sample);
+            if implied_speed > config.max_implied_speed {
+                push(i, IssueKind::PositionJump { implied_speed });
+            }
+        }
+
+        if let Some(next) = samples.get(i + 1) {
+            if is_speed_spike(prev, sample, next, config) {
+                push(
+                    i,
+                    IssueKind::SpeedSpike {
+                        speed: sample.speed,
+                        neighbours: (prev.speed, next.speed),
+                    },
+                );
+            }
+        }
+    }
+
+    if samples.is_empty() {
+        issues.push(Issue {
+            sample_index: None,
+            kind: IssueKind::EmptyLap,
+        });
+    }
+
+    LapReport {
+        lap_number: lap.number,
+        sample_count: samples.len(),
+        issues,
+    }
+}
+
+/// Validate the session, then repair or exclude laps with issues.
+pub fn clean_session(
+    session: &mut Session,
+    config: &ValidationConfig,
+    mode: CleanupMode,
+) -> Cleanup {
+    let report = validate_session(session, config);
+    let mut repaired = 0;
+    let mut excluded = 0;
+
+    for driver in &mut session.drivers {
+        let code = driver.driver.code.clone();
+        driver.laps.retain_mut(|lap| {
+            if report.lap(&code, lap.number).is_none() {
+                return true;
+            }
+            let keep = match mode {
+                CleanupMode::Repair => !lap.samples.is_empty(),
+                CleanupMode::Exclude => false,
+            };
+            if keep {
+                repair_lap(lap, config);
+                repaired += 1;
+            } else {
+                excluded += 1;
+            }
+            keep
+        });
+    }
+
+    Cleanup {
+        report,
+        repaired,
+        excluded,
+    }
+}
+
+/// Fix repairable issues in place.
+///
+/// Samples are sorted by time and deduplicated, isolated position outliers
+/// are dropped, single-sample speed spikes are replaced by the mean of their
+/// neighbours, and out-of-range throttle/gear values are clamped or carried
+/// over from the previous sample. Time gaps are left alone.
+pub fn repair_lap(lap: &mut Lap, config: &ValidationConfig) {
+    let samples = &mut lap.samples;
+    samples.sort_by(|a, b| a.time.total_cmp(&b.time));
+    samples.dedup_by(|b, a| a.time == b.time);
+
+    // Drop a sample only if skipping it makes the track continuous again;
+    // otherwise the jump is real (e.g. a gap in coverage) and is kept.
+    let mut i = 1;
+    while i < samples.len() {
+        let jumps = implied_speed(&samples[i - 1], &samples[i]) > config.max_implied_speed;
+        let bridged = samples
+            .get(i + 1)
+            .is_none_or(|next| implied_speed(&samples[i - 1], next) <= config.max_implied_speed);
+        if jumps && bridged {
+            samples.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    for i in 1..samples.len().saturating_sub(1) {
+        if is_speed_spike(&samples[i - 1], &samples[i], &samples[i + 1], config) {
+            let mean = (samples[i - 1].speed as u32 + samples[i + 1].speed as u32) / 2;
+            samples[i].speed = mean as u16;
+        }
+    }
+
+    let mut last_gear = 0;
+    for sample in samples.iter_mut() {
+        sample.throttle = sample.throttle.min(100);
+        if sample.gear > config.max_gear {
+            sample.gear = last_gear;
+        }
+        last_gear = sample.gear;
+    }
+}
+
+/// Speed implied by moving between two samples (km/h); 0 if time doesn't advance.
+fn implied_speed(a: &TelemetrySample, b: &TelemetrySample) -> f32 {
+    let dt = (b.time - a.time) as f32;
+    if dt <= 0.0 {
+        return 0.0;
+    }
+    planar_distance(a, b) / dt * 3.6
+}
+
+fn is_speed_spike(
+    prev: &TelemetrySample,
+    sample: &TelemetrySample,
+    next: &TelemetrySample,
+    config: &ValidationConfig,
+) -> bool {
+    let threshold = config.max_speed_spike as i32;
+    let before = sample.speed as i32 - prev.speed as i32;
+    let after = sample.speed as i32 - next.speed as i32;
+    before.abs() > threshold && after.abs() > threshold && before.signum() == after.signum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Driver, DriverSession, DrsStatus, SessionType};
+    use glam::Vec3;
+
+    /// 50 m/s (180 km/h) along X, one sample per 0.25 s.
+    fn sample(i: usize) -> TelemetrySample {
+        TelemetrySample {
+            position: Vec3::new(i as f32 * 12.5, 0.0, 0.0),
+            time: i as f64 * 0.25,
+            throttle: 100,
+            brake: 0,
+            gear: 7,
+            speed: 180,
+            drs: DrsStatus::Off,
+        }
+    }
+
+    fn session(laps: Vec<Lap>) -> Session {
+        Session {
+            schema_version: crate::loader::CURRENT_SCHEMA_VERSION,
+            year: 2025,
+            circuit: "United States Grand Prix".into(),
+            circuit_short: "Austin".into(),
+            session_type: SessionType::Race,
+            date: chrono::Utc::now(),
+            drivers: vec![DriverSession {
+                driver: Driver {
+                    code: "VER".into(),
+                    name: String::new(),
+                    number: 1,
+                    team: String::new(),
+                    team_color: String::new(),
+                },
+                laps,
+            }],
+        }
+    }
+
+    fn lap(number: u32, samples: Vec<TelemetrySample>) -> Lap {
+        Lap {
+            number,
+            lap_time: None,
+            sector_times: [None; 3],
+            samples,
+            is_valid: true,
+        }
+    }
+
+    /// Lap 1 is clean, lap 2 has every repairable problem, lap 3 is empty.
+    fn dirty_session() -> Session {
+        let mut samples: Vec<TelemetrySample> = (0..10).map(sample).collect();
+        samples[2].position.x += 500.0; // teleport
+        samples[4].speed = 300; // spike
+        samples[5].gear = 15;
+        samples[6].throttle = 104;
+        samples.swap(7, 8); // time runs backwards
+        samples.insert(3, samples[3].clone()); // duplicate
+        session(vec![
+            lap(1, (0..10).map(sample).collect()),
+            lap(2, samples),
+            lap(3, Vec::new()),
+        ])
+    }
+
+    #[test]
+    fn test_report_flags_each_issue() {
+        let config = ValidationConfig::default();
+        let report = validate_session(&dirty_session(), &config);
+        assert_eq!(report.laps_checked, 3);
+        assert!(report.lap("VER", 1).is_none());
+
+        let kinds: Vec<_> = report
+            .lap("VER", 2)
+            .unwrap()
+            .issues
+            .iter()
+            .map(|i| std::mem::discriminant(&i.kind))
+            .collect();
+        for expected in [
+            IssueKind::PositionJump { implied_speed: 0.0 },
+            IssueKind::DuplicateTimestamp,
+            IssueKind::SpeedSpike {
+                speed: 0,
+                neighbours: (0, 0),
+            },
+            IssueKind::GearOutOfRange { gear: 0 },
+            IssueKind::ThrottleOutOfRange { throttle: 0 },
+            IssueKind::NonMonotonicTime { backwards: 0.0 },
+        ] {
+            assert!(
+                kinds.contains(&std::mem::discriminant(&expected)),
+                "{expected}"
+            );
+        }
+        assert_eq!(
+            report.lap("VER", 3).unwrap().issues[0].kind,
+            IssueKind::EmptyLap
+        );
+    }
+
+    #[test]
+    fn test_repair_and_exclude() {
+        let config = ValidationConfig::default();
+
+        let mut repaired = dirty_session();
+        let cleanup = clean_session(&mut repaired, &config, CleanupMode::Repair);
+        assert_eq!((cleanup.repaired, cleanup.excluded), (1, 1));
+        assert!(validate_session(&repaired, &config).is_clean());
+        assert_eq!(repaired.drivers[0].laps.len(), 2);
+
+        let mut excluded = dirty_session();
+        let cleanup = clean_session(&mut excluded, &config, CleanupMode::Exclude);
+        assert_eq!((cleanup.repaired, cleanup.excluded), (0, 2));
+        assert_eq!(excluded.drivers[0].laps.len(), 1);
+    }
+}
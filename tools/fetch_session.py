@@ -11,11 +11,13 @@ Dependencies managed via pyproject.toml (fastf1, pandas).
 
 import argparse
 import json
-from datetime import datetime
 from pathlib import Path
 
 import fastf1
 
+# Bump together with CURRENT_SCHEMA_VERSION in crates/f1-data/src/loader/migrate.rs
+SCHEMA_VERSION = 1
+
 
 def fetch_session(year: int, circuit: str, session_type: str, output_dir: Path):
     """Fetch session data and export to JSON."""
@@ -65,7 +67,7 @@ def fetch_session(year: int, circuit: str, session_type: str, output_dir: Path):
                     "position": [float(t["X"]), float(t.get("Z", 0)), float(t["Y"])],  # Swap Y/Z for Bevy coords
                     "time": float(t["SessionTime"].total_seconds()) if hasattr(t["SessionTime"], "total_seconds") else float(t["SessionTime"]),
                     "throttle": int(t["Throttle"]),
-                    "brake": int(t["Brake"]),
+                    "brake": 100 if t["Brake"] else 0,
                     "gear": int(t["nGear"]),
                     "speed": int(t["Speed"]),
                     "drs": "Active" if t.get("DRS", 0) > 10 else "Off",
@@ -101,6 +103,7 @@ def fetch_session(year: int, circuit: str, session_type: str, output_dir: Path):
 
     # Build session output
     session_data = {
+        "schema_version": SCHEMA_VERSION,
         "year": year,
         "circuit": session.event["EventName"],
         "circuit_short": session.event["Location"],
@@ -112,7 +115,8 @@ def fetch_session(year: int, circuit: str, session_type: str, output_dir: Path):
             "Sprint": "Sprint",
             "R": "Race",
         }.get(session_key, "Race"),
-        "date": session.date.isoformat() if session.date else datetime.now().isoformat(),
+        # FastF1 session dates are naive UTC; write them with an explicit offset
+        "date": (session.date.tz_localize("UTC") if session.date is not None else pd.Timestamp.now(tz="UTC")).isoformat(),
         "drivers": drivers_data,
     }
 